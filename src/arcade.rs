@@ -0,0 +1,185 @@
+//! The arcade cabinet of day 13.
+//!
+//! The game outputs `(x, y, tile)` triples to draw on the screen, with `(-1, 0, score)` updating
+//! the score display instead, and reads the joystick position whenever it wants input.
+
+use std::collections::HashMap;
+
+use enum_primitive_derive::Primitive;
+use failure_derive::Fail;
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, One, ToPrimitive, Zero};
+
+use crate::int_code_big::{Computer, ComputerExecutionError, ComputerState};
+
+/// Writing 2 here lets the game be played without inserting quarters.
+pub const FREE_PLAY_ADDRESS: i64 = 0;
+pub const FREE_PLAY_VALUE: i64 = 2;
+
+#[derive(Primitive, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Empty = 0,
+    Wall = 1,
+    Block = 2,
+    Paddle = 3,
+    Ball = 4,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Joystick {
+    Left = -1,
+    Neutral = 0,
+    Right = 1,
+}
+
+#[derive(Debug, Fail)]
+pub enum ArcadeError {
+    #[fail(display = "Game failed")]
+    ExecutionError(#[cause] ComputerExecutionError),
+    #[fail(display = "Invalid tile {} at {},{}", tile, x, y)]
+    InvalidTile { x: BigInt, y: BigInt, tile: BigInt },
+}
+
+impl From<ComputerExecutionError> for ArcadeError {
+    fn from(err: ComputerExecutionError) -> Self {
+        ArcadeError::ExecutionError(err)
+    }
+}
+
+/// Decides where to hold the joystick each time the game asks.
+pub trait Controller {
+    fn joystick(&mut self, screen: &Screen) -> Joystick;
+}
+
+impl<F: FnMut(&Screen) -> Joystick> Controller for F {
+    fn joystick(&mut self, screen: &Screen) -> Joystick {
+        self(screen)
+    }
+}
+
+/// Keeps the paddle underneath the ball.
+pub struct AutoPlayer;
+
+impl Controller for AutoPlayer {
+    fn joystick(&mut self, screen: &Screen) -> Joystick {
+        match (screen.find(Tile::Ball), screen.find(Tile::Paddle)) {
+            (Some((ball, _)), Some((paddle, _))) if ball < paddle => Joystick::Left,
+            (Some((ball, _)), Some((paddle, _))) if ball > paddle => Joystick::Right,
+            _ => Joystick::Neutral,
+        }
+    }
+}
+
+/// Holds the joystick in the middle, for games that never read it.
+pub struct Neutral;
+
+impl Controller for Neutral {
+    fn joystick(&mut self, _screen: &Screen) -> Joystick {
+        Joystick::Neutral
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Screen {
+    tiles: HashMap<(i64, i64), Tile>,
+    score: BigInt,
+}
+
+impl Screen {
+    pub fn tile(&self, position: (i64, i64)) -> Tile {
+        self.tiles.get(&position).cloned().unwrap_or(Tile::Empty)
+    }
+
+    pub fn score(&self) -> &BigInt {
+        &self.score
+    }
+
+    pub fn count(&self, tile: Tile) -> usize {
+        self.tiles.values().filter(|&&other| other == tile).count()
+    }
+
+    /// The position of some tile of the given kind, which is only meaningful for the ball and
+    /// paddle as there is one of each.
+    pub fn find(&self, tile: Tile) -> Option<(i64, i64)> {
+        self.tiles.iter()
+            .find(|(_, &other)| other == tile)
+            .map(|(&position, _)| position)
+    }
+
+    /// Draws the screen with the score underneath.
+    pub fn render(&self) -> String {
+        let max_x = self.tiles.keys().map(|position| position.0).max().unwrap_or(-1);
+        let max_y = self.tiles.keys().map(|position| position.1).max().unwrap_or(-1);
+        let mut screen = String::new();
+        for y in 0..=max_y {
+            for x in 0..=max_x {
+                screen.push(match self.tile((x, y)) {
+                    Tile::Empty => ' ',
+                    Tile::Wall => '#',
+                    Tile::Block => '=',
+                    Tile::Paddle => '-',
+                    Tile::Ball => 'o',
+                });
+            }
+            screen.push('\n');
+        }
+        screen.push_str(&format!("Score: {}\n", self.score));
+        screen
+    }
+
+    fn draw(&mut self, x: &BigInt, y: &BigInt, value: &BigInt) -> Result<(), ArcadeError> {
+        if *x == -BigInt::one() && y.is_zero() {
+            self.score = value.clone();
+            return Ok(());
+        }
+        let invalid = || ArcadeError::InvalidTile { x: x.clone(), y: y.clone(), tile: value.clone() };
+        let position = (x.to_i64().ok_or_else(invalid)?, y.to_i64().ok_or_else(invalid)?);
+        let tile = value.to_u8().and_then(Tile::from_u8).ok_or_else(invalid)?;
+        self.tiles.insert(position, tile);
+        Ok(())
+    }
+}
+
+pub struct Arcade<T> {
+    game: Computer,
+    screen: Screen,
+    controller: T,
+}
+
+impl<T: Controller> Arcade<T> {
+    pub fn new(game: Computer, controller: T) -> Self {
+        Arcade { game, screen: Screen::default(), controller }
+    }
+
+    /// Sets the game to free play, which must be done before it starts.
+    pub fn free_play(&mut self) {
+        self.game.patch(BigInt::from(FREE_PLAY_ADDRESS), BigInt::from(FREE_PLAY_VALUE));
+    }
+
+    pub fn screen(&self) -> &Screen {
+        &self.screen
+    }
+
+    /// Runs the game until it halts, asking the controller for the joystick position whenever
+    /// the game reads it.
+    pub fn run(&mut self) -> Result<(), ArcadeError> {
+        let mut outputs = Vec::new();
+        loop {
+            match self.game.state() {
+                ComputerState::Running => self.game.step()?,
+                ComputerState::WaitingForInput => {
+                    let joystick = self.controller.joystick(&self.screen);
+                    self.game.input(BigInt::from(joystick as i8))?;
+                }
+                ComputerState::WaitingToOutput(_) => {
+                    outputs.push(self.game.output()?);
+                    if let [x, y, value] = outputs.as_slice() {
+                        self.screen.draw(x, y, value)?;
+                        outputs.clear();
+                    }
+                }
+                ComputerState::Halted => return Ok(()),
+            }
+        }
+    }
+}
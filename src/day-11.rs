@@ -0,0 +1,37 @@
+use std::env;
+
+use failure::{format_err, Error};
+
+use crate::hull_robot::{Colour, Robot};
+use crate::int_code_big::Computer;
+
+pub mod hull_robot;
+pub mod int_code_big;
+
+/// Usage: day-11 [--image <file>]
+///
+/// With `--image`, the registration identifier painted in part 2 is also written as a PPM image.
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let image = match args.as_slice() {
+        [] => None,
+        [flag, file] if flag == "--image" => Some(file),
+        _ => return Err(format_err!("Usage: day-11 [--image <file>]")),
+    };
+    let brain = Computer::new("input/day-11.txt")?;
+
+    let mut robot = Robot::new(brain.clone(), Colour::Black);
+    robot.run()?;
+    println!("part 1");
+    println!("{}", robot.painted_count());
+
+    let mut robot = Robot::new(brain, Colour::White);
+    robot.run()?;
+    println!("part 2");
+    print!("{}", robot.render());
+    if let Some(file) = image {
+        robot.write_image(file, 8)?;
+    }
+
+    Ok(())
+}
@@ -1,90 +1,47 @@
-use std::fs;
-
-use num_traits::real::Real;
-use regex::Regex;
-
-#[derive(Debug)]
-struct Moon {
-    x: i32,
-    y: i32,
-    z: i32,
-    vx: i32,
-    vy: i32,
-    vz: i32,
-}
-
-impl Moon {
-    fn new(x: i32, y: i32, z: i32) -> Moon {
-        Moon {
-            x,
-            y,
-            z,
-            vx: 0,
-            vy: 0,
-            vz: 0,
-        }
-    }
-}
-
-fn main() {
-    let text = fs::read_to_string("input/day-12.txt").unwrap();
-    let re = Regex::new(r"<x=(-?\d+), y=(-?\d+), z=(-?\d+)>").unwrap();
-
-    let mut moons: Vec<Moon> = re.captures_iter(&text).map(|capture| {
-        Moon::new(
-            capture[1].parse::<i32>().unwrap(),
-            capture[2].parse::<i32>().unwrap(),
-            capture[3].parse::<i32>().unwrap(),
-        )
-    }).collect();
-
-    for _ in 0..1000 {
-//        print!("{:?}\n", moons);
-        step(&mut moons)
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+
+use failure::{format_err, Error};
+
+use crate::n_body::{Format, Simulation};
+
+pub mod n_body;
+
+const STEPS: u64 = 1000;
+
+/// Usage: day-12-1 [<moons file>] [--csv <file> | --jsonl <file>]
+///
+/// Simulates the moons in `input/day-12.txt` unless another file is given, and optionally writes
+/// their positions and velocities at every step to a file for plotting.
+fn main() -> Result<(), Error> {
+    let mut input = "input/day-12.txt".to_string();
+    let mut export = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let format = match arg.as_str() {
+            "--csv" => Format::Csv,
+            "--jsonl" => Format::Jsonl,
+            _ if !arg.starts_with("--") => {
+                input = arg;
+                continue;
+            }
+            _ => return Err(format_err!("Usage: day-12-1 [<moons file>] [--csv <file> | --jsonl <file>]")),
+        };
+        let file = args.next().ok_or_else(|| format_err!("Missing file for {}", arg))?;
+        export = Some((format, file));
     }
-//    print!("{:?}\n", moons);
-
-    print!("{}", energy(&moons))
-}
 
-fn energy(moons: &Vec<Moon>) -> i32 {
-    let mut total = 0;
-    for moon in moons {
-        let potential = moon.x.abs() +
-            moon.y.abs() +
-            moon.z.abs();
-        let kinetic = moon.vx.abs() +
-            moon.vy.abs() +
-            moon.vz.abs();
-        total += potential * kinetic;
-    }
-    total
-}
+    let mut moons: Simulation<3> = Simulation::new(n_body::parse_moons(&fs::read_to_string(input)?)?);
 
-fn step(moons: &mut Vec<Moon>) {
-    for i in 0..moons.len() {
-        for j in 0..moons.len() {
-            if moons[i].x < moons[j].x {
-                moons[i].vx += 1;
-            } else if moons[i].x > moons[j].x {
-                moons[i].vx -= 1;
-            }
-            if moons[i].y < moons[j].y {
-                moons[i].vy += 1;
-            } else if moons[i].y > moons[j].y {
-                moons[i].vy -= 1;
-            }
-            if moons[i].z < moons[j].z {
-                moons[i].vz += 1;
-            } else if moons[i].z > moons[j].z {
-                moons[i].vz -= 1;
-            }
+    match export {
+        Some((format, file)) => {
+            let mut out = BufWriter::new(File::create(file)?);
+            moons.export(&mut out, format, STEPS)?;
         }
+        None => moons.run(STEPS),
     }
 
-    for moon in moons {
-        moon.x += moon.vx;
-        moon.y += moon.vy;
-        moon.z += moon.vz;
-    }
+    print!("{}", moons.total_energy());
+    Ok(())
 }
@@ -1 +1,14 @@
-fn main() {}
\ No newline at end of file
+use std::fs;
+
+use failure::Error;
+
+use crate::n_body::Simulation;
+
+pub mod n_body;
+
+fn main() -> Result<(), Error> {
+    let text = fs::read_to_string("input/day-12.txt")?;
+    let moons: Simulation<3> = Simulation::new(n_body::parse_moons(&text)?);
+    print!("{}", moons.period_by_axis());
+    Ok(())
+}
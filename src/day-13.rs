@@ -0,0 +1,41 @@
+use std::env;
+
+use failure::{format_err, Error};
+
+use crate::arcade::{Arcade, AutoPlayer, Neutral, Tile};
+use crate::int_code_big::Computer;
+
+pub mod arcade;
+pub mod int_code_big;
+
+/// Usage: day-13 [--show]
+///
+/// With `--show`, the screen is printed at the end of each game.
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let show = match args.as_slice() {
+        [] => false,
+        [flag] if flag == "--show" => true,
+        _ => return Err(format_err!("Usage: day-13 [--show]")),
+    };
+    let game = Computer::new("input/day-13.txt")?;
+
+    let mut arcade = Arcade::new(game.clone(), Neutral);
+    arcade.run()?;
+    if show {
+        print!("{}", arcade.screen().render());
+    }
+    println!("part 1");
+    println!("{}", arcade.screen().count(Tile::Block));
+
+    let mut arcade = Arcade::new(game, AutoPlayer);
+    arcade.free_play();
+    arcade.run()?;
+    if show {
+        print!("{}", arcade.screen().render());
+    }
+    println!("part 2");
+    println!("{}", arcade.screen().score());
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use failure::{format_err, Error};
+
+use crate::int_code_big::Computer;
+
+pub mod int_code_big;
+pub mod repair_droid;
+
+fn main() -> Result<(), Error> {
+    let droid = Computer::new("input/day-15.txt")?;
+    let map = repair_droid::explore(droid)?;
+    print!("{}", map.render());
+
+    println!("part 1");
+    println!("{}", map.oxygen_distance().ok_or_else(|| format_err!("No oxygen system found"))?);
+
+    println!("part 2");
+    println!("{}", map.fill_time().ok_or_else(|| format_err!("No oxygen system found"))?);
+
+    Ok(())
+}
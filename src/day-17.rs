@@ -0,0 +1,24 @@
+use failure::Error;
+
+use crate::int_code_big::Computer;
+use crate::scaffold::{Routines, ScaffoldError, View};
+
+pub mod int_code_big;
+pub mod scaffold;
+
+fn main() -> Result<(), Error> {
+    let program = Computer::new("input/day-17.txt")?;
+    let view = View::parse(&scaffold::read_camera(program.clone())?);
+
+    println!("part 1");
+    println!("{}", view.alignment_sum());
+
+    let routines = Routines::compress(&view.path()?).ok_or(ScaffoldError::NoRoutines)?;
+    for line in routines.lines() {
+        println!("{}", line);
+    }
+    println!("part 2");
+    println!("{}", scaffold::wake(program, &routines, false)?);
+
+    Ok(())
+}
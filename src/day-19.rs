@@ -0,0 +1,25 @@
+use failure::{format_err, Error};
+
+use crate::int_code_big::Computer;
+use crate::tractor_beam::Beam;
+
+pub mod int_code_big;
+pub mod tractor_beam;
+
+const SHIP_SIZE: i64 = 100;
+const MAX_Y: i64 = 10_000;
+
+fn main() -> Result<(), Error> {
+    let mut beam = Beam::new(Computer::new("input/day-19.txt")?);
+
+    println!("part 1");
+    println!("{}", beam.count(50, 50)?);
+
+    let (x, y) = beam.fit_square(SHIP_SIZE, MAX_Y)?
+        .ok_or_else(|| format_err!("No room for the ship within {} rows", MAX_Y))?;
+    println!("part 2");
+    println!("{}", x * 10_000 + y);
+    println!("{} drones deployed", beam.queries());
+
+    Ok(())
+}
@@ -1,15 +1,11 @@
 use std::error::Error;
-use std::fs;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let program = fs::read_to_string("input/day-2.txt")?;
-//    print!("{:?}", program.split(",").collect::<Vec<_>>());
-    let commands: Result<Vec<usize>, _> = program
-        .split(",")
-        .map(|op| op.trim().parse::<usize>())
-        .collect();
+use crate::int_code::{ComputerState, IO};
+
+pub mod int_code;
 
-    let memory = commands?;
+fn main() -> Result<(), Box<dyn Error>> {
+    let memory = int_code::load_memory("input/day-2.txt")?;
 
     let result = execute_program(&memory, 12, 2);
 
@@ -18,30 +14,23 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-pub fn execute_program(memory: &Vec<usize>, noun: usize, verb: usize) -> usize {
-    let mut memory = memory.clone();
-
-    memory[1] = noun;
-    memory[2] = verb;
-    let mut pc: usize = 0;
-    loop {
-        match memory[pc] {
-            1 => {
-                let ret_loc = memory[pc + 3];
-                memory[ret_loc] = memory[memory[pc + 1]] + memory[memory[pc + 2]];
-                pc += 4;
-            }
-            2 => {
-                let ret_loc = memory[pc + 3];
-                memory[ret_loc] = memory[memory[pc + 1]] * memory[memory[pc + 2]];
-                pc += 4;
-            }
-            99 => {
-                break
-            }
-            _ => panic!("invalid opcode")
-        }
+pub fn execute_program(memory: &[i32], noun: i32, verb: i32) -> i32 {
+    let mut state = ComputerState::new(memory);
+    state.patch(1, noun);
+    state.patch(2, verb);
+    int_code::execute_computer(&mut state, &mut NoIO);
+
+    state.memory()[0]
+}
+
+struct NoIO;
+
+impl IO for NoIO {
+    fn get_input(&mut self) -> i32 {
+        panic!("Day 2 programs take no input")
     }
 
-    return memory[0]
-}
\ No newline at end of file
+    fn output(&mut self, _output: i32) {
+        panic!("Day 2 programs produce no output")
+    }
+}
@@ -1,53 +1,66 @@
 use std::error::Error;
-use std::fs;
+
+use num_bigint::BigInt;
+
+use crate::int_code::{ComputerState, IO};
+
+pub mod int_code;
+pub mod int_code_symbolic;
+
+const TARGET: i32 = 19690720;
+/// Nouns and verbs are each between 0 and 99 inclusive. The original search stopped at 98, which
+/// would have missed answers using 99.
+const MAX_INPUT: i32 = 99;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let program = fs::read_to_string("input/day-2.txt")?;
-//    print!("{:?}", program.split(",").collect::<Vec<_>>());
-    let commands: Result<Vec<usize>, _> = program
-        .split(",")
-        .map(|op| op.trim().parse::<usize>())
-        .collect();
-
-    let memory = commands?;
-
-    for noun in 0..99 {
-        for verb in 0..99 {
-            let result = execute_program(&memory, noun, verb);
-            if result == 19690720 {
-                print!("{}", 100 * noun + verb)
+    let memory = int_code::load_memory("input/day-2.txt")?;
+
+    let program: Vec<BigInt> = memory.iter().map(|word| BigInt::from(*word)).collect();
+    match int_code_symbolic::evaluate_cell(&program, &[1, 2], 0) {
+        Ok(polynomial) => {
+            println!("memory[0] = {}", polynomial.to_string_with(&["noun", "verb"]));
+            for solution in int_code_symbolic::solve(&polynomial, &BigInt::from(TARGET), &[0..=MAX_INPUT as i64, 0..=MAX_INPUT as i64]) {
+                print!("{}", 100 * solution[0] + solution[1])
             }
         }
+        Err(err) => {
+            println!("Falling back to searching every noun and verb: {}", err);
+            search(&memory)
+        }
     }
 
     Ok(())
 }
 
-
-pub fn execute_program(memory: &Vec<usize>, noun: usize, verb: usize) -> usize {
-    let mut memory = memory.clone();
-
-    memory[1] = noun;
-    memory[2] = verb;
-    let mut pc: usize = 0;
-    loop {
-        match memory[pc] {
-            1 => {
-                let ret_loc = memory[pc + 3];
-                memory[ret_loc] = memory[memory[pc + 1]] + memory[memory[pc + 2]];
-                pc += 4;
-            }
-            2 => {
-                let ret_loc = memory[pc + 3];
-                memory[ret_loc] = memory[memory[pc + 1]] * memory[memory[pc + 2]];
-                pc += 4;
-            }
-            99 => {
-                break
+fn search(memory: &[i32]) {
+    for noun in 0..=MAX_INPUT {
+        for verb in 0..=MAX_INPUT {
+            let result = execute_program(memory, noun, verb);
+            if result == TARGET {
+                print!("{}", 100 * noun + verb)
             }
-            _ => panic!("invalid opcode")
         }
     }
+}
+
+
+pub fn execute_program(memory: &[i32], noun: i32, verb: i32) -> i32 {
+    let mut state = ComputerState::new(memory);
+    state.patch(1, noun);
+    state.patch(2, verb);
+    int_code::execute_computer(&mut state, &mut NoIO);
+
+    state.memory()[0]
+}
 
-    return memory[0]
-}
\ No newline at end of file
+struct NoIO;
+
+impl IO for NoIO {
+    fn get_input(&mut self) -> i32 {
+        panic!("Day 2 programs take no input")
+    }
+
+    fn output(&mut self, _output: i32) {
+        panic!("Day 2 programs produce no output")
+    }
+}
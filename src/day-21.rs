@@ -0,0 +1,41 @@
+use std::env;
+use std::fs;
+
+use failure::{format_err, Error};
+
+use crate::int_code_big::Computer;
+use crate::springdroid::{Mode, Outcome, Script};
+
+pub mod int_code_big;
+pub mod springdroid;
+
+/// Usage: day-21 [<script>]
+///
+/// Searches for scripts to walk and run across the hull, or with a script file, runs just that.
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let droid = Computer::new("input/day-21.txt")?;
+
+    match args.as_slice() {
+        [] => {}
+        [file] => {
+            let script = Script::parse(&fs::read_to_string(file)?)?;
+            match springdroid::run(droid, &script)? {
+                Outcome::Damage(damage) => println!("{}", damage),
+                Outcome::Fell(animation) => print!("{}", animation),
+            }
+            return Ok(());
+        }
+        _ => return Err(format_err!("Usage: day-21 [<script>]")),
+    }
+
+    for (part, mode) in [(1, Mode::Walk), (2, Mode::Run)].iter() {
+        let (script, damage) = springdroid::search(&droid, *mode)?
+            .ok_or_else(|| format_err!("No script found to {}", mode))?;
+        print!("{}", script);
+        println!("part {}", part);
+        println!("{}", damage);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use failure::Error;
+
+use crate::int_code_big::Computer;
+use crate::text_adventure::Adventure;
+
+pub mod int_code_big;
+pub mod text_adventure;
+
+fn main() -> Result<(), Error> {
+    let mut adventure = Adventure::new(Computer::new("input/day-25.txt")?);
+    let solution = adventure.solve()?;
+
+    let mut rooms: Vec<&String> = adventure.rooms().keys().collect();
+    rooms.sort();
+    println!("Explored {} rooms: {}", rooms.len(), rooms.iter().map(|room| room.as_str()).collect::<Vec<_>>().join(", "));
+    let mut dangerous: Vec<&String> = adventure.dangerous().iter().collect();
+    dangerous.sort();
+    println!("Avoided: {}", dangerous.iter().map(|item| item.as_str()).collect::<Vec<_>>().join(", "));
+    println!("Carrying: {}", solution.items.join(", "));
+
+    println!("part 1");
+    match solution.password {
+        Some(password) => println!("{}", password),
+        None => print!("{}", solution.message),
+    }
+
+    Ok(())
+}
@@ -1,20 +1,38 @@
+use std::env;
 use std::error::Error;
 
-mod int_code;
+use failure::Fail;
 
+pub mod int_code;
+pub mod int_code_patch;
+
+/// Usage: day-5 [--patch <@file or address=value,...>]
+///
+/// With `--patch`, memory is patched before running as described in `int_code_patch`.
 fn main() -> Result<(), Box<dyn Error>> {
-    let memory = int_code::load_memory("input/day-5.txt")?;
+    let mut state = int_code::ComputerState::new(int_code::load_memory("input/day-5.txt")?);
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => {}
+        [flag, patches] if flag == "--patch" => {
+            let patches = int_code_patch::load_patches(patches).map_err(|err| err.compat())?;
+            for (address, value) in int_code_patch::to_small(&patches).map_err(|err| err.compat())? {
+                state.patch(address, value);
+            }
+        }
+        _ => return Err("Usage: day-5 [--patch <@file or address=value,...>]".into()),
+    }
 
     println!("part 1");
-    run(memory.clone(), 1);
+    run(state.clone(), 1);
     println!("part 2");
-    run(memory, 5);
+    run(state, 5);
 
     Ok(())
 }
 
-fn run(memory: Vec<i32>, input: i32) {
-    let mut state = int_code::ComputerState::new(memory);
+fn run(mut state: int_code::ComputerState, input: i32) {
     let mut tester = TEST { input };
 
     int_code::execute_computer(&mut state, &mut tester as &mut dyn int_code::IO);
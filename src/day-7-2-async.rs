@@ -0,0 +1,60 @@
+use failure::{format_err, Error};
+use itertools::Itertools;
+use num_bigint::BigInt;
+
+use crate::int_code_async::{channel, AsyncInput, AsyncOutput, Executor, Receiver};
+use crate::int_code_big::Computer;
+
+pub mod int_code_async;
+pub mod int_code_big;
+
+/// Day 7 part 2 with every amplifier of every phase setting permutation running at once as
+/// futures on a single thread.
+fn main() -> Result<(), Error> {
+    let computer = Computer::new("input/day-7.txt")?;
+    let permutations: Vec<Vec<i32>> = (5..=9).permutations(5).collect();
+
+    // Amplifier i of a permutation reads from channel i and writes to the next channel, with
+    // the last one feeding back into the first. The first receiver is kept here by reference
+    // so that the final signal can be read from it once everything has halted.
+    let mut first_receivers: Vec<Receiver> = Vec::new();
+    let mut amplifiers = Vec::new();
+    for settings in &permutations {
+        let (senders, mut receivers): (Vec<_>, Vec<_>) = settings.iter().map(|_| channel()).unzip();
+        for (sender, setting) in senders.iter().zip(settings) {
+            sender.send(BigInt::from(*setting));
+        }
+        senders[0].send(BigInt::from(0));
+
+        let mut outputs = senders;
+        outputs.rotate_left(1);
+        first_receivers.push(receivers.remove(0));
+        amplifiers.push((receivers, outputs));
+    }
+
+    let mut executor = Executor::new();
+    for (first_receiver, (receivers, outputs)) in first_receivers.iter_mut().zip(amplifiers) {
+        let mut outputs = outputs.into_iter();
+        executor.spawn(run(computer.clone(), first_receiver, outputs.next().unwrap()));
+        for (receiver, output) in receivers.into_iter().zip(outputs) {
+            executor.spawn(run(computer.clone(), receiver, output));
+        }
+    }
+    let stuck = executor.run();
+    drop(executor);
+    if stuck > 0 {
+        return Err(format_err!("{} amplifiers never halted", stuck));
+    }
+
+    let best_signal = first_receivers.iter_mut()
+        .filter_map(|receiver| receiver.try_recv())
+        .max()
+        .ok_or_else(|| format_err!("No amplifier produced a signal"))?;
+    println!("{}", best_signal);
+
+    Ok(())
+}
+
+async fn run<I: AsyncInput + Unpin, O: AsyncOutput + Unpin>(computer: Computer, input: I, output: O) {
+    int_code_async::run_async(computer, input, output).await.unwrap();
+}
@@ -1,17 +1,34 @@
-use std::cmp::max;
+use std::env;
 use std::error::Error;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::thread;
 
 use crate::int_code::{ComputerState, IO};
+use crate::int_code_record::{Recorder, RecordingIO};
 
 pub mod int_code;
-
+pub mod int_code_big;
+pub mod int_code_record;
+
+/// Usage: day-7-2 [--record <directory>]
+///
+/// With `--record`, the winning settings are run again with the IO of every amplifier logged to
+/// `<settings>-<amplifier>.log` in the directory so that the run can be replayed with
+/// `int-code-replay`.
 fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let record = match args.as_slice() {
+        [] => None,
+        [flag, directory] if flag == "--record" => Some(PathBuf::from(directory)),
+        _ => return Err("Usage: day-7-2 [--record <directory>]".into()),
+    };
+
     let memory = int_code::load_memory("input/day-7.txt")?;
     let state = ComputerState::new(memory);
 
     let mut best_signal = 0;
+    let mut best_settings = [5, 6, 7, 8, 9];
 
     for a in 5..=9 {
         for b in 5..=9 {
@@ -24,19 +41,27 @@ fn main() -> Result<(), Box<dyn Error>> {
                         if e == a || e == b || e == c || e == d { continue; }
                         let settings = [a, b, c, d, e];
 
-                        best_signal = max(best_signal, run_amplifiers(&settings, &state))
+                        let signal = run_amplifiers(&settings, &state, None)?;
+                        if signal > best_signal {
+                            best_signal = signal;
+                            best_settings = settings;
+                        }
                     }
                 }
             }
         }
     }
 
+    if let Some(directory) = record {
+        run_amplifiers(&best_settings, &state, Some(&directory))?;
+    }
+
     println!("{}", best_signal);
 
     Ok(())
 }
 
-fn run_amplifiers(settings: &[i32], program: &ComputerState) -> i32 {
+fn run_amplifiers(settings: &[i32], program: &ComputerState, record: Option<&Path>) -> Result<i32, Box<dyn Error>> {
     let channels: (Vec<Sender<i32>>, Vec<Receiver<i32>>) = settings.iter()
         .map(|_| channel())
         .unzip();
@@ -64,12 +89,29 @@ fn run_amplifiers(settings: &[i32], program: &ComputerState) -> i32 {
     let (send, receive) = channel::<i32>();
     senders[0].send(0).unwrap();
 
-    for tester in testers {
+    for (i, tester) in testers.into_iter().enumerate() {
         let mut mem = program.clone();
         let send = send.clone();
+        let recorder = match record {
+            Some(directory) => {
+                let name: String = settings.iter().map(|setting| setting.to_string()).collect();
+                Some(Recorder::create(directory.join(format!("{}-{}.log", name, i)))?)
+            }
+            None => None,
+        };
         thread::spawn(move || {
-            let mut tester = tester;
-            int_code::execute_computer(&mut mem, &mut tester as &mut dyn IO);
+            let tester = match recorder {
+                Some(recorder) => {
+                    let mut io = RecordingIO::new(tester, recorder);
+                    int_code::execute_computer(&mut mem, &mut io as &mut dyn IO);
+                    io.into_inner()
+                }
+                None => {
+                    let mut tester = tester;
+                    int_code::execute_computer(&mut mem, &mut tester as &mut dyn IO);
+                    tester
+                }
+            };
             if tester.is_last {
                 send.send(tester.last_output).unwrap();
             }
@@ -77,7 +119,7 @@ fn run_amplifiers(settings: &[i32], program: &ComputerState) -> i32 {
     }
 
     let result = receive.recv().unwrap();
-    result
+    Ok(result)
 }
 
 struct AmplifierTester {
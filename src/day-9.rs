@@ -1,15 +1,46 @@
+use std::env;
 use std::sync::mpsc::channel;
 
-use failure::Error;
+use failure::{format_err, Error};
 use num_bigint::ToBigInt;
 
+use crate::int_code_big::ChannelIO;
+use crate::int_code_record::{Recorder, RecordingIO};
+
+pub mod int_code;
 pub mod int_code_big;
+pub mod int_code_patch;
+pub mod int_code_record;
+
+const USAGE: &str = "Usage: day-9 [--record <log>] [--patch <@file or address=value,...>]";
 
+/// Usage: day-9 [--record <log>] [--patch <@file or address=value,...>]
+///
+/// With `--record`, the IO of the run is logged so that it can be replayed with `int-code-replay`.
+/// With `--patch`, memory is patched before running as described in `int_code_patch`.
 fn main() -> Result<(), Error> {
-    let computer = int_code_big::Computer::new("input/day-9.txt")?;
+    let mut computer = int_code_big::Computer::new("input/day-9.txt")?;
+    let mut record = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| format_err!("{}", USAGE))?;
+        match arg.as_str() {
+            "--record" => record = Some(Recorder::create(value)?),
+            "--patch" => {
+                for patch in int_code_patch::load_patches(&value)? {
+                    computer.patch(patch.address, patch.value);
+                }
+            }
+            _ => return Err(format_err!("{}", USAGE)),
+        }
+    }
+
     let (in_send, in_recv) = channel();
     let (out_send, out_recv) = channel();
-    computer.run_threaded_channels(in_recv, out_send);
+    match record {
+        Some(recorder) => computer.run_threaded(RecordingIO::new(ChannelIO::new(in_recv, out_send), recorder)),
+        None => computer.run_threaded_channels(in_recv, out_send),
+    }
 
     in_send.send(2.to_bigint().unwrap())?;
     for out in out_recv.iter() {
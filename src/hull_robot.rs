@@ -0,0 +1,154 @@
+//! The emergency hull painting robot of day 11.
+//!
+//! The robot's Intcode brain reads the colour of the panel under the robot, then outputs the
+//! colour to paint it followed by the direction to turn before moving forward one panel.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use failure_derive::Fail;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::int_code_big::{Computer, ComputerExecutionError, ComputerState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colour {
+    Black = 0,
+    White = 1,
+}
+
+#[derive(Debug, Fail)]
+pub enum RobotError {
+    #[fail(display = "Brain failed")]
+    ExecutionError(#[cause] ComputerExecutionError),
+    #[fail(display = "Invalid colour {}", value)]
+    InvalidColour { value: BigInt },
+    #[fail(display = "Invalid turn {}", value)]
+    InvalidTurn { value: BigInt },
+}
+
+impl From<ComputerExecutionError> for RobotError {
+    fn from(err: ComputerExecutionError) -> Self {
+        RobotError::ExecutionError(err)
+    }
+}
+
+/// Positions have x increasing to the right and y increasing downwards, with the robot starting
+/// at the origin facing up.
+pub struct Robot {
+    brain: Computer,
+    position: (i64, i64),
+    direction: (i64, i64),
+    panels: HashMap<(i64, i64), Colour>,
+    painted: HashSet<(i64, i64)>,
+}
+
+impl Robot {
+    /// Creates a robot standing on a panel of the `start` colour, with every other panel black.
+    pub fn new(brain: Computer, start: Colour) -> Self {
+        let mut panels = HashMap::new();
+        panels.insert((0, 0), start);
+        Robot { brain, position: (0, 0), direction: (0, -1), panels, painted: HashSet::new() }
+    }
+
+    /// Runs the brain until it halts.
+    pub fn run(&mut self) -> Result<(), RobotError> {
+        let mut outputs = Vec::new();
+        loop {
+            match self.brain.state() {
+                ComputerState::Running => self.brain.step()?,
+                ComputerState::WaitingForInput => self.brain.input(BigInt::from(self.colour(self.position) as u8))?,
+                ComputerState::WaitingToOutput(_) => {
+                    outputs.push(self.brain.output()?);
+                    if let [paint, turn] = outputs.as_slice() {
+                        self.paint(paint)?;
+                        self.turn(turn)?;
+                        outputs.clear();
+                    }
+                }
+                ComputerState::Halted => return Ok(()),
+            }
+        }
+    }
+
+    pub fn colour(&self, panel: (i64, i64)) -> Colour {
+        self.panels.get(&panel).cloned().unwrap_or(Colour::Black)
+    }
+
+    /// The number of panels painted at least once, whatever colour they ended up.
+    pub fn painted_count(&self) -> usize {
+        self.painted.len()
+    }
+
+    fn paint(&mut self, colour: &BigInt) -> Result<(), RobotError> {
+        let colour = match colour.to_u8() {
+            Some(0) => Colour::Black,
+            Some(1) => Colour::White,
+            _ => return Err(RobotError::InvalidColour { value: colour.clone() }),
+        };
+        self.panels.insert(self.position, colour);
+        self.painted.insert(self.position);
+        Ok(())
+    }
+
+    fn turn(&mut self, turn: &BigInt) -> Result<(), RobotError> {
+        let (dx, dy) = self.direction;
+        self.direction = match turn.to_u8() {
+            Some(0) => (dy, -dx),
+            Some(1) => (-dy, dx),
+            _ => return Err(RobotError::InvalidTurn { value: turn.clone() }),
+        };
+        self.position = (self.position.0 + self.direction.0, self.position.1 + self.direction.1);
+        Ok(())
+    }
+
+    /// The smallest rectangle holding every white panel, as its minimum and maximum corners.
+    fn bounds(&self) -> Option<((i64, i64), (i64, i64))> {
+        let white: Vec<&(i64, i64)> = self.panels.iter()
+            .filter(|(_, colour)| **colour == Colour::White)
+            .map(|(panel, _)| panel)
+            .collect();
+        let min_x = white.iter().map(|panel| panel.0).min()?;
+        let max_x = white.iter().map(|panel| panel.0).max()?;
+        let min_y = white.iter().map(|panel| panel.1).min()?;
+        let max_y = white.iter().map(|panel| panel.1).max()?;
+        Some(((min_x, min_y), (max_x, max_y)))
+    }
+
+    /// Draws the white panels as `#` and black panels as `.`.
+    pub fn render(&self) -> String {
+        let ((min_x, min_y), (max_x, max_y)) = match self.bounds() {
+            Some(bounds) => bounds,
+            None => return String::new(),
+        };
+        let mut hull = String::new();
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                hull.push(if self.colour((x, y)) == Colour::White { '#' } else { '.' });
+            }
+            hull.push('\n');
+        }
+        hull
+    }
+
+    /// Writes the hull as a binary PPM image with each panel drawn as a `scale` pixel square.
+    pub fn write_image<T: AsRef<Path>>(&self, file: T, scale: usize) -> io::Result<()> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds().unwrap_or(((0, 0), (0, 0)));
+        let width = (max_x - min_x + 1) as usize * scale;
+        let height = (max_y - min_y + 1) as usize * scale;
+
+        let mut image = BufWriter::new(File::create(file)?);
+        write!(image, "P6\n{} {}\n255\n", width, height)?;
+        for row in 0..height {
+            for column in 0..width {
+                let panel = (min_x + (column / scale) as i64, min_y + (row / scale) as i64);
+                let pixel = if self.colour(panel) == Colour::White { 255 } else { 0 };
+                image.write_all(&[pixel, pixel, pixel])?;
+            }
+        }
+        image.flush()
+    }
+}
@@ -0,0 +1,62 @@
+use std::env;
+use std::process;
+
+use failure::{format_err, Error};
+use num_bigint::BigInt;
+
+use crate::int_code_concolic::{Explorer, Goal};
+
+pub mod int_code_big;
+pub mod int_code_concolic;
+pub mod int_code_symbolic;
+
+/// Searches for program inputs that reach an address or produce an output.
+///
+/// Usage: int-code-concolic <program> (--reach ADDRESS | --output VALUE [--index N] | --all-outputs VALUE)
+///        [--runs N] [--steps N]
+///
+/// `--index` asks for the output with that index, counting from zero, to be the value rather
+/// than any output.
+fn main() -> Result<(), Error> {
+    let mut args = env::args().skip(1);
+    let program = args.next().ok_or_else(|| format_err!("Missing program file"))?;
+    let mut explorer = Explorer::new(int_code_big::Computer::new(&program)?);
+    let mut goal = None;
+    let mut index = None;
+
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| format_err!("Missing value for {}", arg))?;
+        match arg.as_str() {
+            "--reach" => goal = Some(Goal::ReachAddress(value.parse::<BigInt>()?)),
+            "--output" => goal = Some(Goal::Output(value.parse::<BigInt>()?)),
+            "--index" => index = Some(value.parse::<usize>()?),
+            "--all-outputs" => goal = Some(Goal::AllOutputs(value.parse::<BigInt>()?)),
+            "--runs" => explorer.max_runs = value.parse()?,
+            "--steps" => explorer.max_steps = value.parse()?,
+            _ => return Err(format_err!("Unknown argument {}", arg)),
+        }
+    }
+    let goal = match (goal, index) {
+        (None, _) => return Err(format_err!("Missing --reach, --output or --all-outputs")),
+        (Some(Goal::Output(target)), Some(index)) => Goal::OutputAt(index, target),
+        (Some(_), Some(_)) => return Err(format_err!("--index only applies to --output")),
+        (Some(goal), None) => goal,
+    };
+
+    match explorer.find(&goal) {
+        Some(found) => {
+            println!("Found after {} runs", found.runs);
+            println!("inputs:  {}", join(&found.inputs));
+            println!("outputs: {}", join(&found.outputs));
+        }
+        None => {
+            println!("No inputs found within {} runs", explorer.max_runs);
+            process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+fn join(values: &[BigInt]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(",")
+}
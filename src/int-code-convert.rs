@@ -0,0 +1,42 @@
+use std::env;
+use std::fs;
+
+use failure::{format_err, Error};
+use num_bigint::BigInt;
+
+pub mod int_code_binary;
+
+/// Converts an Intcode program between comma separated text and the binary format. The direction
+/// is picked from the input, and the binary word width defaults to the smallest that fits. Memory
+/// images are converted to text with their gaps filled with zeros.
+///
+/// Usage: int-code-convert <input> <output> [--width 0|32|64]
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (input, output) = match args.as_slice() {
+        [input, output] | [input, output, _, _] => (input, output),
+        _ => return Err(format_err!("Usage: int-code-convert <input> <output> [--width 0|32|64]")),
+    };
+    let width = match args.get(2..) {
+        Some([flag, width]) if flag == "--width" => match width.as_str() {
+            "0" | "32" | "64" => Some(width.parse::<u8>()?),
+            _ => return Err(format_err!("Unsupported width {}, expected 0, 32 or 64", width)),
+        },
+        Some([]) => None,
+        _ => return Err(format_err!("Unknown arguments {:?}", &args[2..])),
+    };
+
+    let bytes = fs::read(input)?;
+    if int_code_binary::is_binary(&bytes) {
+        let (_, memory) = int_code_binary::decode_image(&bytes)?;
+        fs::write(output, int_code_binary::to_text(&int_code_binary::to_words(&memory)?))?;
+    } else {
+        let words = String::from_utf8(bytes)?
+            .split(',')
+            .map(|word| word.trim().parse::<BigInt>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let width = width.unwrap_or_else(|| int_code_binary::minimum_width(&words));
+        fs::write(output, int_code_binary::encode(&words, width)?)?;
+    }
+    Ok(())
+}
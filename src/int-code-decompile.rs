@@ -0,0 +1,16 @@
+use std::env;
+
+use failure::{format_err, Error};
+
+pub mod int_code_big;
+pub mod int_code_decompile;
+
+/// Prints an Intcode program as structured pseudocode.
+///
+/// Usage: int-code-decompile <program>
+fn main() -> Result<(), Error> {
+    let program = env::args().nth(1).ok_or_else(|| format_err!("Usage: int-code-decompile <program>"))?;
+    let memory = int_code_big::Computer::new(program)?.memory().clone();
+    print!("{}", int_code_decompile::decompile(&memory));
+    Ok(())
+}
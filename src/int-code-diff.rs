@@ -0,0 +1,63 @@
+use std::collections::VecDeque;
+use std::env;
+
+use failure::{format_err, Error};
+use num_bigint::BigInt;
+
+use crate::int_code_big::{Computer, ComputerState};
+
+pub mod int_code_big;
+pub mod int_code_diff;
+
+/// Runs a program once with each of two comma separated input lists and prints how the final
+/// computers differ. A run stops when it halts, runs out of input or takes too many steps.
+///
+/// Usage: int-code-diff <program> <inputs> <inputs> [--steps N]
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (program, first, second) = match args.as_slice() {
+        [program, first, second] | [program, first, second, _, _] => (program, first, second),
+        _ => return Err(format_err!("Usage: int-code-diff <program> <inputs> <inputs> [--steps N]")),
+    };
+    let max_steps = match args.get(3..) {
+        Some([flag, steps]) if flag == "--steps" => steps.parse()?,
+        Some([]) => 1_000_000,
+        _ => return Err(format_err!("Unknown arguments {:?}", &args[3..])),
+    };
+
+    let computer = Computer::new(program)?;
+    let (before, before_outputs) = run(computer.clone(), parse_inputs(first)?, max_steps)?;
+    let (after, after_outputs) = run(computer, parse_inputs(second)?, max_steps)?;
+
+    println!("outputs: {} -> {}", join(&before_outputs), join(&after_outputs));
+    print!("{}", int_code_diff::diff(&before, &after));
+    Ok(())
+}
+
+fn parse_inputs(inputs: &str) -> Result<VecDeque<BigInt>, Error> {
+    inputs
+        .split(',')
+        .filter(|input| !input.trim().is_empty())
+        .map(|input| Ok(input.trim().parse::<BigInt>()?))
+        .collect()
+}
+
+fn run(mut computer: Computer, mut inputs: VecDeque<BigInt>, max_steps: usize) -> Result<(Computer, Vec<BigInt>), Error> {
+    let mut outputs = Vec::new();
+    for _ in 0..max_steps {
+        match computer.state() {
+            ComputerState::Running => computer.step()?,
+            ComputerState::WaitingForInput => match inputs.pop_front() {
+                Some(input) => computer.input(input)?,
+                None => break,
+            },
+            ComputerState::WaitingToOutput(_) => outputs.push(computer.output()?),
+            ComputerState::Halted => break,
+        }
+    }
+    Ok((computer, outputs))
+}
+
+fn join(values: &[BigInt]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(",")
+}
@@ -0,0 +1,103 @@
+use std::env;
+use std::error::Error;
+use std::process;
+
+use num_bigint::BigInt;
+use num_traits::Zero;
+
+use crate::int_code_fuzz::{compare, generate_inputs, generate_program, minimize, run_big, run_small, Comparison, Signature, XorShift};
+
+pub mod int_code;
+pub mod int_code_big;
+pub mod int_code_fuzz;
+
+/// Generates random programs using opcodes 1-8 and runs them on both interpreters, reporting
+/// minimized programs where the two disagree.
+///
+/// Both interpreters read memory past the end of the program as zero and fail on negative
+/// addresses, negative instructions and immediate mode write parameters. Cases whose words grow
+/// past i32, or which modify themselves into using relative mode from day 9, are skipped as
+/// `int_code` doesn't support them, as are cases writing past address 65536. The BigInt run
+/// stops at the first word past i32, so a program that keeps squaring a word is skipped rather
+/// than run until the step limit.
+///
+/// Usage: int-code-fuzz [--seed N] [--cases N] [--steps N] [--reports N]
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut seed = 2019;
+    let mut cases = 10_000;
+    let mut steps = 1_000;
+    let mut max_reports = 5;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value: u64 = args.next().ok_or_else(|| format!("Missing value for {}", arg))?.parse()?;
+        match arg.as_str() {
+            "--seed" => seed = value,
+            "--cases" => cases = value,
+            "--steps" => steps = value as usize,
+            "--reports" => max_reports = value as usize,
+            _ => return Err(format!("Unknown argument {}", arg).into()),
+        }
+    }
+
+    let mut rng = XorShift::new(seed);
+    let mut signatures = Vec::new();
+    let mut skipped = 0;
+    let mut unsupported = 0;
+    for case in 0..cases {
+        let program = generate_program(&mut rng);
+        let inputs = generate_inputs(&mut rng, &program);
+
+        match compare(&program, &inputs, steps) {
+            Comparison::Agree => {}
+            Comparison::OutOfRange => skipped += 1,
+            Comparison::Unsupported => unsupported += 1,
+            Comparison::Diverge(signature) => {
+                if signatures.contains(&signature) {
+                    continue;
+                }
+                let (program, inputs) = minimize(program, inputs, steps, &signature);
+                report(case, &program, &inputs, steps, &signature);
+                signatures.push(signature);
+                if signatures.len() >= max_reports {
+                    break;
+                }
+            }
+        }
+    }
+
+    println!(
+        "{} cases, {} skipped for going out of range, {} skipped for using relative mode, {} divergences",
+        cases, skipped, unsupported, signatures.len()
+    );
+    if !signatures.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn report(case: u64, program: &[i32], inputs: &[i32], steps: usize, signature: &Signature) {
+    let join = |words: &[i32]| words.iter().map(|word| word.to_string()).collect::<Vec<_>>().join(",");
+    let small = run_small(program, inputs, steps);
+    let big = run_big(program, inputs, steps);
+
+    println!("Divergence in case {} ({:?})", case, signature);
+    println!("  program: {}", join(program));
+    println!("  inputs:  {}", join(inputs));
+    for (name, run) in &[("int_code", &small), ("int_code_big", &big)] {
+        let outputs: Vec<String> = run.outputs.iter().map(|output| output.to_string()).collect();
+        println!("  {}: {:?}, outputs [{}]", name, run.outcome, outputs.join(","));
+    }
+    if let Signature::Memory = signature {
+        let len = small.memory.len().max(big.memory.len());
+        for address in 0..len {
+            let zero = BigInt::zero();
+            let a = small.memory.get(address).unwrap_or(&zero);
+            let b = big.memory.get(address).unwrap_or(&zero);
+            if a != b {
+                println!("  memory[{}]: {} vs {}", address, a, b);
+            }
+        }
+    }
+    println!();
+}
@@ -0,0 +1,43 @@
+use std::env;
+use std::process;
+
+use failure::{format_err, Error};
+
+use crate::int_code_big::Computer;
+use crate::int_code_record::Replayer;
+
+pub mod int_code;
+pub mod int_code_big;
+pub mod int_code_record;
+
+/// Re-executes a program against the inputs of a log written with `--record`, reporting the first
+/// point where it reads, writes or outputs differently from the recorded run.
+///
+/// Usage: int-code-replay <program> <log> [--small]
+///
+/// `--small` replays with the `int_code` interpreter instead of `int_code_big`.
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (program, log, small) = match args.as_slice() {
+        [program, log] => (program, log, false),
+        [program, log, flag] if flag == "--small" => (program, log, true),
+        _ => return Err(format_err!("Usage: int-code-replay <program> <log> [--small]")),
+    };
+
+    let mut replayer = Replayer::new(int_code_record::read_log(log)?);
+    if small {
+        let memory = int_code::load_memory(program).map_err(|err| format_err!("{}", err))?;
+        replayer.replay_small(&mut int_code::ComputerState::new(memory));
+    } else {
+        replayer.replay_big(&mut Computer::new(program)?)?;
+    }
+
+    match replayer.divergence() {
+        Some(divergence) => {
+            println!("Diverged at event {}: {}", replayer.matched() + 1, divergence);
+            process::exit(1);
+        }
+        None => println!("Replayed {} events", replayer.events().len()),
+    }
+    Ok(())
+}
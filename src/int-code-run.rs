@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process;
+
+use failure::{format_err, Error};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::int_code_big::{Computer, ComputerExecutionError, ComputerState};
+
+pub mod int_code_big;
+pub mod int_code_patch;
+
+const USAGE: &str = "Usage: int-code-run <program> [--input <values>] [--input-file <file> | --stdin] \
+[--ascii] [--patch <@file or address=value,...>] [--max-steps <n>] [--trace]
+       int-code-run --help";
+
+const EXIT_HALTED: i32 = 0;
+const EXIT_USAGE: i32 = 1;
+const EXIT_LOAD_ERROR: i32 = 2;
+const EXIT_STEP_LIMIT: i32 = 3;
+const EXIT_INPUT_EXHAUSTED: i32 = 4;
+const EXIT_PROGRAM_ERROR: i32 = 5;
+
+/// Runs any Intcode program with the BigInt computer.
+///
+/// Inputs come from the comma separated `--input` values first, then from `--input-file` or
+/// `--stdin` a line at a time. With `--ascii`, lines of input are fed as character codes ending
+/// in a newline, and outputs are printed as characters, apart from those outside ASCII which are
+/// printed as numbers on their own line. Otherwise inputs are comma or whitespace separated
+/// numbers and each output is printed on its own line. `--trace` prints every instruction to
+/// stderr before it runs.
+///
+/// Exits with 0 when the program halts, 1 for bad arguments, 2 when the program, patches or input
+/// file can't be loaded or reading input or writing output fails, 3 when `--max-steps` is
+/// reached, 4 when the program wants more input than given and 5 when the program fails, such
+/// as on an invalid instruction. `--help` prints the usage and exits with 0.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        process::exit(EXIT_HALTED);
+    }
+    let options = match Options::parse(args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    let code = match run(&options) {
+        Ok(code) => code,
+        Err(Failure::Load(err)) => {
+            eprintln!("Error: {}", err);
+            EXIT_LOAD_ERROR
+        }
+        Err(Failure::Program(err)) => {
+            eprintln!("Program failed: {}", err);
+            EXIT_PROGRAM_ERROR
+        }
+    };
+    io::stdout().flush().unwrap_or(());
+    process::exit(code);
+}
+
+/// Where inputs come from once the `--input` values run out.
+enum InputSource {
+    File(String),
+    Stdin,
+}
+
+struct Options {
+    program: String,
+    inputs: VecDeque<BigInt>,
+    input_source: Option<InputSource>,
+    ascii: bool,
+    patches: Option<String>,
+    max_steps: Option<u64>,
+    trace: bool,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self, Error> {
+        let mut args = args.into_iter();
+        let program = args.next().ok_or_else(|| format_err!("Missing program file"))?;
+        let mut options = Options {
+            program,
+            inputs: VecDeque::new(),
+            input_source: None,
+            ascii: false,
+            patches: None,
+            max_steps: None,
+            trace: false,
+        };
+
+        let mut values = Vec::new();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format_err!("Missing value for {}", arg));
+            let source = match arg.as_str() {
+                "--input-file" => Some(InputSource::File(value()?)),
+                "--stdin" => Some(InputSource::Stdin),
+                _ => None,
+            };
+            if let Some(source) = source {
+                if options.input_source.is_some() {
+                    return Err(format_err!("Only one of --input-file and --stdin can be given"));
+                }
+                options.input_source = Some(source);
+                continue;
+            }
+
+            match arg.as_str() {
+                "--input" => values.push(value()?),
+                "--ascii" => options.ascii = true,
+                "--patch" => options.patches = Some(value()?),
+                "--max-steps" => options.max_steps = Some(value()?.parse()?),
+                "--trace" => options.trace = true,
+                _ => return Err(format_err!("Unknown argument {}", arg)),
+            }
+        }
+
+        for value in values {
+            options.inputs.extend(parse_numbers(&value)?);
+        }
+        Ok(options)
+    }
+}
+
+/// Why a run stopped early, which decides the exit code.
+enum Failure {
+    /// The program, patches or input couldn't be read, or output couldn't be written.
+    Load(Error),
+    /// The program itself failed.
+    Program(ComputerExecutionError),
+}
+
+impl From<Error> for Failure {
+    fn from(err: Error) -> Self {
+        Failure::Load(err)
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(err: io::Error) -> Self {
+        Failure::Load(err.into())
+    }
+}
+
+fn run(options: &Options) -> Result<i32, Failure> {
+    let mut computer = Computer::new(&options.program).map_err(Error::from)?;
+    let reader: Option<Box<dyn BufRead>> = match &options.input_source {
+        Some(InputSource::File(file)) => {
+            let file = File::open(file).map_err(|err| format_err!("Can't open input file {}: {}", file, err))?;
+            Some(Box::new(BufReader::new(file)))
+        }
+        Some(InputSource::Stdin) => Some(Box::new(BufReader::new(io::stdin()))),
+        None => None,
+    };
+    let mut inputs = Inputs { queue: options.inputs.clone(), reader, ascii: options.ascii };
+    if let Some(patches) = &options.patches {
+        for patch in int_code_patch::load_patches(patches).map_err(Error::from)? {
+            computer.patch(patch.address, patch.value);
+        }
+    }
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let mut steps = 0;
+    loop {
+        match computer.state() {
+            ComputerState::Running => {
+                if options.max_steps.map_or(false, |max_steps| steps >= max_steps) {
+                    eprintln!("Stopped after {} steps", steps);
+                    return Ok(EXIT_STEP_LIMIT);
+                }
+                if options.trace {
+                    trace(&computer);
+                }
+                computer.step().map_err(Failure::Program)?;
+                steps += 1;
+            }
+            ComputerState::WaitingForInput => match inputs.next()? {
+                Some(input) => computer.input(input).map_err(Failure::Program)?,
+                None => {
+                    eprintln!("Ran out of input at {}", computer.program_counter());
+                    return Ok(EXIT_INPUT_EXHAUSTED);
+                }
+            },
+            ComputerState::WaitingToOutput(_) => {
+                let output = computer.output().map_err(Failure::Program)?;
+                match output.to_u8().filter(|byte| options.ascii && byte.is_ascii()) {
+                    Some(byte) => write!(stdout, "{}", byte as char)?,
+                    None => writeln!(stdout, "{}", output)?,
+                }
+                stdout.flush()?;
+            }
+            ComputerState::Halted => return Ok(EXIT_HALTED),
+        }
+    }
+}
+
+/// Prints the instruction at the program counter as the step count, address, raw words and
+/// decoded op code.
+fn trace(computer: &Computer) {
+    let pc = computer.program_counter();
+    let (words, op_code) = match computer.op_code_and_modes() {
+        Ok((op_code, _)) => {
+            let words = (0..=op_code.num_args())
+                .map(|offset| computer.read(&(pc + BigInt::from(offset))).to_string())
+                .collect::<Vec<_>>();
+            (words.join(","), format!("{:?}", op_code))
+        }
+        Err(_) => (computer.read(pc).to_string(), "?".to_string()),
+    };
+    eprintln!("{:>8} {:>6}: {:<24} {}", computer.instructions() + 1, pc, words, op_code);
+}
+
+/// Inputs given on the command line followed by those read from a file or stdin as needed.
+struct Inputs {
+    queue: VecDeque<BigInt>,
+    reader: Option<Box<dyn BufRead>>,
+    ascii: bool,
+}
+
+impl Inputs {
+    fn next(&mut self) -> Result<Option<BigInt>, Error> {
+        while self.queue.is_empty() {
+            let reader = match &mut self.reader {
+                Some(reader) => reader,
+                None => return Ok(None),
+            };
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                self.reader = None;
+                return Ok(None);
+            }
+            if self.ascii {
+                let line = line.trim_end_matches(['\n', '\r']);
+                self.queue.extend(line.bytes().map(BigInt::from));
+                self.queue.push_back(BigInt::from(b'\n'));
+            } else {
+                self.queue.extend(parse_numbers(&line)?);
+            }
+        }
+        Ok(self.queue.pop_front())
+    }
+}
+
+fn parse_numbers(text: &str) -> Result<Vec<BigInt>, Error> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.parse::<BigInt>().map_err(|_| format_err!("Invalid input {}", word)))
+        .collect()
+}
@@ -1,47 +1,103 @@
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs;
+use std::num::ParseIntError;
 use std::path::Path;
 
+use failure::Fail;
+use num_traits::ToPrimitive;
+
 use crate::int_code::OpResult::{AdvanceProgramCounter, SuppressProgramCounter};
 
+#[path = "int_code_binary.rs"]
+pub mod binary;
+
+/// Loads a comma separated program, or a binary program or memory image in the `binary` format.
 pub fn load_memory<T: AsRef<Path>>(file: T) -> Result<Vec<i32>, Box<dyn Error>> {
-    let program = fs::read_to_string(file)?;
-    let commands: Result<Vec<i32>, _> = program
+    let bytes = fs::read(file)?;
+    if binary::is_binary(&bytes) {
+        let (_, memory) = binary::decode_image(&bytes).map_err(|err| err.compat())?;
+        let words = binary::to_words(&memory).map_err(|err| err.compat())?;
+        let memory: Option<Vec<i32>> = words.iter().map(|word| word.to_i32()).collect();
+        return Ok(memory.ok_or("Binary program has words too wide for 32 bits")?);
+    }
+    let program = String::from_utf8(bytes)?;
+    let memory = parse_memory(&program)?;
+    Ok(memory)
+}
+
+/// Parses a comma separated program.
+pub fn parse_memory(program: &str) -> Result<Vec<i32>, ParseIntError> {
+    program
         .split(",")
         .map(|op| op.trim().parse::<i32>())
-        .collect();
-    let memory = commands?;
-    Ok(memory)
+        .collect()
 }
 
 pub fn execute_computer(state: &mut ComputerState, io: &mut dyn IO) {
-    let opcodes: HashMap<i32, &dyn Op> = get_ops();
+    execute_computer_with_ops(state, io, &Ops::new())
+}
+
+/// Executes the computer with the instructions in `ops`, which can include ones registered
+/// alongside the built-in ones.
+pub fn execute_computer_with_ops(state: &mut ComputerState, io: &mut dyn IO, ops: &Ops) {
+    execute_computer_with_limit(state, io, ops, None);
+}
+
+/// Executes at most `max_steps` instructions, or until halted if there is no limit.
+/// Returns true if the computer halted.
+pub fn execute_computer_with_limit(state: &mut ComputerState, io: &mut dyn IO, ops: &Ops, max_steps: Option<usize>) -> bool {
+    let opcodes = &ops.ops;
 
+    let mut steps = 0;
     loop {
-        let op = state.memory[state.program_counter] % 100;
+        if let Some(max_steps) = max_steps {
+            if steps >= max_steps {
+                return false;
+            }
+        }
+        let op = state.read(state.program_counter) % 100;
 //        print!("{}\n", op);
         if !opcodes.contains_key(&op) {
-            println! {"Invalid op: {}", op}
-            println!("State: {:?}", state)
+            panic!("Invalid op: {}\nState: {:?}", op, state);
         }
+        check_op_modes(state.read(state.program_counter));
+        state.instructions += 1;
+        io.at_instruction(state.instructions);
         let should_stop = opcodes[&op].execute(state, io);
+        steps += 1;
 
         if should_stop {
-            break;
+            return true;
         }
     }
 }
 
+/// Panics on mode digits other than position, immediate and relative, including digits past the
+/// instruction's parameters, the same as `int_code_big::get_op_modes` rejects them.
+fn check_op_modes(instruction: i32) {
+    let mut modes = instruction / 100;
+    while modes > 0 {
+        if modes % 10 > 2 {
+            panic!("Invalid op mode {}", modes % 10);
+        }
+        modes /= 10;
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ComputerState {
     memory: Vec<i32>,
     program_counter: usize,
+    instructions: u64,
 }
 
 pub trait IO {
     fn get_input(&mut self) -> i32;
     fn output(&mut self, output: i32);
+    /// Called before each instruction with the number of instructions executed so far,
+    /// including the one about to run.
+    fn at_instruction(&mut self, _instructions: u64) {}
 }
 
 impl ComputerState {
@@ -49,8 +105,87 @@ impl ComputerState {
         return ComputerState {
             memory: memory.into(),
             program_counter: 0,
+            instructions: 0,
         };
     }
+
+    pub fn memory(&self) -> &[i32] {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut Vec<i32> {
+        &mut self.memory
+    }
+
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    pub fn set_program_counter(&mut self, program_counter: usize) {
+        self.program_counter = program_counter;
+    }
+
+    /// The number of instructions executed since the computer was created.
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /// Sets a word before running the program, growing memory with zeros if the address is past
+    /// the end of it.
+    pub fn patch(&mut self, address: usize, value: i32) {
+        self.write(address, value)
+    }
+
+    /// Reads a word, with addresses past the end of memory reading as zero like in `int_code_big`.
+    pub fn read(&self, address: usize) -> i32 {
+        self.memory.get(address).cloned().unwrap_or(0)
+    }
+
+    /// Writes a word, growing memory with zeros if the address is past the end of it.
+    pub fn write(&mut self, address: usize, value: i32) {
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+        }
+        self.memory[address] = value;
+    }
+}
+
+#[derive(Debug, Fail)]
+#[fail(display = "Op code {} is already in use", op)]
+pub struct OpCodeInUse {
+    pub op: i32,
+}
+
+/// The instructions a computer executes: the built-in ones and any added with `register_op`.
+/// This is the counterpart of `int_code_big::Computer::register_op`, which follows the same
+/// rules.
+#[derive(Clone)]
+pub struct Ops<'a> {
+    ops: HashMap<i32, &'a dyn Op>,
+}
+
+impl<'a> Ops<'a> {
+    /// Just the built-in instructions.
+    pub fn new() -> Self {
+        Ops { ops: get_ops() }
+    }
+
+    /// Adds an instruction. Built-in op codes can not be replaced; registering an extension op
+    /// code a second time replaces the earlier op.
+    pub fn register_op(&mut self, op: &'a dyn Op) -> Result<(), OpCodeInUse> {
+        let op_code = op.get_opcode();
+        if get_ops().contains_key(&op_code) {
+            return Err(OpCodeInUse { op: op_code });
+        }
+        self.ops.insert(op_code, op);
+        Ok(())
+    }
+}
+
+impl<'a> Default for Ops<'a> {
+    fn default() -> Self {
+        Ops::new()
+    }
 }
 
 fn get_ops() -> HashMap<i32, &'static dyn Op> {
@@ -83,17 +218,23 @@ const LESS_THAN_OP: LessThanOp = LessThanOp {};
 const EQUALS_OP: EqualsOp = EqualsOp {};
 
 
-trait Op {
+/// An instruction with full control over decoding its parameters and moving the program counter.
+/// `int_code_big::ExtensionOp` plays the same part for the BigInt interpreter.
+pub trait Op {
     fn get_opcode(&self) -> i32;
+    /// Returns true if the computer should stop.
     fn execute(&self, computer: &mut ComputerState, io: &mut dyn IO) -> bool;
 }
 
-enum OpResult {
+pub enum OpResult {
     AdvanceProgramCounter,
     SuppressProgramCounter,
 }
 
-trait ParameterizedOp {
+/// An instruction whose first `num_parameterized_params` parameters are resolved according to
+/// their position/immediate modes before `execute` is called. The remaining parameters are passed
+/// through as written, and as they are addresses to write to they must be in position mode.
+pub trait ParameterizedOp {
     fn num_params(&self) -> usize;
     fn num_parameterized_params(&self) -> usize;
     fn get_opcode(&self) -> i32;
@@ -106,25 +247,22 @@ impl<T: ParameterizedOp> Op for T {
     }
 
     fn execute(&self, computer: &mut ComputerState, io: &mut dyn IO) -> bool {
-        let arg_start = computer.program_counter + 1;
-        let arg_end = computer.program_counter + 1 + self.num_params();
-        let params = &computer.memory[arg_start..arg_end];
-
-        let mut i = 0;
-        let mut position_modes = computer.memory[computer.program_counter] / 100;
-        let actual_args: Vec<i32> = params.iter().map(|arg| {
-            let arg = *arg;
+        let mut position_modes = computer.read(computer.program_counter) / 100;
+        let actual_args: Vec<i32> = (0..self.num_params()).map(|i| {
+            let arg = computer.read(computer.program_counter + 1 + i);
+            let mode = position_modes % 10;
+            position_modes /= 10;
             if i >= self.num_parameterized_params() {
+                if mode != 0 {
+                    panic!("Invalid mode {} for a parameter written to", mode);
+                }
                 return arg;
             }
-            i += 1;
-            let mode = position_modes % 10;
-            position_modes /= 10;
 
             match mode {
-                0 => computer.memory[arg as usize],
+                0 => computer.read(address(arg)),
                 1 => arg,
-                _ => panic!("unexpected arg")
+                _ => panic!("Invalid mode {} for a parameter read from", mode)
             }
         }).collect();
 
@@ -139,6 +277,14 @@ impl<T: ParameterizedOp> Op for T {
     }
 }
 
+/// Converts a parameter to an address, panicking on negative addresses as they are invalid.
+pub fn address(value: i32) -> usize {
+    if value < 0 {
+        panic!("Negative address {}", value);
+    }
+    value as usize
+}
+
 struct PlusOp;
 
 impl ParameterizedOp for PlusOp {
@@ -156,8 +302,7 @@ impl ParameterizedOp for PlusOp {
 
     fn execute(&self, args: Vec<i32>, state: &mut ComputerState, _io: &mut dyn IO) -> OpResult {
 //        print!("{:?}\n", &args);
-        let memory = &mut state.memory;
-        memory[args[2] as usize] = args[0] + args[1];
+        state.write(address(args[2]), args[0] + args[1]);
         OpResult::AdvanceProgramCounter
     }
 }
@@ -179,8 +324,7 @@ impl ParameterizedOp for TimesOp {
     }
 
     fn execute(&self, args: Vec<i32>, state: &mut ComputerState, _io: &mut dyn IO) -> OpResult {
-        let memory = &mut state.memory;
-        memory[args[2] as usize] = args[0] * args[1];
+        state.write(address(args[2]), args[0] * args[1]);
         AdvanceProgramCounter
     }
 }
@@ -213,8 +357,8 @@ impl ParameterizedOp for InputOp {
     }
 
     fn execute(&self, args: Vec<i32>, state: &mut ComputerState, io: &mut dyn IO) -> OpResult {
-        let memory = &mut state.memory;
-        memory[args[0] as usize] = io.get_input();
+        let input = io.get_input();
+        state.write(address(args[0]), input);
         AdvanceProgramCounter
     }
 }
@@ -257,7 +401,7 @@ impl ParameterizedOp for JumpIfTrueOp {
 
     fn execute(&self, args: Vec<i32>, state: &mut ComputerState, _io: &mut dyn IO) -> OpResult {
         if args[0] != 0 {
-            state.program_counter = args[1] as usize;
+            state.program_counter = address(args[1]);
             SuppressProgramCounter
         } else {
             AdvanceProgramCounter
@@ -282,7 +426,7 @@ impl ParameterizedOp for JumpIfFalseOP {
 
     fn execute(&self, args: Vec<i32>, state: &mut ComputerState, _io: &mut dyn IO) -> OpResult {
         if args[0] == 0 {
-            state.program_counter = args[1] as usize;
+            state.program_counter = address(args[1]);
             SuppressProgramCounter
         } else {
             AdvanceProgramCounter
@@ -306,7 +450,7 @@ impl ParameterizedOp for LessThanOp {
     }
 
     fn execute(&self, args: Vec<i32>, state: &mut ComputerState, _io: &mut dyn IO) -> OpResult {
-        state.memory[args[2] as usize] = if args[0] < args[1] { 1 } else { 0 };
+        state.write(address(args[2]), if args[0] < args[1] { 1 } else { 0 });
         AdvanceProgramCounter
     }
 }
@@ -327,7 +471,7 @@ impl ParameterizedOp for EqualsOp {
     }
 
     fn execute(&self, args: Vec<i32>, state: &mut ComputerState, _io: &mut dyn IO) -> OpResult {
-        state.memory[args[2] as usize] = if args[0] == args[1] { 1 } else { 0 };
+        state.write(address(args[2]), if args[0] == args[1] { 1 } else { 0 });
         AdvanceProgramCounter
     }
 }
\ No newline at end of file
@@ -0,0 +1,223 @@
+//! Runs `int_code_big::Computer`s as futures, so that many machines connected by channels can
+//! share one thread instead of needing a thread each.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+use num_bigint::BigInt;
+
+use crate::int_code_big::{Computer, ComputerExecutionError, ComputerState};
+
+/// Instructions a computer runs before yielding to other tasks.
+const STEPS_PER_POLL: usize = 10_000;
+
+/// An asynchronous source of inputs.
+pub trait AsyncInput {
+    /// Returns the next input, or `None` if there will be no more.
+    fn poll_input(&mut self, cx: &mut Context<'_>) -> Poll<Option<BigInt>>;
+}
+
+/// An asynchronous sink for outputs.
+pub trait AsyncOutput {
+    fn poll_output(&mut self, cx: &mut Context<'_>, output: &BigInt) -> Poll<()>;
+}
+
+impl<T: AsyncInput + ?Sized> AsyncInput for &mut T {
+    fn poll_input(&mut self, cx: &mut Context<'_>) -> Poll<Option<BigInt>> {
+        (**self).poll_input(cx)
+    }
+}
+
+impl<T: AsyncOutput + ?Sized> AsyncOutput for &mut T {
+    fn poll_output(&mut self, cx: &mut Context<'_>, output: &BigInt) -> Poll<()> {
+        (**self).poll_output(cx, output)
+    }
+}
+
+/// Runs the computer until it halts, awaiting `input` whenever it is waiting for input and
+/// `output` whenever it is waiting to output. Fails with `ComputerExecutionError::InputClosed` if
+/// the input runs out.
+pub fn run_async<I: AsyncInput + Unpin, O: AsyncOutput + Unpin>(computer: Computer, input: I, output: O) -> Run<I, O> {
+    Run { computer: Some(computer), input, output }
+}
+
+/// The future returned by `run_async`, resolving to the halted computer.
+pub struct Run<I, O> {
+    computer: Option<Computer>,
+    input: I,
+    output: O,
+}
+
+impl<I: AsyncInput + Unpin, O: AsyncOutput + Unpin> Future for Run<I, O> {
+    type Output = Result<Computer, ComputerExecutionError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let run = self.get_mut();
+        let computer = run.computer.as_mut().expect("Run polled after completion");
+
+        for _ in 0..STEPS_PER_POLL {
+            let result = match computer.state() {
+                ComputerState::Running => computer.step(),
+                ComputerState::WaitingForInput => match run.input.poll_input(cx) {
+                    Poll::Ready(Some(input)) => computer.input(input),
+                    Poll::Ready(None) => Err(ComputerExecutionError::InputClosed),
+                    Poll::Pending => return Poll::Pending,
+                },
+                ComputerState::WaitingToOutput(value) => match run.output.poll_output(cx, value) {
+                    Poll::Ready(()) => computer.output().map(|_| ()),
+                    Poll::Pending => return Poll::Pending,
+                },
+                ComputerState::Halted => return Poll::Ready(Ok(run.computer.take().unwrap())),
+            };
+            if let Err(err) = result {
+                return Poll::Ready(Err(err));
+            }
+        }
+
+        // Give other machines a turn before carrying on
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+struct Shared {
+    queue: VecDeque<BigInt>,
+    receiver: Option<Waker>,
+    senders: usize,
+}
+
+/// Sends values to the `Receiver` of the same channel. Sending never waits.
+pub struct Sender {
+    shared: Rc<RefCell<Shared>>,
+}
+
+/// Receives values in the order they were sent, and sees the end of the input once every
+/// `Sender` is dropped.
+pub struct Receiver {
+    shared: Rc<RefCell<Shared>>,
+}
+
+/// Creates an unbounded channel for connecting computers running on the same `Executor`.
+pub fn channel() -> (Sender, Receiver) {
+    let shared = Rc::new(RefCell::new(Shared { queue: VecDeque::new(), receiver: None, senders: 1 }));
+    (Sender { shared: shared.clone() }, Receiver { shared })
+}
+
+impl Sender {
+    pub fn send(&self, value: BigInt) {
+        let mut shared = self.shared.borrow_mut();
+        shared.queue.push_back(value);
+        if let Some(waker) = shared.receiver.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Clone for Sender {
+    fn clone(&self) -> Self {
+        self.shared.borrow_mut().senders += 1;
+        Sender { shared: self.shared.clone() }
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        shared.senders -= 1;
+        if shared.senders == 0 {
+            if let Some(waker) = shared.receiver.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl AsyncOutput for Sender {
+    fn poll_output(&mut self, _cx: &mut Context<'_>, output: &BigInt) -> Poll<()> {
+        self.send(output.clone());
+        Poll::Ready(())
+    }
+}
+
+impl Receiver {
+    /// Takes the next value if one has already been sent.
+    pub fn try_recv(&mut self) -> Option<BigInt> {
+        self.shared.borrow_mut().queue.pop_front()
+    }
+}
+
+impl AsyncInput for Receiver {
+    fn poll_input(&mut self, cx: &mut Context<'_>) -> Poll<Option<BigInt>> {
+        let mut shared = self.shared.borrow_mut();
+        match shared.queue.pop_front() {
+            Some(value) => Poll::Ready(Some(value)),
+            None if shared.senders == 0 => Poll::Ready(None),
+            None => {
+                shared.receiver = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+type Task<'a> = Pin<Box<dyn Future<Output=()> + 'a>>;
+
+/// A single-threaded executor polling tasks in the order they are woken.
+pub struct Executor<'a> {
+    tasks: Vec<Option<Task<'a>>>,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+struct TaskWaker {
+    task: usize,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.ready.lock().unwrap().push_back(self.task);
+    }
+}
+
+impl<'a> Executor<'a> {
+    pub fn new() -> Self {
+        Executor { tasks: Vec::new(), ready: Arc::new(Mutex::new(VecDeque::new())) }
+    }
+
+    pub fn spawn<F: Future<Output=()> + 'a>(&mut self, future: F) {
+        self.ready.lock().unwrap().push_back(self.tasks.len());
+        self.tasks.push(Some(Box::pin(future)));
+    }
+
+    /// Polls tasks until none can make progress. Returns the number of tasks left unfinished,
+    /// which are waiting on something that will never happen.
+    pub fn run(&mut self) -> usize {
+        loop {
+            let task = match self.ready.lock().unwrap().pop_front() {
+                Some(task) => task,
+                None => break,
+            };
+            // Tasks can be woken again after finishing
+            let future = match &mut self.tasks[task] {
+                Some(future) => future,
+                None => continue,
+            };
+            let waker = Waker::from(Arc::new(TaskWaker { task, ready: self.ready.clone() }));
+            if future.as_mut().poll(&mut Context::from_waker(&waker)).is_ready() {
+                self.tasks[task] = None;
+            }
+        }
+        self.tasks.iter().filter(|task| task.is_some()).count()
+    }
+}
+
+impl<'a> Default for Executor<'a> {
+    fn default() -> Self {
+        Executor::new()
+    }
+}
@@ -1,19 +1,24 @@
 extern crate failure;
 
 use std::{fs, thread};
-use std::collections::HashMap;
-use std::fmt::Display;
-use std::ops::Index;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Display};
+use std::ops::{Index, Range};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::sync::mpsc::{Receiver, Sender};
 
 use enum_primitive_derive::Primitive;
 use failure_derive::Fail;
 use num_bigint::BigInt;
-use num_traits::{abs, FromPrimitive, One, ToPrimitive, Zero};
+use num_traits::{abs, FromPrimitive, One, Signed, ToPrimitive, Zero};
 
+use self::binary::BinaryFormatError;
 use self::failure::_core::fmt::{Error, Formatter};
 
+#[path = "int_code_binary.rs"]
+pub mod binary;
+
 pub type Memory = HashMap<BigInt, BigInt>;
 
 
@@ -24,9 +29,15 @@ pub struct Computer {
     relative_base: BigInt,
     state: ComputerState,
     default_mem: BigInt,
+    self_modification_mode: SelfModificationMode,
+    executed: HashSet<BigInt>,
+    self_modifications: Vec<SelfModification>,
+    extension_ops: HashMap<u8, Arc<dyn ExtensionOp>>,
+    devices: Vec<MappedDevice>,
+    instructions: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ComputerState {
     Running,
     Halted,
@@ -34,9 +45,107 @@ pub enum ComputerState {
     WaitingToOutput(BigInt),
 }
 
+/// How the computer reacts to a program writing into its own instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfModificationMode {
+    /// Writes are not checked. This is the default.
+    Ignore,
+    /// Writes are performed and recorded in `Computer::self_modifications`.
+    Report,
+    /// Writes are refused with `ComputerExecutionError::SelfModifyingWrite`.
+    Trap,
+}
+
+/// A write into an address that has been executed as part of an instruction, or that is part of
+/// the current instruction or one that could be executed next.
+#[derive(Debug, Clone)]
+pub struct SelfModification {
+    /// Program counter of the instruction performing the write.
+    pub pc: BigInt,
+    pub address: BigInt,
+    pub value: BigInt,
+}
+
+/// An instruction registered on a `Computer` alongside the built-in op codes, the counterpart
+/// of `int_code::Op`.
+pub trait ExtensionOp: Debug + Send + Sync {
+    fn op_code(&self) -> u8;
+    fn num_args(&self) -> u8;
+    /// Executes the instruction at the program counter. Arguments can be read with
+    /// `Computer::arg` and `Computer::position_arg` using `op_modes`.
+    fn execute(&self, computer: &mut Computer, op_modes: &OpModes) -> Result<OpResult, ComputerExecutionError>;
+}
+
+/// A peripheral that handles reads and writes to a range of a `Computer`'s memory.
+pub trait Device: Debug + Send + CloneDevice {
+    /// Reads the word at `offset` from the start of the device's address range.
+    fn read(&mut self, offset: &BigInt) -> BigInt;
+    /// The word at `offset` without any side effects a read would have, such as a clock ticking.
+    fn peek(&self, offset: &BigInt) -> BigInt;
+    /// Writes the word at `offset` from the start of the device's address range.
+    fn write(&mut self, offset: &BigInt, value: BigInt);
+}
+
+/// Copies a device for a clone of the `Computer` it is attached to. Implemented for every
+/// `Clone` device.
+pub trait CloneDevice {
+    fn clone_device(&self) -> Arc<Mutex<dyn Device>>;
+}
+
+impl<T: Device + Clone + 'static> CloneDevice for T {
+    fn clone_device(&self) -> Arc<Mutex<dyn Device>> {
+        Arc::new(Mutex::new(self.clone()))
+    }
+}
+
+/// A device attached to an address range. Clones of a computer get their own copy of each
+/// device, so forked computers don't disturb each other.
+#[derive(Debug)]
+struct MappedDevice {
+    range: Range<BigInt>,
+    device: Arc<Mutex<dyn Device>>,
+}
+
+impl Clone for MappedDevice {
+    fn clone(&self) -> Self {
+        MappedDevice { range: self.range.clone(), device: self.device.lock().unwrap().clone_device() }
+    }
+}
+
+pub enum OpResult {
+    AdvanceProgramCounter,
+    SuppressProgramCounter,
+}
+
 pub trait IO {
     fn get_input(&mut self) -> BigInt;
     fn output(&mut self, output: BigInt);
+    /// Called before each instruction with the number of instructions executed so far,
+    /// including the one about to run.
+    fn at_instruction(&mut self, _instructions: u64) {}
+}
+
+/// A reason for `Computer::run_until` to hand control back to the caller.
+pub enum StopCondition {
+    /// Stop before executing the instruction at this address.
+    Address(BigInt),
+    /// Stop after a step makes the word at this address satisfy the predicate, when it did not
+    /// before the step.
+    Memory(BigInt, Box<dyn Fn(&BigInt) -> bool>),
+    /// Stop after this value has been output.
+    OutputEquals(BigInt),
+    /// Stop after an output satisfying the predicate.
+    Output(Box<dyn Fn(&BigInt) -> bool>),
+    /// Stop before reading input.
+    WaitingForInput,
+}
+
+/// Why `Computer::run_until` returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Halted,
+    /// The condition at this index fired.
+    Condition(usize),
 }
 
 #[derive(Debug, Fail)]
@@ -45,6 +154,8 @@ pub enum MemoryParseError {
     IoError(#[cause] std::io::Error),
     #[fail(display = "Problem parsing opcode")]
     ParseError(#[cause] num_bigint::ParseBigIntError),
+    #[fail(display = "Problem decoding binary program")]
+    BinaryError(#[cause] BinaryFormatError),
 }
 
 #[derive(Debug, Fail)]
@@ -67,6 +178,18 @@ pub enum ComputerExecutionError {
     InvalidInputOpCode,
     #[fail(display = "Invalid position op mode {}", mode)]
     InvalidPositionOpMode { mode: OpMode },
+    #[fail(display = "Instruction at {} wrote to instruction address {}", pc, address)]
+    SelfModifyingWrite { pc: BigInt, address: BigInt },
+    #[fail(display = "Op code {} is already in use", op)]
+    OpCodeInUse { op: u8 },
+    #[fail(display = "Device range {}..{} overlaps an attached device", start, end)]
+    OverlappingDevice { start: BigInt, end: BigInt },
+    #[fail(display = "Input closed")]
+    InputClosed,
+    #[fail(display = "Invalid instruction {}", word)]
+    InvalidInstruction { word: BigInt },
+    #[fail(display = "Negative address {}", address)]
+    NegativeAddress { address: BigInt },
 }
 
 #[derive(Primitive, Copy, Clone, Debug)]
@@ -76,6 +199,7 @@ pub enum OpMode {
     Relative = 2,
 }
 
+/// Plain memory, bypassing any attached devices.
 impl Index<&BigInt> for Computer {
     type Output = BigInt;
 
@@ -86,13 +210,120 @@ impl Index<&BigInt> for Computer {
 
 impl Computer {
     pub fn new<T: AsRef<Path>>(file: T) -> Result<Self, MemoryParseError> {
-        return Ok(Computer {
-            memory: read_memory(file)?,
+        Ok(Computer::from_memory(read_memory(file)?))
+    }
+
+    pub fn from_memory(memory: Memory) -> Self {
+        Computer {
+            memory,
             program_counter: Zero::zero(),
             relative_base: Zero::zero(),
             state: ComputerState::Running,
             default_mem: Zero::zero(),
-        });
+            self_modification_mode: SelfModificationMode::Ignore,
+            executed: HashSet::new(),
+            self_modifications: Vec::new(),
+            extension_ops: HashMap::new(),
+            devices: Vec::new(),
+            instructions: 0,
+        }
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Encodes the memory as a sparse image in the `binary` format, which `Computer::new` can
+    /// load again. Devices, registers and the program counter are not included.
+    pub fn memory_image(&self) -> Vec<u8> {
+        let memory: Vec<(BigInt, BigInt)> = self.memory.iter()
+            .map(|(address, word)| (address.clone(), word.clone()))
+            .collect();
+        let words: Vec<BigInt> = self.memory.values().cloned().collect();
+        binary::encode_image(&memory, binary::minimum_width(&words))
+            .expect("minimum width fits every word")
+    }
+
+    /// Sets a word in memory directly, bypassing any device mapped at the address. Meant for
+    /// changing the program before running it.
+    pub fn patch(&mut self, address: BigInt, value: BigInt) {
+        self.memory.insert(address, value);
+    }
+
+    pub fn state(&self) -> &ComputerState {
+        &self.state
+    }
+
+    /// Routes reads and writes of addresses in `range` to `device`. Returns a handle so the
+    /// device can be inspected while the computer runs. The handle is for this computer's device
+    /// only, as clones of the computer copy it.
+    pub fn attach_device<T: Device + 'static>(&mut self, range: Range<BigInt>, device: T) -> Result<Arc<Mutex<T>>, ComputerExecutionError> {
+        if self.devices.iter().any(|mapped| range.start < mapped.range.end && mapped.range.start < range.end) {
+            return Err(ComputerExecutionError::OverlappingDevice { start: range.start, end: range.end });
+        }
+        let device = Arc::new(Mutex::new(device));
+        self.devices.push(MappedDevice { range, device: device.clone() });
+        Ok(device)
+    }
+
+    /// Reads a word of memory, going through an attached device if one covers `address`.
+    pub fn read(&self, address: &BigInt) -> BigInt {
+        match self.device_at(address) {
+            Some(mapped) => mapped.device.lock().unwrap().read(&(address - &mapped.range.start)),
+            None => self[address].clone(),
+        }
+    }
+
+    /// Reads a word of memory like `read`, but without any side effects of reading a device.
+    pub fn peek(&self, address: &BigInt) -> BigInt {
+        match self.device_at(address) {
+            Some(mapped) => mapped.device.lock().unwrap().peek(&(address - &mapped.range.start)),
+            None => self[address].clone(),
+        }
+    }
+
+    fn device_at(&self, address: &BigInt) -> Option<&MappedDevice> {
+        self.devices.iter().find(|mapped| mapped.range.contains(address))
+    }
+
+    /// Adds an instruction to this computer. Built-in op codes can not be replaced; registering
+    /// an extension op code a second time replaces the earlier op.
+    pub fn register_op<T: ExtensionOp + 'static>(&mut self, op: T) -> Result<(), ComputerExecutionError> {
+        let op_code = op.op_code();
+        if OpCode::from_u8(op_code).is_some() {
+            return Err(ComputerExecutionError::OpCodeInUse { op: op_code });
+        }
+        self.extension_ops.insert(op_code, Arc::new(op));
+        Ok(())
+    }
+
+    pub fn program_counter(&self) -> &BigInt {
+        &self.program_counter
+    }
+
+    pub fn set_program_counter(&mut self, program_counter: BigInt) {
+        self.program_counter = program_counter;
+    }
+
+    pub fn relative_base(&self) -> &BigInt {
+        &self.relative_base
+    }
+
+    /// The number of instructions executed since the computer was created.
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /// Sets whether writes into instructions are ignored, reported or trapped. Executed addresses
+    /// are only tracked outside `SelfModificationMode::Ignore`, so switching to reporting part way
+    /// through a run doesn't cover the instructions executed before.
+    pub fn set_self_modification_mode(&mut self, mode: SelfModificationMode) {
+        self.self_modification_mode = mode;
+    }
+
+    /// Writes into instructions recorded while in `SelfModificationMode::Report`.
+    pub fn self_modifications(&self) -> &[SelfModification] {
+        &self.self_modifications
     }
 
     pub fn step(&mut self) -> Result<(), ComputerExecutionError> {
@@ -103,12 +334,32 @@ impl Computer {
             ComputerState::Running => {}
         }
 
-        let op = &self[&self.program_counter];
-        let op_code = get_op_code(&op)?;
+        if self.program_counter.is_negative() {
+            return Err(ComputerExecutionError::NegativeAddress { address: self.program_counter.clone() });
+        }
+        // Executed addresses are only needed to check writes, so they aren't kept when ignoring them
+        let start = match self.self_modification_mode {
+            SelfModificationMode::Ignore => None,
+            _ => Some(self.program_counter.clone()),
+        };
+        let op = self.read(&self.program_counter);
         let op_modes = get_op_modes(&op)?;
+        let num_args = match get_op_code(&op) {
+            Ok(op_code) => {
+                self.execute_op(&op_code, &op_modes)?;
+                op_code.num_args()
+            }
+            Err(ComputerExecutionError::InvalidOpCode { op }) if self.extension_ops.contains_key(&op) => {
+                self.execute_extension_op(op, &op_modes)?
+            }
+            Err(err) => return Err(err),
+        };
 
-        self.execute_op(&op_code, &op_modes)?;
-
+        // Only counted once it succeeds, so a trapped write leaves the computer as it was
+        self.instructions += 1;
+        if let Some(start) = start {
+            self.mark_executed(&start, num_args);
+        }
         Ok(())
     }
 
@@ -139,18 +390,19 @@ impl Computer {
             _ => return Err(ComputerExecutionError::InvalidInputOpCode)
         }
 
-        self.memory.insert(
+        self.write(
             self.position_arg(0, &op_modes)?,
             input,
-        );
+        )?;
         self.state = ComputerState::Running;
         self.increase_program_counter(1);
 
         Ok(())
     }
 
-    fn op_code_and_modes(&mut self) -> Result<(OpCode, OpModes), ComputerExecutionError> {
-        let op = &self.memory[&self.program_counter];
+    /// Decodes the built-in instruction at the program counter.
+    pub fn op_code_and_modes(&self) -> Result<(OpCode, OpModes), ComputerExecutionError> {
+        let op = self.read(&self.program_counter);
         let op_code = get_op_code(&op)?;
         let op_modes = get_op_modes(&op)?;
         Ok((op_code, op_modes))
@@ -166,6 +418,7 @@ impl Computer {
                 }
                 ComputerState::Running => {
 //                    println!("c");
+                    io.at_instruction(self.instructions + 1);
                     self.step()?
                 }
                 ComputerState::Halted => {
@@ -181,6 +434,67 @@ impl Computer {
         Ok(())
     }
 
+    /// Runs like `run` until the program halts or one of `conditions` fires, leaving the computer
+    /// ready to be inspected, modified and resumed by calling this again. Conditions that stop
+    /// before an instruction or input are not checked on the first step of a call, so resuming
+    /// does not stop at the same place again.
+    pub fn run_until<T: IO>(&mut self, io: &mut T, conditions: &[StopCondition]) -> Result<Stop, ComputerExecutionError> {
+        let mut held: Vec<bool> = conditions.iter().map(|condition| self.memory_condition_holds(condition)).collect();
+        let mut resuming = true;
+        loop {
+            if !resuming {
+                if let Some(index) = conditions.iter().position(|condition| self.stops_before_step(condition)) {
+                    return Ok(Stop::Condition(index));
+                }
+            }
+            resuming = false;
+
+            match self.state {
+                ComputerState::WaitingForInput => self.input(io.get_input())?,
+                ComputerState::Running => {
+                    io.at_instruction(self.instructions + 1);
+                    self.step()?
+                }
+                ComputerState::Halted => return Ok(Stop::Halted),
+                ComputerState::WaitingToOutput(_) => {
+                    let output = self.output()?;
+                    let fired = conditions.iter().position(|condition| match condition {
+                        StopCondition::OutputEquals(value) => *value == output,
+                        StopCondition::Output(predicate) => predicate(&output),
+                        _ => false,
+                    });
+                    io.output(output);
+                    if let Some(index) = fired {
+                        return Ok(Stop::Condition(index));
+                    }
+                }
+            }
+
+            for (index, condition) in conditions.iter().enumerate() {
+                let holds = self.memory_condition_holds(condition);
+                if holds && !held[index] {
+                    return Ok(Stop::Condition(index));
+                }
+                held[index] = holds;
+            }
+        }
+    }
+
+    fn stops_before_step(&self, condition: &StopCondition) -> bool {
+        match (condition, &self.state) {
+            (StopCondition::Address(address), ComputerState::Running) => self.program_counter == *address,
+            (StopCondition::WaitingForInput, ComputerState::WaitingForInput) => true,
+            _ => false,
+        }
+    }
+
+    fn memory_condition_holds(&self, condition: &StopCondition) -> bool {
+        match condition {
+            StopCondition::Memory(address, predicate) => predicate(&self.peek(address)),
+            _ => false,
+        }
+    }
+
     pub fn run_threaded<T: 'static + IO + Send>(&self, io: T) {
         let mut copy = self.clone();
         let mut io = io;
@@ -196,25 +510,37 @@ impl Computer {
     }
 }
 
+/// Reads a comma separated program, or a binary program or memory image in the `binary` format.
 fn read_memory<T: AsRef<Path>>(file: T) -> Result<Memory, MemoryParseError> {
-    Ok(
-        fs::read_to_string(file)
-            .map_err(|err| MemoryParseError::IoError(err))?
-            .split(",")
-            .map(|op| op.trim().parse::<BigInt>())
-            .enumerate()
-            .map(|(i, op)|
-                op.map(|code|
-                    (BigInt::from(i), code)
-                )
-            )
-            .collect::<Result<HashMap<_, _>, _>>()
-            .map_err(|err| MemoryParseError::ParseError(err))?
+    let bytes = fs::read(file).map_err(MemoryParseError::IoError)?;
+    if binary::is_binary(&bytes) {
+        let (_, memory) = binary::decode_image(&bytes)
+            .map_err(MemoryParseError::BinaryError)?;
+        return Ok(memory.into_iter().collect());
+    }
+    parse_memory(
+        &String::from_utf8(bytes)
+            .map_err(|err| MemoryParseError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidData, err)))?
     )
 }
 
-#[derive(Primitive)]
-enum OpCode {
+/// Parses a comma separated program into memory starting at address zero.
+pub fn parse_memory(program: &str) -> Result<Memory, MemoryParseError> {
+    program
+        .split(",")
+        .map(|op| op.trim().parse::<BigInt>())
+        .enumerate()
+        .map(|(i, op)|
+            op.map(|code|
+                (BigInt::from(i), code)
+            )
+        )
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(MemoryParseError::ParseError)
+}
+
+#[derive(Primitive, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpCode {
     Plus = 1,
     Times = 2,
     Input = 3,
@@ -227,6 +553,17 @@ enum OpCode {
     Halt = 99,
 }
 
+impl OpCode {
+    pub fn num_args(&self) -> u8 {
+        match self {
+            OpCode::Plus | OpCode::Times | OpCode::LessThan | OpCode::Equals => 3,
+            OpCode::JumpIfTrue | OpCode::JumpIfFalse => 2,
+            OpCode::Input | OpCode::Output | OpCode::AdjustRelativeBase => 1,
+            OpCode::Halt => 0,
+        }
+    }
+}
+
 impl Display for OpMode {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         match self {
@@ -237,7 +574,7 @@ impl Display for OpMode {
     }
 }
 
-struct OpModes {
+pub struct OpModes {
     modes: Vec<OpMode>
 }
 
@@ -253,8 +590,12 @@ impl Index<usize> for OpModes {
     }
 }
 
+/// Decodes the op code of an instruction word.
 //noinspection RsTypeCheck
-fn get_op_code(command: &BigInt) -> Result<OpCode, ComputerExecutionError> {
+pub fn get_op_code(command: &BigInt) -> Result<OpCode, ComputerExecutionError> {
+    if command.is_negative() {
+        return Err(ComputerExecutionError::InvalidInstruction { word: command.clone() });
+    }
     let op_big: BigInt = command % 100;
     let op_num = abs(op_big.to_isize().unwrap()) as u8;
     let op = OpCode::from_u8(op_num);
@@ -262,8 +603,18 @@ fn get_op_code(command: &BigInt) -> Result<OpCode, ComputerExecutionError> {
     op.ok_or_else(|| ComputerExecutionError::InvalidOpCode { op: op_num })
 }
 
+/// Programs may only use non-negative addresses, although `Computer::patch` and devices can use
+/// any.
+fn check_address(address: BigInt) -> Result<BigInt, ComputerExecutionError> {
+    if address.is_negative() {
+        return Err(ComputerExecutionError::NegativeAddress { address });
+    }
+    Ok(address)
+}
+
+/// Decodes the parameter modes of an instruction word.
 //noinspection RsTypeCheck
-fn get_op_modes(command: &BigInt) -> Result<OpModes, ComputerExecutionError> {
+pub fn get_op_modes(command: &BigInt) -> Result<OpModes, ComputerExecutionError> {
     let mut modes = Vec::<OpMode>::new();
 
     let mut modes_num = command / 100;
@@ -282,41 +633,124 @@ fn get_op_modes(command: &BigInt) -> Result<OpModes, ComputerExecutionError> {
 }
 
 impl Computer {
-    fn position_arg(&self, arg_index: usize, op_modes: &OpModes) -> Result<BigInt, ComputerExecutionError> {
+    /// The address written to by argument `arg_index` of the current instruction.
+    pub fn position_arg(&self, arg_index: usize, op_modes: &OpModes) -> Result<BigInt, ComputerExecutionError> {
         let mode = op_modes[arg_index];
         Ok(
             match mode {
-                OpMode::Position => self.raw_arg(arg_index).clone(),
+                OpMode::Position => self.raw_arg(arg_index),
                 OpMode::Immediate => return Err(ComputerExecutionError::InvalidPositionOpMode { mode }),
                 OpMode::Relative => &self.relative_base + self.raw_arg(arg_index)
             }
         )
     }
 
-    fn raw_arg(&self, arg_index: usize) -> &BigInt {
-        &self[&(&self.program_counter + (1 + arg_index))]
+    fn raw_arg(&self, arg_index: usize) -> BigInt {
+        self.read(&(&self.program_counter + (1 + arg_index)))
     }
 
-    fn arg(&self, arg_index: usize, op_modes: &OpModes) -> Result<BigInt, ComputerExecutionError> {
+    /// The value of argument `arg_index` of the current instruction.
+    pub fn arg(&self, arg_index: usize, op_modes: &OpModes) -> Result<BigInt, ComputerExecutionError> {
         let mode = op_modes[arg_index];
         Ok(
             match mode {
-                OpMode::Position => self[&self.raw_arg(arg_index)].clone(),
-                OpMode::Immediate => self.raw_arg(arg_index).clone(),
-                OpMode::Relative => self.memory[&(&self.relative_base + self.raw_arg(arg_index))].clone()
+                OpMode::Position => self.read(&check_address(self.raw_arg(arg_index))?),
+                OpMode::Immediate => self.raw_arg(arg_index),
+                OpMode::Relative => self.read(&check_address(&self.relative_base + self.raw_arg(arg_index))?)
             }
         )
     }
 
+    pub fn write(&mut self, address: BigInt, value: BigInt) -> Result<(), ComputerExecutionError> {
+        let address = check_address(address)?;
+        if self.self_modification_mode != SelfModificationMode::Ignore && self.is_instruction(&address) {
+            if self.self_modification_mode == SelfModificationMode::Trap {
+                return Err(ComputerExecutionError::SelfModifyingWrite {
+                    pc: self.program_counter.clone(),
+                    address,
+                });
+            }
+            self.self_modifications.push(SelfModification {
+                pc: self.program_counter.clone(),
+                address: address.clone(),
+                value: value.clone(),
+            });
+        }
+
+        match self.device_at(&address) {
+            Some(mapped) => mapped.device.lock().unwrap().write(&(&address - &mapped.range.start), value),
+            None => { self.memory.insert(address, value); }
+        }
+        Ok(())
+    }
+
+    /// Whether `address` is part of an instruction that has been executed, the current instruction
+    /// or the instruction after it, arguments included. When the instruction after is a jump,
+    /// the instruction at its target counts too.
+    fn is_instruction(&self, address: &BigInt) -> bool {
+        if self.executed.contains(address) {
+            return true;
+        }
+        let current = self.instruction_range(&self.program_counter);
+        let next = self.instruction_range(&current.end);
+        let target = self.jump_target(&next.start).map(|target| self.instruction_range(&target));
+        [Some(current), Some(next), target].iter().flatten().any(|range| range.contains(address))
+    }
+
+    /// The addresses of the instruction starting at `address`, going by plain memory. Words that
+    /// aren't a valid instruction are taken to be one without arguments.
+    fn instruction_range(&self, address: &BigInt) -> Range<BigInt> {
+        let op = &self[address];
+        let num_args = match get_op_code(op) {
+            Ok(op_code) => op_code.num_args(),
+            Err(ComputerExecutionError::InvalidOpCode { op }) if self.extension_ops.contains_key(&op) => {
+                self.extension_ops[&op].num_args()
+            }
+            Err(_) => 0,
+        };
+        address.clone()..address + (1 + num_args)
+    }
+
+    /// Where the jump starting at `address` would go if taken, or `None` if it isn't a jump.
+    fn jump_target(&self, address: &BigInt) -> Option<BigInt> {
+        let op = &self[address];
+        match get_op_code(op) {
+            Ok(OpCode::JumpIfTrue) | Ok(OpCode::JumpIfFalse) => {}
+            _ => return None,
+        }
+        let raw = &self[&(address + 2)];
+        match get_op_modes(op).ok()?[1] {
+            OpMode::Position => Some(self[raw].clone()),
+            OpMode::Immediate => Some(raw.clone()),
+            OpMode::Relative => Some(self[&(&self.relative_base + raw)].clone()),
+        }
+    }
+
+    fn mark_executed(&mut self, address: &BigInt, num_args: u8) {
+        for offset in 0..=num_args {
+            self.executed.insert(address + offset);
+        }
+    }
+
     fn increase_program_counter(&mut self, num_args: u8) {
         // One added to skip by current command
         self.program_counter += 1 + num_args;
     }
+    /// Executes a registered extension op, returning its number of arguments.
+    fn execute_extension_op(&mut self, op: u8, op_modes: &OpModes) -> Result<u8, ComputerExecutionError> {
+        let extension = self.extension_ops[&op].clone();
+        match extension.execute(self, op_modes)? {
+            OpResult::AdvanceProgramCounter => self.increase_program_counter(extension.num_args()),
+            OpResult::SuppressProgramCounter => {}
+        }
+        Ok(extension.num_args())
+    }
+
     fn execute_op(&mut self, op_code: &OpCode, op_modes: &OpModes) -> Result<(), ComputerExecutionError> {
         match op_code {
             OpCode::Plus => self.plus_op(op_modes),
             OpCode::Times => self.times_op(op_modes),
-            OpCode::Input => Ok(self.input_op()),
+            OpCode::Input => self.input_op(op_modes),
             OpCode::Output => self.output_op(op_modes),
             OpCode::JumpIfTrue => self.jump_if_true_op(op_modes),
             OpCode::JumpIfFalse => self.jump_if_false_op(op_modes),
@@ -338,10 +772,10 @@ impl Computer {
             Zero::zero()
         };
 
-        self.memory.insert(
+        self.write(
             self.position_arg(2, &op_modes)?,
             result,
-        );
+        )?;
         self.increase_program_counter(3);
         Ok(())
     }
@@ -353,17 +787,19 @@ impl Computer {
             Zero::zero()
         };
 
-        self.memory.insert(
+        self.write(
             self.position_arg(2, &op_modes)?,
             result,
-        );
+        )?;
         self.increase_program_counter(3);
         Ok(())
     }
 
     fn jump_if_false_op(&mut self, op_modes: &OpModes) -> Result<(), ComputerExecutionError> {
+        // The target is read even when the jump isn't taken, like `int_code` does
+        let target = self.arg(1, op_modes)?;
         if self.arg(0, &op_modes)? == Zero::zero() {
-            self.program_counter = self.arg(1, &op_modes)?;
+            self.program_counter = target;
         } else {
             self.increase_program_counter(2);
         }
@@ -371,8 +807,10 @@ impl Computer {
     }
 
     fn jump_if_true_op(&mut self, op_modes: &OpModes) -> Result<(), ComputerExecutionError> {
+        // The target is read even when the jump isn't taken, like `int_code` does
+        let target = self.arg(1, op_modes)?;
         if self.arg(0, &op_modes)? != Zero::zero() {
-            self.program_counter = self.arg(1, &op_modes)?;
+            self.program_counter = target;
         } else {
             self.increase_program_counter(2);
         }
@@ -386,24 +824,27 @@ impl Computer {
         Ok(())
     }
 
-    fn input_op(&mut self) -> () {
-        self.state = ComputerState::WaitingForInput
+    fn input_op(&mut self, op_modes: &OpModes) -> Result<(), ComputerExecutionError> {
+        // Checked before waiting so an invalid instruction fails without asking for input
+        self.position_arg(0, op_modes)?;
+        self.state = ComputerState::WaitingForInput;
+        Ok(())
     }
 
     fn times_op(&mut self, op_modes: &OpModes) -> Result<(), ComputerExecutionError> {
-        self.memory.insert(
+        self.write(
             self.position_arg(2, &op_modes)?,
             self.arg(0, &op_modes)? * self.arg(1, &op_modes)?,
-        );
+        )?;
         self.increase_program_counter(3);
         Ok(())
     }
 
     fn plus_op(&mut self, op_modes: &OpModes) -> Result<(), ComputerExecutionError> {
-        self.memory.insert(
+        self.write(
             self.position_arg(2, &op_modes)?,
             self.arg(0, &op_modes)? + self.arg(1, &op_modes)?,
-        );
+        )?;
         self.increase_program_counter(3);
         Ok(())
     }
@@ -415,11 +856,18 @@ impl Computer {
     }
 }
 
-struct ChannelIO {
+/// IO reading inputs from and sending outputs to channels, as used by `run_threaded_channels`.
+pub struct ChannelIO {
     input: Receiver<BigInt>,
     output: Sender<BigInt>,
 }
 
+impl ChannelIO {
+    pub fn new(input: Receiver<BigInt>, output: Sender<BigInt>) -> Self {
+        ChannelIO { input, output }
+    }
+}
+
 impl IO for ChannelIO {
     fn get_input(&mut self) -> BigInt {
         self.input.recv().unwrap()
@@ -431,4 +879,113 @@ impl IO for ChannelIO {
         self.output.send(output).unwrap_or(());
 //        println!("outputted");
     }
-}
\ No newline at end of file
+}
+
+/// Counts up by one on every read. Writing sets the count.
+#[derive(Debug, Clone, Default)]
+pub struct ClockDevice {
+    ticks: BigInt,
+}
+
+impl ClockDevice {
+    pub fn ticks(&self) -> &BigInt {
+        &self.ticks
+    }
+}
+
+impl Device for ClockDevice {
+    fn read(&mut self, _offset: &BigInt) -> BigInt {
+        self.ticks += 1;
+        self.ticks.clone()
+    }
+
+    fn peek(&self, _offset: &BigInt) -> BigInt {
+        self.ticks.clone()
+    }
+
+    fn write(&mut self, _offset: &BigInt, value: BigInt) {
+        self.ticks = value;
+    }
+}
+
+/// Produces pseudo-random non-negative words with a xorshift generator. Writing reseeds it.
+#[derive(Debug, Clone)]
+pub struct RandomDevice {
+    state: u64,
+}
+
+impl RandomDevice {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on zero
+        RandomDevice { state: seed.max(1) }
+    }
+}
+
+impl Device for RandomDevice {
+    fn read(&mut self, _offset: &BigInt) -> BigInt {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        BigInt::from(self.state >> 1)
+    }
+
+    /// The word the next read will give.
+    fn peek(&self, offset: &BigInt) -> BigInt {
+        self.clone().read(offset)
+    }
+
+    fn write(&mut self, _offset: &BigInt, value: BigInt) {
+        *self = RandomDevice::new((value % u64::MAX).to_u64().unwrap_or(1));
+    }
+}
+
+/// A `width` by `height` grid of pixels stored row by row. Addresses outside the grid read as zero
+/// and ignore writes.
+#[derive(Debug, Clone)]
+pub struct FramebufferDevice {
+    width: usize,
+    height: usize,
+    pixels: Vec<BigInt>,
+}
+
+impl FramebufferDevice {
+    pub fn new(width: usize, height: usize) -> Self {
+        FramebufferDevice { width, height, pixels: vec![Zero::zero(); width * height] }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> &BigInt {
+        &self.pixels[y * self.width + x]
+    }
+
+    /// Draws zero pixels as spaces and all others as `#`.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                out.push(if self.pixel(x, y).is_zero() { ' ' } else { '#' });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn index(&self, offset: &BigInt) -> Option<usize> {
+        offset.to_usize().filter(|index| *index < self.pixels.len())
+    }
+}
+
+impl Device for FramebufferDevice {
+    fn read(&mut self, offset: &BigInt) -> BigInt {
+        self.peek(offset)
+    }
+
+    fn peek(&self, offset: &BigInt) -> BigInt {
+        self.index(offset).map_or_else(Zero::zero, |index| self.pixels[index].clone())
+    }
+
+    fn write(&mut self, offset: &BigInt, value: BigInt) {
+        if let Some(index) = self.index(offset) {
+            self.pixels[index] = value;
+        }
+    }
+}
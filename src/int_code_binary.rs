@@ -0,0 +1,260 @@
+//! Compact binary encoding of Intcode programs and memory images.
+//!
+//! A file starts with the magic bytes `ICB`, a format version byte and a word width byte giving
+//! the number of bits every word fits in, with zero meaning unbounded. The number of words
+//! follows as an unsigned LEB128 varint, then each word zigzag encoded as a varint so that small
+//! negative numbers stay small.
+//!
+//! Sparse memory images start with `ICM` instead and hold runs of consecutive addresses in place
+//! of the word count: the number of runs, then for each run the zigzag encoded distance from the
+//! end of the previous run (or from address zero), its length and its words.
+
+use failure_derive::Fail;
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+const MAGIC: &[u8] = b"ICB";
+const IMAGE_MAGIC: &[u8] = b"ICM";
+pub const VERSION: u8 = 1;
+/// The longest varint accepted, which bounds the memory a corrupt file can make a word take.
+pub const MAX_VARINT_BYTES: usize = 1024;
+/// The highest address `to_words` lays out, so a sparse image with a far off word can't make it
+/// allocate more than a few tens of megabytes.
+pub const MAX_DENSE_ADDRESS: usize = 1 << 20;
+
+#[derive(Debug, Fail)]
+pub enum BinaryFormatError {
+    #[fail(display = "Missing binary program header")]
+    MissingHeader,
+    #[fail(display = "Unsupported binary program version {}", version)]
+    UnsupportedVersion { version: u8 },
+    #[fail(display = "Binary program ended in the middle of a word")]
+    Truncated,
+    #[fail(display = "Binary program has {} bytes after its last word", count)]
+    TrailingBytes { count: usize },
+    #[fail(display = "Word {} at {} does not fit in {} bits", word, address, width)]
+    WordTooWide { address: BigInt, word: BigInt, width: u8 },
+    #[fail(display = "Varint longer than {} bytes", max)]
+    VarintTooLong { max: usize },
+    #[fail(display = "Memory image has a word at negative address {}", address)]
+    NegativeAddress { address: BigInt },
+    #[fail(display = "Memory image has a word at address {}, past the limit of {}", address, max)]
+    AddressTooLarge { address: BigInt, max: usize },
+}
+
+/// Whether `bytes` start with the header of a binary program or memory image rather than text.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC) || bytes.starts_with(IMAGE_MAGIC)
+}
+
+/// The smallest of 32 bits, 64 bits or unbounded that every word fits in.
+pub fn minimum_width(words: &[BigInt]) -> u8 {
+    if words.iter().all(|word| word.to_i32().is_some()) {
+        32
+    } else if words.iter().all(|word| word.to_i64().is_some()) {
+        64
+    } else {
+        0
+    }
+}
+
+pub fn encode(words: &[BigInt], width: u8) -> Result<Vec<u8>, BinaryFormatError> {
+    let mut bytes = header(MAGIC, width);
+    write_varint(&mut bytes, BigUint::from(words.len()));
+    for (address, word) in words.iter().enumerate() {
+        write_word(&mut bytes, &BigInt::from(address), word, width)?;
+    }
+    Ok(bytes)
+}
+
+/// Decodes a binary program into its word width and words.
+pub fn decode(bytes: &[u8]) -> Result<(u8, Vec<BigInt>), BinaryFormatError> {
+    let (width, mut rest) = read_header(bytes, MAGIC)?;
+    let count = read_count(&mut rest)?;
+    // Every word takes at least a byte, which stops a corrupt count from allocating too much
+    let mut words = Vec::with_capacity(count.min(rest.len()));
+    for address in 0..count {
+        words.push(read_word(&mut rest, &BigInt::from(address), width)?);
+    }
+    check_end(rest)?;
+    Ok((width, words))
+}
+
+/// Encodes the words of a sparse memory as an image, leaving out the zero words.
+pub fn encode_image(memory: &[(BigInt, BigInt)], width: u8) -> Result<Vec<u8>, BinaryFormatError> {
+    let mut entries: Vec<&(BigInt, BigInt)> = memory.iter().filter(|(_, word)| !word.is_zero()).collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut runs: Vec<&[&(BigInt, BigInt)]> = Vec::new();
+    let mut start = 0;
+    for i in 1..=entries.len() {
+        if i == entries.len() || entries[i].0 != &entries[i - 1].0 + 1 {
+            runs.push(&entries[start..i]);
+            start = i;
+        }
+    }
+
+    let mut bytes = header(IMAGE_MAGIC, width);
+    write_varint(&mut bytes, BigUint::from(runs.len()));
+    let mut end = BigInt::zero();
+    for run in runs {
+        write_varint(&mut bytes, zigzag(&(&run[0].0 - &end)));
+        write_varint(&mut bytes, BigUint::from(run.len()));
+        for (address, word) in run {
+            write_word(&mut bytes, address, word, width)?;
+        }
+        end = &run[run.len() - 1].0 + 1;
+    }
+    Ok(bytes)
+}
+
+/// Decodes a binary program or memory image into its word width and the address and value of
+/// each word. Programs give every word from address zero and images only the non-zero words.
+pub fn decode_image(bytes: &[u8]) -> Result<(u8, Vec<(BigInt, BigInt)>), BinaryFormatError> {
+    if bytes.starts_with(MAGIC) {
+        let (width, words) = decode(bytes)?;
+        return Ok((width, words.into_iter().enumerate().map(|(address, word)| (BigInt::from(address), word)).collect()));
+    }
+
+    let (width, mut rest) = read_header(bytes, IMAGE_MAGIC)?;
+    let runs = read_count(&mut rest)?;
+    let mut memory = Vec::new();
+    let mut address = BigInt::zero();
+    for _ in 0..runs {
+        address += unzigzag(read_varint(&mut rest)?);
+        for _ in 0..read_count(&mut rest)? {
+            let word = read_word(&mut rest, &address, width)?;
+            memory.push((address.clone(), word));
+            address += 1;
+        }
+    }
+    check_end(rest)?;
+    Ok((width, memory))
+}
+
+/// Lays out the words of a memory image from address zero, filling gaps with zeros. Addresses
+/// past `MAX_DENSE_ADDRESS` are rejected.
+pub fn to_words(memory: &[(BigInt, BigInt)]) -> Result<Vec<BigInt>, BinaryFormatError> {
+    let mut words = Vec::new();
+    for (address, word) in memory {
+        if address.is_negative() {
+            return Err(BinaryFormatError::NegativeAddress { address: address.clone() });
+        }
+        let index = match address.to_usize() {
+            Some(index) if index <= MAX_DENSE_ADDRESS => index,
+            _ => return Err(BinaryFormatError::AddressTooLarge { address: address.clone(), max: MAX_DENSE_ADDRESS }),
+        };
+        if index >= words.len() {
+            words.resize(index + 1, BigInt::zero());
+        }
+        words[index] = word.clone();
+    }
+    Ok(words)
+}
+
+/// Formats words as a comma separated program.
+pub fn to_text(words: &[BigInt]) -> String {
+    words.iter().map(|word| word.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn header(magic: &[u8], width: u8) -> Vec<u8> {
+    let mut bytes = magic.to_vec();
+    bytes.push(VERSION);
+    bytes.push(width);
+    bytes
+}
+
+/// Checks the header, returning the word width and the bytes after the header.
+fn read_header<'a>(bytes: &'a [u8], magic: &[u8]) -> Result<(u8, &'a [u8]), BinaryFormatError> {
+    if !bytes.starts_with(magic) || bytes.len() < magic.len() + 2 {
+        return Err(BinaryFormatError::MissingHeader);
+    }
+    let version = bytes[magic.len()];
+    if version != VERSION {
+        return Err(BinaryFormatError::UnsupportedVersion { version });
+    }
+    Ok((bytes[magic.len() + 1], &bytes[magic.len() + 2..]))
+}
+
+fn read_count(bytes: &mut &[u8]) -> Result<usize, BinaryFormatError> {
+    read_varint(bytes)?.to_usize().ok_or(BinaryFormatError::Truncated)
+}
+
+fn check_end(rest: &[u8]) -> Result<(), BinaryFormatError> {
+    if rest.is_empty() {
+        Ok(())
+    } else {
+        Err(BinaryFormatError::TrailingBytes { count: rest.len() })
+    }
+}
+
+fn write_word(bytes: &mut Vec<u8>, address: &BigInt, word: &BigInt, width: u8) -> Result<(), BinaryFormatError> {
+    if !fits(word, width) {
+        return Err(BinaryFormatError::WordTooWide { address: address.clone(), word: word.clone(), width });
+    }
+    write_varint(bytes, zigzag(word));
+    Ok(())
+}
+
+fn read_word(bytes: &mut &[u8], address: &BigInt, width: u8) -> Result<BigInt, BinaryFormatError> {
+    let word = unzigzag(read_varint(bytes)?);
+    if !fits(&word, width) {
+        return Err(BinaryFormatError::WordTooWide { address: address.clone(), word, width });
+    }
+    Ok(word)
+}
+
+fn fits(word: &BigInt, width: u8) -> bool {
+    if width == 0 {
+        return true;
+    }
+    let limit = BigInt::one() << (width as usize - 1);
+    -&limit <= *word && *word < limit
+}
+
+fn zigzag(word: &BigInt) -> BigUint {
+    let doubled: BigUint = word.abs().to_biguint().unwrap() << 1usize;
+    match word.sign() {
+        Sign::Minus => doubled - 1u32,
+        _ => doubled,
+    }
+}
+
+fn unzigzag(value: BigUint) -> BigInt {
+    let negative = (&value & BigUint::one()).is_one();
+    let magnitude = value >> 1usize;
+    if negative {
+        -BigInt::from(magnitude) - 1
+    } else {
+        BigInt::from(magnitude)
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: BigUint) {
+    loop {
+        let low = (&value & BigUint::from(0x7fu8)).to_u8().unwrap();
+        value >>= 7usize;
+        if value.is_zero() {
+            bytes.push(low);
+            return;
+        }
+        bytes.push(low | 0x80);
+    }
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<BigUint, BinaryFormatError> {
+    let mut value = BigUint::zero();
+    let mut shift = 0;
+    loop {
+        if shift == MAX_VARINT_BYTES * 7 {
+            return Err(BinaryFormatError::VarintTooLong { max: MAX_VARINT_BYTES });
+        }
+        let (byte, rest) = bytes.split_first().ok_or(BinaryFormatError::Truncated)?;
+        *bytes = rest;
+        value |= BigUint::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
@@ -0,0 +1,500 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
+
+use crate::int_code_big::{Computer, ComputerExecutionError, ComputerState, OpCode, OpMode, OpModes};
+use crate::int_code_symbolic::Polynomial;
+
+/// A value computed from the program's inputs, where variable `i` of a polynomial is input `i`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Polynomial(Polynomial),
+    Plus(Box<Expr>, Box<Expr>),
+    Times(Box<Expr>, Box<Expr>),
+    LessThan(Box<Expr>, Box<Expr>),
+    Equals(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn constant(value: BigInt) -> Expr {
+        Expr::Polynomial(Polynomial::constant(value))
+    }
+
+    fn combine(op_code: OpCode, a: Expr, b: Expr) -> Expr {
+        match (op_code, a, b) {
+            (OpCode::Plus, Expr::Polynomial(a), Expr::Polynomial(b)) => Expr::Polynomial(&a + &b),
+            (OpCode::Times, Expr::Polynomial(a), Expr::Polynomial(b)) => Expr::Polynomial(&a * &b),
+            (OpCode::Plus, a, b) => Expr::Plus(Box::new(a), Box::new(b)),
+            (OpCode::Times, a, b) => Expr::Times(Box::new(a), Box::new(b)),
+            (OpCode::LessThan, a, b) => Expr::LessThan(Box::new(a), Box::new(b)),
+            (_, a, b) => Expr::Equals(Box::new(a), Box::new(b)),
+        }
+    }
+
+    pub fn evaluate(&self, inputs: &[BigInt]) -> BigInt {
+        let boolean = |value: bool| if value { BigInt::one() } else { BigInt::zero() };
+        match self {
+            Expr::Polynomial(polynomial) => polynomial.evaluate(inputs),
+            Expr::Plus(a, b) => a.evaluate(inputs) + b.evaluate(inputs),
+            Expr::Times(a, b) => a.evaluate(inputs) * b.evaluate(inputs),
+            Expr::LessThan(a, b) => boolean(a.evaluate(inputs) < b.evaluate(inputs)),
+            Expr::Equals(a, b) => boolean(a.evaluate(inputs) == b.evaluate(inputs)),
+        }
+    }
+
+    pub fn variables(&self) -> Vec<usize> {
+        let mut variables = match self {
+            Expr::Polynomial(polynomial) => polynomial.variables(),
+            Expr::Plus(a, b) | Expr::Times(a, b) | Expr::LessThan(a, b) | Expr::Equals(a, b) => {
+                [a.variables(), b.variables()].concat()
+            }
+        };
+        variables.sort();
+        variables.dedup();
+        variables
+    }
+
+    /// Values of `variable` near where this expression, or a comparison inside it, changes truth
+    /// with the other variables fixed at `inputs`.
+    fn candidates(&self, variable: usize, inputs: &[BigInt], out: &mut Vec<BigInt>) {
+        match self {
+            Expr::Polynomial(polynomial) => roots_near(polynomial, variable, inputs, out),
+            Expr::LessThan(a, b) | Expr::Equals(a, b) => {
+                if let (Expr::Polynomial(a), Expr::Polynomial(b)) = (&**a, &**b) {
+                    roots_near(&(a - b), variable, inputs, out);
+                } else {
+                    a.candidates(variable, inputs, out);
+                    b.candidates(variable, inputs, out);
+                }
+            }
+            Expr::Plus(a, b) | Expr::Times(a, b) => {
+                a.candidates(variable, inputs, out);
+                b.candidates(variable, inputs, out);
+            }
+        }
+    }
+}
+
+/// Adds the integers around the root of `polynomial` in `variable` if it is linear in it.
+fn roots_near(polynomial: &Polynomial, variable: usize, inputs: &[BigInt], out: &mut Vec<BigInt>) {
+    let mut univariate = polynomial.clone();
+    for other in polynomial.variables().into_iter().filter(|other| *other != variable) {
+        univariate = univariate.substitute(other, &inputs[other]);
+    }
+    if let Some(coefficients) = univariate.univariate_coefficients(variable) {
+        if let [constant, slope] = coefficients.as_slice() {
+            let root = -constant / slope;
+            out.push(&root - 1);
+            out.push(&root + 1);
+            out.push(root);
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Polynomial(polynomial) => write!(f, "{}", polynomial),
+            Expr::Plus(a, b) => write!(f, "({} + {})", a, b),
+            Expr::Times(a, b) => write!(f, "({} * {})", a, b),
+            Expr::LessThan(a, b) => write!(f, "({} < {})", a, b),
+            Expr::Equals(a, b) => write!(f, "({} == {})", a, b),
+        }
+    }
+}
+
+/// The requirement that `condition` is non-zero exactly when `holds` is true.
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pub condition: Expr,
+    pub holds: bool,
+}
+
+impl Constraint {
+    fn equals(expr: Expr, value: BigInt) -> Constraint {
+        Constraint { condition: Expr::combine(OpCode::Equals, expr, Expr::constant(value)), holds: true }
+    }
+
+    pub fn satisfied(&self, inputs: &[BigInt]) -> bool {
+        self.condition.evaluate(inputs).is_zero() != self.holds
+    }
+
+    fn negated(&self) -> Constraint {
+        Constraint { condition: self.condition.clone(), holds: !self.holds }
+    }
+}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} 0", self.condition, if self.holds { "!=" } else { "==" })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PathConstraint {
+    constraint: Constraint,
+    /// Constraints that lead down other paths from here. Empty for constraints that only fix a
+    /// symbolic address to its concrete value.
+    alternatives: Vec<Constraint>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub value: BigInt,
+    pub expr: Option<Expr>,
+    /// Number of path constraints collected before the output.
+    constraints: usize,
+}
+
+/// What one concrete run of the program did, alongside the symbolic view of it.
+#[derive(Debug, Default)]
+pub struct Trace {
+    pub inputs: Vec<BigInt>,
+    pub outputs: Vec<Output>,
+    constraints: Vec<PathConstraint>,
+    reached: bool,
+    pub error: Option<String>,
+}
+
+impl Trace {
+    pub fn constraints(&self) -> impl Iterator<Item=&Constraint> {
+        self.constraints.iter().map(|path| &path.constraint)
+    }
+
+    fn satisfies(&self, goal: &Goal) -> bool {
+        match goal {
+            Goal::ReachAddress(_) => self.reached,
+            Goal::Output(target) => self.outputs.iter().any(|output| output.value == *target),
+            Goal::OutputAt(index, target) => self.outputs.get(*index).map_or(false, |output| output.value == *target),
+            Goal::AllOutputs(target) => !self.outputs.is_empty() && self.outputs.iter().all(|output| output.value == *target),
+        }
+    }
+
+    fn fix(&mut self, expr: Option<Expr>, value: BigInt) {
+        if let Some(expr) = expr {
+            self.constraints.push(PathConstraint { constraint: Constraint::equals(expr, value), alternatives: Vec::new() });
+        }
+    }
+
+    fn jump(&mut self, condition: Expr, holds: bool) {
+        let constraint = Constraint { condition, holds };
+        let alternatives = vec![constraint.negated()];
+        self.constraints.push(PathConstraint { constraint, alternatives });
+    }
+
+    /// Fixes a symbolic op code, offering every other built-in op code with the same modes as an
+    /// alternative.
+    fn op_code(&mut self, expr: Option<Expr>, value: BigInt) {
+        if let Some(expr) = expr {
+            let modes = &value - &value % 100;
+            let alternatives = (1..=9).chain(Some(99))
+                .map(|op_code| &modes + op_code)
+                .filter(|word| *word != value)
+                .map(|word| Constraint::equals(expr.clone(), word))
+                .collect();
+            self.constraints.push(PathConstraint { constraint: Constraint::equals(expr, value), alternatives });
+        }
+    }
+}
+
+pub enum Goal {
+    /// Execute the instruction at this address.
+    ReachAddress(BigInt),
+    /// Output this value.
+    Output(BigInt),
+    /// Output this value as the output with this index, counting from zero.
+    OutputAt(usize, BigInt),
+    /// Output at least once, with every output being this value.
+    AllOutputs(BigInt),
+}
+
+pub struct Found {
+    pub inputs: Vec<BigInt>,
+    pub outputs: Vec<BigInt>,
+    pub runs: usize,
+}
+
+/// Explores the paths of a program by running it concretely while tracking how values depend on
+/// its inputs, then solving for inputs that take the other side of each jump.
+pub struct Explorer {
+    program: Computer,
+    /// Maximum number of concrete runs before giving up.
+    pub max_runs: usize,
+    /// Maximum number of instructions in each run.
+    pub max_steps: usize,
+}
+
+impl Explorer {
+    pub fn new(program: Computer) -> Explorer {
+        Explorer { program, max_runs: 1_000, max_steps: 100_000 }
+    }
+
+    /// Searches for inputs meeting the goal. Paths are explored breadth first, taking the other
+    /// side of each jump, or another op code for each symbolic op code, after the branch that
+    /// produced the inputs being run.
+    pub fn find(&self, goal: &Goal) -> Option<Found> {
+        let mut queue = VecDeque::new();
+        let mut seen = HashSet::new();
+        queue.push_back((Vec::new(), 0));
+        seen.insert(Vec::new());
+
+        let mut runs = 0;
+        while let Some((inputs, bound)) = queue.pop_front() {
+            if runs == self.max_runs {
+                break;
+            }
+            runs += 1;
+
+            let trace = self.run(&inputs, goal);
+            if trace.satisfies(goal) {
+                let outputs = trace.outputs.into_iter().map(|output| output.value).collect();
+                return Some(Found { inputs: trace.inputs, outputs, runs });
+            }
+
+            let num_inputs = trace.inputs.len();
+            let mut enqueue = |constraints: &[Constraint], bound: usize, urgent: bool| {
+                if let Some(next) = solve(constraints, &trace.inputs, num_inputs) {
+                    if seen.insert(next.clone()) {
+                        if urgent {
+                            queue.push_front((next, bound));
+                        } else {
+                            queue.push_back((next, bound));
+                        }
+                    }
+                }
+            };
+
+            // Steer the outputs the goal cares about straight towards it
+            let mut steer = |outputs: &[Output], target: &BigInt| {
+                let last = match outputs.last() {
+                    Some(last) => last,
+                    None => return,
+                };
+                let mut constraints: Vec<Constraint> = trace.constraints().take(last.constraints).cloned().collect();
+                let mut symbolic = false;
+                for output in outputs {
+                    if let Some(expr) = &output.expr {
+                        constraints.push(Constraint::equals(expr.clone(), target.clone()));
+                        symbolic = true;
+                    }
+                }
+                if symbolic {
+                    enqueue(&constraints, bound, true);
+                }
+            };
+            match goal {
+                Goal::ReachAddress(_) => {}
+                Goal::Output(target) => {
+                    for index in 0..trace.outputs.len() {
+                        steer(&trace.outputs[index..=index], target);
+                    }
+                }
+                Goal::OutputAt(index, target) => {
+                    if *index < trace.outputs.len() {
+                        steer(&trace.outputs[*index..=*index], target);
+                    }
+                }
+                Goal::AllOutputs(target) => {
+                    // Everything up to the first wrong output has to be right at once
+                    if let Some(first) = trace.outputs.iter().position(|output| output.value != *target) {
+                        steer(&trace.outputs[..=first], target);
+                    }
+                }
+            }
+
+            for i in bound..trace.constraints.len() {
+                for alternative in &trace.constraints[i].alternatives {
+                    let mut constraints: Vec<Constraint> = trace.constraints().take(i).cloned().collect();
+                    constraints.push(alternative.clone());
+                    enqueue(&constraints, i + 1, false);
+                }
+            }
+        }
+        None
+    }
+
+    /// Runs the program on `inputs`, reading zero once they run out.
+    pub fn run(&self, inputs: &[BigInt], goal: &Goal) -> Trace {
+        let mut computer = self.program.clone();
+        let mut shadow = HashMap::new();
+        let mut trace = Trace::default();
+
+        for _ in 0..self.max_steps {
+            if let Goal::ReachAddress(address) = goal {
+                if computer.program_counter() == address {
+                    trace.reached = true;
+                    break;
+                }
+            }
+
+            let result = match computer.state().clone() {
+                ComputerState::Halted => break,
+                ComputerState::WaitingForInput => input(&mut computer, &mut shadow, &mut trace, inputs),
+                ComputerState::WaitingToOutput(_) => computer.output().map(|_| ()),
+                ComputerState::Running => step(&mut computer, &mut shadow, &mut trace),
+            };
+            if let Err(err) = result {
+                trace.error = Some(err.to_string());
+                break;
+            }
+        }
+        trace
+    }
+}
+
+type Shadow = HashMap<BigInt, Expr>;
+
+fn input(computer: &mut Computer, shadow: &mut Shadow, trace: &mut Trace, inputs: &[BigInt]) -> Result<(), ComputerExecutionError> {
+    let index = trace.inputs.len();
+    let value = inputs.get(index).cloned().unwrap_or_else(Zero::zero);
+    let (_, op_modes) = computer.op_code_and_modes()?;
+    let address = write_target(computer, shadow, trace, 0, &op_modes)?;
+
+    computer.input(value.clone())?;
+    shadow.insert(address, Expr::Polynomial(Polynomial::variable(index)));
+    trace.inputs.push(value);
+    Ok(())
+}
+
+/// Executes one instruction concretely and mirrors its effect on the symbolic values.
+fn step(computer: &mut Computer, shadow: &mut Shadow, trace: &mut Trace) -> Result<(), ComputerExecutionError> {
+    let pc = computer.program_counter().clone();
+    trace.op_code(shadow.get(&pc).cloned(), computer.read(&pc));
+    let (op_code, op_modes) = computer.op_code_and_modes()?;
+
+    match op_code {
+        OpCode::Plus | OpCode::Times | OpCode::LessThan | OpCode::Equals => {
+            let a = read_arg(computer, shadow, trace, 0, &op_modes);
+            let b = read_arg(computer, shadow, trace, 1, &op_modes);
+            let target = write_target(computer, shadow, trace, 2, &op_modes)?;
+            let result = if a.is_none() && b.is_none() {
+                None
+            } else {
+                let a = match a {
+                    Some(a) => a,
+                    None => Expr::constant(computer.arg(0, &op_modes)?),
+                };
+                let b = match b {
+                    Some(b) => b,
+                    None => Expr::constant(computer.arg(1, &op_modes)?),
+                };
+                Some(Expr::combine(op_code, a, b))
+            };
+
+            computer.step()?;
+            match result {
+                Some(result) => shadow.insert(target, result),
+                None => shadow.remove(&target),
+            };
+        }
+        OpCode::JumpIfTrue | OpCode::JumpIfFalse => {
+            let condition = read_arg(computer, shadow, trace, 0, &op_modes);
+            let target = read_arg(computer, shadow, trace, 1, &op_modes);
+            trace.fix(target, computer.arg(1, &op_modes)?);
+            if let Some(condition) = condition {
+                let holds = !computer.arg(0, &op_modes)?.is_zero();
+                trace.jump(condition, holds);
+            }
+            computer.step()?;
+        }
+        OpCode::Output => {
+            let expr = read_arg(computer, shadow, trace, 0, &op_modes);
+            let value = computer.arg(0, &op_modes)?;
+            trace.outputs.push(Output { value, expr, constraints: trace.constraints.len() });
+            computer.step()?;
+        }
+        OpCode::AdjustRelativeBase => {
+            // Keeping the relative base concrete means relative addresses are always concrete
+            let adjustment = read_arg(computer, shadow, trace, 0, &op_modes);
+            trace.fix(adjustment, computer.arg(0, &op_modes)?);
+            computer.step()?;
+        }
+        OpCode::Input | OpCode::Halt => computer.step()?,
+    }
+    Ok(())
+}
+
+/// The symbolic value of an argument, if it has one. Symbolic addresses are fixed to their
+/// concrete values.
+fn read_arg(computer: &Computer, shadow: &Shadow, trace: &mut Trace, arg_index: usize, op_modes: &OpModes) -> Option<Expr> {
+    let param = computer.program_counter() + (1 + arg_index);
+    match op_modes[arg_index] {
+        OpMode::Immediate => shadow.get(&param).cloned(),
+        OpMode::Position | OpMode::Relative => {
+            trace.fix(shadow.get(&param).cloned(), computer.read(&param));
+            let address = computer.position_arg(arg_index, op_modes).ok()?;
+            shadow.get(&address).cloned()
+        }
+    }
+}
+
+fn write_target(computer: &Computer, shadow: &Shadow, trace: &mut Trace, arg_index: usize, op_modes: &OpModes) -> Result<BigInt, ComputerExecutionError> {
+    let param = computer.program_counter() + (1 + arg_index);
+    trace.fix(shadow.get(&param).cloned(), computer.read(&param));
+    computer.position_arg(arg_index, op_modes)
+}
+
+const MAX_REPAIRS: usize = 200;
+
+/// Looks for inputs satisfying every constraint by repeatedly repairing the first violated one,
+/// changing a single input to a value near where that constraint flips. When no single change
+/// repairs it, an input of the constraint is nudged instead, which gets products moving. Returns
+/// `None` when neither makes progress.
+pub fn solve(constraints: &[Constraint], start: &[BigInt], num_inputs: usize) -> Option<Vec<BigInt>> {
+    let mut inputs = start.to_vec();
+    inputs.resize(num_inputs, Zero::zero());
+    let mut tried = HashSet::new();
+
+    for _ in 0..MAX_REPAIRS {
+        let violated = match constraints.iter().find(|constraint| !constraint.satisfied(&inputs)) {
+            Some(violated) => violated,
+            None => return Some(inputs),
+        };
+        if !tried.insert(inputs.clone()) {
+            return None;
+        }
+
+        let mut best: Option<(usize, Vec<BigInt>)> = None;
+        for variable in violated.condition.variables() {
+            let mut candidates = vec![BigInt::zero(), &inputs[variable] + 1, &inputs[variable] - 1];
+            violated.condition.candidates(variable, &inputs, &mut candidates);
+
+            for candidate in candidates {
+                let mut next = inputs.clone();
+                next[variable] = candidate;
+                if !violated.satisfied(&next) || tried.contains(&next) {
+                    continue;
+                }
+                let score = constraints.iter().filter(|constraint| constraint.satisfied(&next)).count();
+                let better = match &best {
+                    Some((best_score, _)) => score > *best_score,
+                    None => true,
+                };
+                if better {
+                    best = Some((score, next));
+                }
+            }
+        }
+
+        inputs = match best {
+            Some((_, next)) => next,
+            None => nudge(&violated.condition.variables(), &inputs, &tried)?,
+        };
+    }
+    None
+}
+
+fn nudge(variables: &[usize], inputs: &[BigInt], tried: &HashSet<Vec<BigInt>>) -> Option<Vec<BigInt>> {
+    for variable in variables {
+        for delta in &[1, -1] {
+            let mut next = inputs.to_vec();
+            next[*variable] += *delta;
+            if !tried.contains(&next) {
+                return Some(next);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,1162 @@
+//! Decompiles Intcode programs into structured pseudocode.
+//!
+//! Code is found by following control flow from address 0. Jumps whose target is an immediate, or
+//! a cell that no instruction writes to, are followed. A jump to a constant address is treated as
+//! a call when the instructions just before it store the address after the jump into the
+//! relative base frame, and a jump to a relative mode address is treated as a return, which is
+//! the calling convention of compiled Intcode programs such as day 9's:
+//!
+//! ```text
+//! rb[1] = argument; rb[0] = return address; jump function
+//! function: rb += frame size; ...; rb -= frame size; jump rb[0]
+//! ```
+//!
+//! Each function's control flow graph is then turned back into loops and if/else using
+//! dominators and post-dominators, falling back to `goto` where it isn't structured. Cells used
+//! in position mode are named `v<address>`, or `code[<address>]` if they are part of an
+//! instruction, and operands whose words the program writes to read `memory[code[<address>]]`.
+//! Relative mode cells in called functions are named from the function's frame, with `ret` for
+//! the return address, `arg<n>` for the arguments and `local<n>` for the rest. Elsewhere, and once
+//! the relative base is moved by an unknown amount, they are named `rb[<offset>]`.
+//!
+//! Decompilation stops with a note at code the program overwrites before running and at jumps to
+//! addresses only known at run time.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+
+use crate::int_code_big::{get_op_code, get_op_modes, Memory, OpCode, OpMode};
+
+/// How many instructions before a jump are searched for the store of its return address.
+const CALL_SEARCH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Operand {
+    Immediate(BigInt),
+    Position(i64),
+    Relative(i64),
+    /// A position mode operand whose word, at this address, is written by the program, as is
+    /// done to index arrays.
+    Indexed(i64),
+}
+
+#[derive(Debug, Clone)]
+pub struct Instruction {
+    pub address: i64,
+    pub op_code: OpCode,
+    pub operands: Vec<Operand>,
+}
+
+impl Instruction {
+    fn next(&self) -> i64 {
+        self.address + 1 + self.operands.len() as i64
+    }
+
+    /// The operand written to, if any.
+    fn destination(&self) -> Option<&Operand> {
+        match self.op_code {
+            OpCode::Plus | OpCode::Times | OpCode::LessThan | OpCode::Equals => self.operands.get(2),
+            OpCode::Input => self.operands.first(),
+            _ => None,
+        }
+    }
+
+    /// The operands read from.
+    fn sources(&self) -> &[Operand] {
+        match self.op_code {
+            OpCode::Input | OpCode::Halt => &[],
+            OpCode::Plus | OpCode::Times | OpCode::LessThan | OpCode::Equals => &self.operands[..2],
+            _ => &self.operands,
+        }
+    }
+
+    /// The value written when it only depends on immediates.
+    fn constant_result(&self) -> Option<BigInt> {
+        match (self.op_code, self.operands.first(), self.operands.get(1)) {
+            (OpCode::Plus, Some(Operand::Immediate(a)), Some(Operand::Immediate(b))) => Some(a + b),
+            (OpCode::Times, Some(Operand::Immediate(a)), Some(Operand::Immediate(b))) => Some(a * b),
+            _ => None,
+        }
+    }
+}
+
+/// How control leaves an instruction.
+#[derive(Debug, Clone)]
+enum Exit {
+    Next(i64),
+    Jump(i64),
+    /// Jumps to `target` when `condition` is non-zero if `if_true`, or zero otherwise.
+    Branch { condition: Operand, if_true: bool, target: i64, next: i64 },
+    /// A conditional jump to an address only known at run time.
+    IndirectBranch { condition: Operand, if_true: bool, target: Operand, next: i64 },
+    /// A call, with the address of the instruction storing the return address.
+    Call { target: i64, ret: i64, ret_store: i64 },
+    Return,
+    Indirect(Operand),
+    Halt,
+    Invalid(BigInt),
+}
+
+/// A comparison used as a branch condition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    pub left: String,
+    pub op: &'static str,
+    pub right: String,
+}
+
+impl Condition {
+    fn negate(&self) -> Self {
+        let op = match self.op {
+            "<" => ">=",
+            ">=" => "<",
+            "==" => "!=",
+            _ => "==",
+        };
+        Condition { left: self.left.clone(), op, right: self.right.clone() }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.left, self.op, self.right)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    Line(String),
+    If { condition: Condition, then: Vec<Statement>, otherwise: Vec<Statement> },
+    While { condition: Condition, body: Vec<Statement> },
+    Loop(Vec<Statement>),
+    Break,
+    Continue,
+    Return,
+    Halt,
+    Goto(i64),
+    /// The start of the block at this address, printed as a label if anything jumps to it.
+    Label(i64),
+}
+
+impl Statement {
+    /// Whether control never carries on after this statement.
+    fn is_terminal(&self) -> bool {
+        match self {
+            Statement::Break | Statement::Continue | Statement::Return | Statement::Halt | Statement::Goto(_) => true,
+            Statement::Line(line) => line.starts_with("goto "),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub entry: i64,
+    pub name: String,
+    pub parameters: usize,
+    pub body: Vec<Statement>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Decompiled {
+    /// Initial values of the cells used as variables.
+    pub globals: BTreeMap<i64, BigInt>,
+    pub functions: Vec<Function>,
+}
+
+pub fn decompile(memory: &Memory) -> Decompiled {
+    Decompiler::new(memory).decompile()
+}
+
+struct Decompiler<'a> {
+    memory: &'a Memory,
+    instructions: BTreeMap<i64, Instruction>,
+    exits: HashMap<i64, Exit>,
+    /// Addresses that are part of an instruction.
+    code: HashSet<i64>,
+    /// Cells written in position mode.
+    written: HashSet<i64>,
+    /// Position mode cells only ever read after being written in the same block, which are used
+    /// as temporaries.
+    temporaries: HashSet<i64>,
+    /// Number of arguments passed to each function.
+    parameters: BTreeMap<i64, usize>,
+}
+
+impl<'a> Decompiler<'a> {
+    fn new(memory: &'a Memory) -> Self {
+        Decompiler {
+            memory,
+            instructions: BTreeMap::new(),
+            exits: HashMap::new(),
+            code: HashSet::new(),
+            written: HashSet::new(),
+            temporaries: HashSet::new(),
+            parameters: BTreeMap::new(),
+        }
+    }
+
+    fn word(&self, address: i64) -> BigInt {
+        self.memory.get(&BigInt::from(address)).cloned().unwrap_or_else(Zero::zero)
+    }
+
+    fn decompile(mut self) -> Decompiled {
+        // Jump targets read from memory can only be trusted once every write is known, and
+        // following them can find more writes, so discover until nothing changes
+        loop {
+            let written = self.written.clone();
+            self.discover();
+            if self.written == written {
+                break;
+            }
+        }
+
+        self.temporaries = self.temporaries();
+
+        let mut entries: BTreeSet<i64> = self.exits.values()
+            .filter_map(|exit| match exit {
+                Exit::Call { target, .. } => Some(*target),
+                _ => None,
+            })
+            .collect();
+        entries.insert(0);
+        self.count_parameters();
+
+        let functions = entries.iter().map(|entry| self.function(*entry)).collect();
+        let globals = self.instructions.values()
+            .flat_map(|instruction| instruction.operands.iter())
+            .filter_map(|operand| match operand {
+                Operand::Position(address) if !self.code.contains(address) && !self.temporaries.contains(address) => {
+                    Some((*address, self.word(*address)))
+                }
+                _ => None,
+            })
+            .collect();
+        Decompiled { globals, functions }
+    }
+
+    /// Decodes every instruction reachable from address 0.
+    fn discover(&mut self) {
+        self.instructions.clear();
+        self.exits.clear();
+        self.code.clear();
+        // Writes found before are kept so that the set only grows and discovery settles
+        let written = self.written.clone();
+
+        let mut pending = vec![0];
+        while let Some(start) = pending.pop() {
+            let mut recent: Vec<Instruction> = Vec::new();
+            let mut address = start;
+            while address >= 0 && !self.instructions.contains_key(&address) {
+                let instruction = match self.decode(address, &written) {
+                    Ok(instruction) => instruction,
+                    Err(word) => {
+                        self.exits.insert(address, Exit::Invalid(word));
+                        break;
+                    }
+                };
+                let exit = self.exit(&instruction, &recent, &written);
+                for offset in 0..=instruction.operands.len() as i64 {
+                    self.code.insert(address + offset);
+                }
+                if let Some(Operand::Position(cell)) = instruction.destination() {
+                    self.written.insert(*cell);
+                }
+                self.instructions.insert(address, instruction.clone());
+
+                let next = match &exit {
+                    Exit::Next(next) => Some(*next),
+                    Exit::Jump(target) => {
+                        pending.push(*target);
+                        None
+                    }
+                    Exit::Branch { target, next, .. } => {
+                        pending.push(*target);
+                        Some(*next)
+                    }
+                    Exit::IndirectBranch { next, .. } => Some(*next),
+                    Exit::Call { target, ret, .. } => {
+                        pending.push(*target);
+                        pending.push(*ret);
+                        None
+                    }
+                    _ => None,
+                };
+                if !matches!(exit, Exit::Next(_)) {
+                    self.exits.insert(address, exit);
+                }
+                recent.push(instruction);
+                match next {
+                    Some(next) => address = next,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Addresses that start a block.
+    fn leaders(&self) -> HashSet<i64> {
+        self.exits.iter()
+            .flat_map(|(address, exit)| {
+                let mut leaders = match exit {
+                    Exit::Jump(target) => vec![*target],
+                    Exit::Branch { target, next, .. } => vec![*target, *next],
+                    Exit::IndirectBranch { next, .. } => vec![*next],
+                    Exit::Call { target, ret, .. } => vec![*target, *ret],
+                    _ => vec![],
+                };
+                leaders.extend(self.instructions.get(address).map(Instruction::next));
+                leaders
+            })
+            .chain(Some(0))
+            .collect()
+    }
+
+    fn temporaries(&self) -> HashSet<i64> {
+        let leaders = self.leaders();
+        let mut written = HashSet::new();
+        let mut live = HashSet::new();
+        let mut block_writes = HashSet::new();
+        for (address, instruction) in &self.instructions {
+            if leaders.contains(address) {
+                block_writes.clear();
+            }
+            for source in instruction.sources() {
+                if let Operand::Position(cell) = source {
+                    if !block_writes.contains(cell) {
+                        live.insert(*cell);
+                    }
+                }
+            }
+            if let Some(Operand::Position(cell)) = instruction.destination() {
+                written.insert(*cell);
+                block_writes.insert(*cell);
+            }
+        }
+        written.difference(&live).cloned().collect()
+    }
+
+    /// Decodes the instruction at `address`, treating operand words in `written` as variables.
+    fn decode(&self, address: i64, written: &HashSet<i64>) -> Result<Instruction, BigInt> {
+        let word = self.word(address);
+        let (op_code, op_modes) = match (get_op_code(&word), get_op_modes(&word)) {
+            (Ok(op_code), Ok(op_modes)) => (op_code, op_modes),
+            _ => return Err(word),
+        };
+        let mut operands = Vec::new();
+        for i in 0..op_code.num_args() as usize {
+            let operand_address = address + 1 + i as i64;
+            let value = self.word(operand_address);
+            let operand = match op_modes[i] {
+                OpMode::Immediate if written.contains(&operand_address) => Operand::Position(operand_address),
+                OpMode::Position if written.contains(&operand_address) => Operand::Indexed(operand_address),
+                OpMode::Immediate => Operand::Immediate(value),
+                OpMode::Position => Operand::Position(value.to_i64().ok_or_else(|| word.clone())?),
+                OpMode::Relative => Operand::Relative(value.to_i64().ok_or_else(|| word.clone())?),
+            };
+            operands.push(operand);
+        }
+        Ok(Instruction { address, op_code, operands })
+    }
+
+    fn exit(&self, instruction: &Instruction, recent: &[Instruction], written: &HashSet<i64>) -> Exit {
+        let next = instruction.next();
+        let if_true = match instruction.op_code {
+            OpCode::JumpIfTrue => true,
+            OpCode::JumpIfFalse => false,
+            OpCode::Halt => return Exit::Halt,
+            _ => return Exit::Next(next),
+        };
+        let condition = instruction.operands[0].clone();
+        let target = match &instruction.operands[1] {
+            Operand::Immediate(target) => target.to_i64(),
+            Operand::Position(cell) if !written.contains(cell) => self.word(*cell).to_i64(),
+            _ => None,
+        };
+
+        match (condition, target) {
+            (Operand::Immediate(value), _) if value.is_zero() == if_true => Exit::Next(next),
+            (Operand::Immediate(_), Some(target)) => self.jump_or_call(target, next, recent),
+            (Operand::Immediate(_), None) => match &instruction.operands[1] {
+                Operand::Relative(_) => Exit::Return,
+                target => Exit::Indirect(target.clone()),
+            },
+            (condition, Some(target)) => Exit::Branch { condition, if_true, target, next },
+            (condition, None) => Exit::IndirectBranch { condition, if_true, target: instruction.operands[1].clone(), next },
+        }
+    }
+
+    fn jump_or_call(&self, target: i64, next: i64, recent: &[Instruction]) -> Exit {
+        let ret_store = recent.iter().rev().take(CALL_SEARCH).find(|instruction| {
+            matches!(instruction.destination(), Some(Operand::Relative(_)))
+                && instruction.constant_result() == Some(BigInt::from(next))
+        });
+        match ret_store {
+            Some(instruction) => Exit::Call { target, ret: next, ret_store: instruction.address },
+            None => Exit::Jump(target),
+        }
+    }
+
+    /// Counts each function's arguments as the most relative mode cells above the return
+    /// address written before any call to it.
+    fn count_parameters(&mut self) {
+        for (address, exit) in &self.exits {
+            if let Exit::Call { target, ret_store, .. } = exit {
+                let slot = match self.instructions[ret_store].destination() {
+                    Some(Operand::Relative(slot)) => *slot,
+                    _ => continue,
+                };
+                let arguments = self.call_arguments(*address, *ret_store, slot).len();
+                let count = self.parameters.entry(*target).or_insert(0);
+                *count = (*count).max(arguments);
+            }
+        }
+    }
+
+    /// Instructions between the previous control flow and the call at `call` that store values
+    /// just above the return address slot, in slot order.
+    fn call_arguments(&self, call: i64, ret_store: i64, slot: i64) -> Vec<&Instruction> {
+        let mut arguments = BTreeMap::new();
+        for (address, instruction) in self.instructions.range(..call).rev() {
+            if self.exits.contains_key(address) {
+                break;
+            }
+            if *address == ret_store {
+                continue;
+            }
+            if let Some(Operand::Relative(offset)) = instruction.destination() {
+                if *offset > slot {
+                    arguments.entry(*offset).or_insert(instruction);
+                }
+            }
+        }
+        // Only a run of consecutive slots counts, so stray stores aren't taken as arguments
+        arguments.iter()
+            .enumerate()
+            .take_while(|(i, (offset, _))| **offset == slot + 1 + *i as i64)
+            .map(|(_, (_, instruction))| *instruction)
+            .collect()
+    }
+
+    fn function(&self, entry: i64) -> Function {
+        let graph = Graph::new(self, entry);
+        let parameters = self.parameters.get(&entry).cloned().unwrap_or(0);
+        let mut structurer = Structurer {
+            decompiler: self,
+            graph: &graph,
+            parameters,
+            done: HashSet::new(),
+            loops: Vec::new(),
+        };
+        let mut body = structurer.region(entry, None);
+        tidy(&mut body);
+        let name = if entry == 0 { "main".to_string() } else { format!("f{}", entry) };
+        Function { entry, name, parameters, body }
+    }
+
+    /// Names an operand as read or written with the relative base `frame` cells above the one
+    /// at the entry of a called function, if known.
+    fn name(&self, operand: &Operand, frame: Option<i64>, parameters: usize) -> String {
+        match operand {
+            Operand::Immediate(value) => value.to_string(),
+            Operand::Position(address) if self.code.contains(address) => format!("code[{}]", address),
+            Operand::Position(address) => format!("v{}", address),
+            Operand::Indexed(address) => format!("memory[code[{}]]", address),
+            Operand::Relative(offset) => match frame.map(|frame| frame + offset) {
+                Some(0) => "ret".to_string(),
+                Some(slot) if slot > 0 && slot <= parameters as i64 => format!("arg{}", slot),
+                Some(slot) if slot > 0 => format!("local{}", slot),
+                Some(slot) => format!("frame[{}]", slot),
+                None => format!("rb[{}]", offset),
+            },
+        }
+    }
+}
+
+/// The blocks of one function, with calls treated as carrying on at their return address.
+struct Graph {
+    blocks: BTreeMap<i64, Block>,
+    successors: HashMap<i64, Vec<i64>>,
+    dominators: HashMap<i64, HashSet<i64>>,
+    post_dominators: HashMap<i64, HashSet<i64>>,
+    loops: HashMap<i64, Loop>,
+}
+
+struct Block {
+    instructions: Vec<Instruction>,
+    exit: Exit,
+    /// The relative base at the start of the block, relative to the function's entry. Only known
+    /// in called functions.
+    frame: Option<i64>,
+}
+
+struct Loop {
+    body: HashSet<i64>,
+    follow: Option<i64>,
+}
+
+impl Graph {
+    fn new(decompiler: &Decompiler, entry: i64) -> Self {
+        let mut blocks = BTreeMap::new();
+        // Only a call sets up a frame, the relative base of other code isn't tracked
+        let called = decompiler.exits.values().any(|exit| matches!(exit, Exit::Call { target, .. } if *target == entry));
+        let mut pending = vec![(entry, if called { Some(0) } else { None })];
+        let mut leaders = decompiler.leaders();
+        leaders.insert(entry);
+
+        while let Some((start, frame)) = pending.pop() {
+            if blocks.contains_key(&start) {
+                continue;
+            }
+            let mut instructions = Vec::new();
+            let mut address = start;
+            let mut end_frame = frame;
+            let exit = loop {
+                let instruction = match decompiler.instructions.get(&address) {
+                    Some(instruction) => instruction.clone(),
+                    None => break decompiler.exits.get(&address).cloned().unwrap_or(Exit::Halt),
+                };
+                end_frame = adjust_frame(end_frame, &instruction);
+                let next = instruction.next();
+                instructions.push(instruction);
+                if let Some(exit) = decompiler.exits.get(&address) {
+                    break exit.clone();
+                }
+                if leaders.contains(&next) {
+                    break Exit::Next(next);
+                }
+                address = next;
+            };
+
+            let successors = match &exit {
+                Exit::Next(next) | Exit::Jump(next) | Exit::IndirectBranch { next, .. } => vec![*next],
+                Exit::Branch { target, next, .. } => vec![*target, *next],
+                Exit::Call { ret, .. } => vec![*ret],
+                _ => vec![],
+            };
+            for successor in successors {
+                pending.push((successor, end_frame));
+            }
+            blocks.insert(start, Block { instructions, exit, frame });
+        }
+
+        let successors: HashMap<i64, Vec<i64>> = blocks.iter()
+            .map(|(start, block)| {
+                let successors = match &block.exit {
+                    Exit::Next(next) | Exit::Jump(next) | Exit::IndirectBranch { next, .. } => vec![*next],
+                    Exit::Branch { target, next, .. } if target == next => vec![*next],
+                    Exit::Branch { target, next, .. } => vec![*target, *next],
+                    Exit::Call { ret, .. } => vec![*ret],
+                    _ => vec![],
+                };
+                (*start, successors)
+            })
+            .collect();
+
+        let mut graph = Graph {
+            blocks,
+            successors,
+            dominators: HashMap::new(),
+            post_dominators: HashMap::new(),
+            loops: HashMap::new(),
+        };
+        graph.dominators = graph.dominators(entry);
+        graph.post_dominators = graph.post_dominators();
+        graph.loops = graph.loops();
+        graph
+    }
+
+    fn predecessors(&self) -> HashMap<i64, Vec<i64>> {
+        let mut predecessors: HashMap<i64, Vec<i64>> = HashMap::new();
+        for (block, successors) in &self.successors {
+            for successor in successors {
+                predecessors.entry(*successor).or_default().push(*block);
+            }
+        }
+        predecessors
+    }
+
+    fn dominators(&self, entry: i64) -> HashMap<i64, HashSet<i64>> {
+        let all: HashSet<i64> = self.blocks.keys().cloned().collect();
+        let predecessors = self.predecessors();
+        let mut dominators: HashMap<i64, HashSet<i64>> = all.iter()
+            .map(|block| (*block, if *block == entry { [entry].iter().cloned().collect() } else { all.clone() }))
+            .collect();
+        solve_dominators(&mut dominators, &predecessors, &[entry]);
+        dominators
+    }
+
+    /// Post-dominators of the blocks that can reach the end of the function.
+    fn post_dominators(&self) -> HashMap<i64, HashSet<i64>> {
+        let exits: Vec<i64> = self.successors.iter()
+            .filter(|(_, successors)| successors.is_empty())
+            .map(|(block, _)| *block)
+            .collect();
+        let predecessors = self.predecessors();
+
+        let mut reaching: HashSet<i64> = exits.iter().cloned().collect();
+        let mut pending = exits.clone();
+        while let Some(block) = pending.pop() {
+            for predecessor in predecessors.get(&block).into_iter().flatten() {
+                if reaching.insert(*predecessor) {
+                    pending.push(*predecessor);
+                }
+            }
+        }
+
+        let mut post_dominators: HashMap<i64, HashSet<i64>> = reaching.iter()
+            .map(|block| (*block, if exits.contains(block) { [*block].iter().cloned().collect() } else { reaching.clone() }))
+            .collect();
+        let successors: HashMap<i64, Vec<i64>> = self.successors.iter()
+            .map(|(block, successors)| (*block, successors.iter().filter(|successor| reaching.contains(successor)).cloned().collect()))
+            .collect();
+        solve_dominators(&mut post_dominators, &successors, &exits);
+        post_dominators
+    }
+
+    /// The closest block other than `block` that every path from it to the end passes through.
+    fn immediate_post_dominator(&self, block: i64) -> Option<i64> {
+        let post_dominators = self.post_dominators.get(&block)?;
+        post_dominators.iter()
+            .filter(|other| **other != block)
+            .max_by_key(|other| self.post_dominators[*other].len())
+            .cloned()
+    }
+
+    fn loops(&self) -> HashMap<i64, Loop> {
+        let predecessors = self.predecessors();
+        let mut bodies: HashMap<i64, HashSet<i64>> = HashMap::new();
+        for (block, successors) in &self.successors {
+            for header in successors {
+                if !self.dominators.get(block).map_or(false, |dominators| dominators.contains(header)) {
+                    continue;
+                }
+                let body = bodies.entry(*header).or_insert_with(|| [*header].iter().cloned().collect());
+                let mut pending = vec![*block];
+                while let Some(member) = pending.pop() {
+                    if body.insert(member) {
+                        pending.extend(predecessors.get(&member).into_iter().flatten());
+                    }
+                }
+            }
+        }
+
+        bodies.into_iter()
+            .map(|(header, body)| {
+                let exits: BTreeSet<i64> = body.iter()
+                    .flat_map(|block| self.successors[block].iter())
+                    .filter(|successor| !body.contains(successor))
+                    .cloned()
+                    .collect();
+                let follow = self.successors[&header].iter()
+                    .find(|successor| exits.contains(successor))
+                    .or_else(|| exits.iter().next())
+                    .cloned();
+                (header, Loop { body, follow })
+            })
+            .collect()
+    }
+}
+
+/// Iterates the dominator equations over the graph whose predecessors are given, leaving the
+/// sets of the `roots` alone.
+fn solve_dominators(sets: &mut HashMap<i64, HashSet<i64>>, predecessors: &HashMap<i64, Vec<i64>>, roots: &[i64]) {
+    let blocks: BTreeSet<i64> = sets.keys().cloned().collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in &blocks {
+            if roots.contains(block) {
+                continue;
+            }
+            let mut set: Option<HashSet<i64>> = None;
+            for predecessor in predecessors.get(block).into_iter().flatten() {
+                if let Some(other) = sets.get(predecessor) {
+                    set = Some(match set {
+                        Some(set) => set.intersection(other).cloned().collect(),
+                        None => other.clone(),
+                    });
+                }
+            }
+            let mut set = set.unwrap_or_default();
+            set.insert(*block);
+            if set != sets[block] {
+                sets.insert(*block, set);
+                changed = true;
+            }
+        }
+    }
+}
+
+/// The loop being emitted, innermost last.
+struct LoopContext {
+    header: i64,
+    follow: Option<i64>,
+}
+
+struct Structurer<'a, 'b> {
+    decompiler: &'a Decompiler<'b>,
+    graph: &'a Graph,
+    parameters: usize,
+    done: HashSet<i64>,
+    loops: Vec<LoopContext>,
+}
+
+impl<'a, 'b> Structurer<'a, 'b> {
+    /// Emits the blocks from `start` until reaching `stop`.
+    fn region(&mut self, start: i64, stop: Option<i64>) -> Vec<Statement> {
+        let mut statements = Vec::new();
+        let mut current = Some(start);
+        // The header of a loop being entered is emitted as a plain block once
+        let mut entering = None;
+
+        while let Some(block) = current {
+            if Some(block) == stop {
+                break;
+            }
+            if entering != Some(block) {
+                if let Some(context) = self.loops.last() {
+                    if block == context.header {
+                        statements.push(Statement::Continue);
+                        break;
+                    }
+                    if Some(block) == context.follow {
+                        statements.push(Statement::Break);
+                        break;
+                    }
+                    if !self.graph.loops[&context.header].body.contains(&block) {
+                        statements.push(Statement::Goto(block));
+                        break;
+                    }
+                }
+                if self.done.contains(&block) || !self.graph.blocks.contains_key(&block) {
+                    statements.push(Statement::Goto(block));
+                    break;
+                }
+                if let Some(lp) = self.graph.loops.get(&block) {
+                    self.loops.push(LoopContext { header: block, follow: lp.follow });
+                    let body = self.loop_body(block);
+                    self.loops.pop();
+                    statements.push(Statement::Loop(body));
+                    current = lp.follow;
+                    continue;
+                }
+            }
+            entering = None;
+
+            self.done.insert(block);
+            statements.push(Statement::Label(block));
+            let (lines, exit) = self.block(block);
+            statements.extend(lines.into_iter().map(Statement::Line));
+
+            current = match exit {
+                Exit::Next(next) | Exit::Jump(next) | Exit::Call { ret: next, .. } => Some(next),
+                Exit::IndirectBranch { next, .. } => Some(next),
+                Exit::Branch { target, next, .. } if target == next => Some(next),
+                Exit::Branch { condition, if_true, target, next } => {
+                    let condition = self.condition(block, &condition, if_true);
+                    let join = self.graph.immediate_post_dominator(block).filter(|join| {
+                        self.loops.last().map_or(true, |context| self.graph.loops[&context.header].body.contains(join))
+                    });
+                    match join {
+                        Some(join) => {
+                            let then = self.region(target, Some(join));
+                            let otherwise = self.region(next, Some(join));
+                            statements.push(if_statement(condition, then, otherwise));
+                            Some(join)
+                        }
+                        None => {
+                            let then = self.region(target, None);
+                            if then.last().map_or(false, Statement::is_terminal) {
+                                statements.push(if_statement(condition, then, vec![]));
+                                Some(next)
+                            } else {
+                                let otherwise = self.region(next, None);
+                                statements.push(if_statement(condition, then, otherwise));
+                                None
+                            }
+                        }
+                    }
+                }
+                Exit::Return => {
+                    statements.push(Statement::Return);
+                    None
+                }
+                Exit::Halt => {
+                    statements.push(Statement::Halt);
+                    None
+                }
+                Exit::Indirect(_) | Exit::Invalid(_) => {
+                    statements.push(self.stop(block, &exit));
+                    None
+                }
+            };
+        }
+        statements
+    }
+
+    fn loop_body(&mut self, header: i64) -> Vec<Statement> {
+        // Emit the header as a plain block by skipping the checks for the first block
+        let follow = self.graph.loops[&header].follow;
+        let mut statements = Vec::new();
+        self.done.insert(header);
+        statements.push(Statement::Label(header));
+        let (lines, exit) = self.block(header);
+        statements.extend(lines.into_iter().map(Statement::Line));
+        let mut tail = match exit {
+            Exit::Branch { condition, if_true, target, next } if target != next => {
+                let condition = self.condition(header, &condition, if_true);
+                let (inside, exit_on) = if Some(target) == follow {
+                    (next, condition)
+                } else if Some(next) == follow {
+                    (target, condition.negate())
+                } else {
+                    let join = self.graph.immediate_post_dominator(header)
+                        .filter(|join| self.graph.loops[&header].body.contains(join));
+                    let then = self.region(target, join);
+                    let otherwise = self.region(next, join);
+                    statements.push(if_statement(condition, then, otherwise));
+                    statements.extend(join.map(|join| self.region(join, None)).unwrap_or_default());
+                    return statements;
+                };
+                vec![
+                    Statement::If { condition: exit_on, then: vec![Statement::Break], otherwise: vec![] },
+                ].into_iter().chain(self.region(inside, None)).collect()
+            }
+            Exit::Next(next) | Exit::Jump(next) | Exit::Call { ret: next, .. } => self.region(next, None),
+            Exit::Branch { next, .. } | Exit::IndirectBranch { next, .. } => self.region(next, None),
+            Exit::Return => vec![Statement::Return],
+            Exit::Halt => vec![Statement::Halt],
+            Exit::Indirect(_) | Exit::Invalid(_) => vec![self.stop(header, &exit)],
+        };
+        statements.append(&mut tail);
+        statements
+    }
+
+    /// The line ending a block whose exit can't be followed, saying why decompilation stops.
+    fn stop(&self, block: i64, exit: &Exit) -> Statement {
+        let block_info = &self.graph.blocks[&block];
+        Statement::Line(match exit {
+            Exit::Indirect(target) => {
+                let target = self.decompiler.name(target, self.end_frame(block), self.parameters);
+                format!("goto *{}  // unresolved jump, stopping here", target)
+            }
+            Exit::Invalid(word) => {
+                let address = block_info.instructions.last().map_or(block, Instruction::next);
+                if self.decompiler.written.contains(&address) {
+                    format!("invalid {}  // self-modifying: the program writes to {} before running it, stopping here", word, address)
+                } else {
+                    format!("invalid {}", word)
+                }
+            }
+            _ => unreachable!("only indirect and invalid exits stop decompilation"),
+        })
+    }
+
+    fn end_frame(&self, block: i64) -> Option<i64> {
+        let block = &self.graph.blocks[&block];
+        block.instructions.iter().fold(block.frame, adjust_frame)
+    }
+
+    /// The condition for taking a branch at the end of `block`.
+    fn condition(&self, block: i64, operand: &Operand, if_true: bool) -> Condition {
+        let frame = self.end_frame(block);
+        let comparison = self.folded_comparison(block, operand);
+        let condition = match comparison {
+            Some(instruction) => Condition {
+                left: self.decompiler.name(&instruction.operands[0], frame, self.parameters),
+                op: if instruction.op_code == OpCode::LessThan { "<" } else { "==" },
+                right: self.decompiler.name(&instruction.operands[1], frame, self.parameters),
+            },
+            None => Condition {
+                left: self.decompiler.name(operand, frame, self.parameters),
+                op: "==",
+                right: "0".to_string(),
+            },
+        };
+        // A folded comparison holds when its cell is non-zero, a plain operand when it is zero
+        if if_true == comparison.is_some() {
+            condition
+        } else {
+            condition.negate()
+        }
+    }
+
+    /// The comparison computing a branch condition, if it is the instruction just before the
+    /// branch and writes a temporary, so it can be written into the condition instead.
+    fn folded_comparison(&self, block: i64, operand: &Operand) -> Option<&'a Instruction> {
+        let instructions = &self.graph.blocks[&block].instructions;
+        let previous = instructions.get(instructions.len().checked_sub(2)?)?;
+        let cell = match operand {
+            Operand::Position(cell) => cell,
+            _ => return None,
+        };
+        let folds = (previous.op_code == OpCode::LessThan || previous.op_code == OpCode::Equals)
+            && previous.destination() == Some(operand)
+            && self.decompiler.temporaries.contains(cell);
+        if folds {
+            Some(previous)
+        } else {
+            None
+        }
+    }
+
+    /// The statements of a block and how it exits.
+    fn block(&self, block: i64) -> (Vec<String>, Exit) {
+        let block_info = &self.graph.blocks[&block];
+        let mut skip: HashSet<i64> = HashSet::new();
+        let mut call = None;
+
+        match &block_info.exit {
+            Exit::Branch { condition, .. } => {
+                if let Some(comparison) = self.folded_comparison(block, condition) {
+                    skip.insert(comparison.address);
+                }
+            }
+            Exit::Call { target, ret_store, .. } => {
+                if let Some(Operand::Relative(slot)) = self.decompiler.instructions[ret_store].destination() {
+                    let call_address = block_info.instructions.last().map_or(block, |instruction| instruction.address);
+                    let arguments = self.decompiler.call_arguments(call_address, *ret_store, *slot);
+                    skip.insert(*ret_store);
+                    skip.extend(arguments.iter().map(|argument| argument.address));
+                    call = Some((*target, arguments));
+                }
+            }
+            _ => {}
+        }
+
+        let mut frame = block_info.frame;
+        let mut lines = Vec::new();
+        for instruction in &block_info.instructions {
+            if instruction.op_code == OpCode::AdjustRelativeBase {
+                // Constant adjustments of a known frame are absorbed into the names of its cells
+                let absorbed = frame.is_some() && matches!(instruction.operands[0], Operand::Immediate(_));
+                if !absorbed {
+                    lines.push(match &instruction.operands[0] {
+                        Operand::Immediate(value) if value.is_negative() => format!("rb -= {}", -value),
+                        operand => format!("rb += {}", self.decompiler.name(operand, frame, self.parameters)),
+                    });
+                }
+                frame = adjust_frame(frame, instruction);
+                continue;
+            }
+            if skip.contains(&instruction.address) {
+                continue;
+            }
+            if instruction.op_code == OpCode::JumpIfTrue || instruction.op_code == OpCode::JumpIfFalse {
+                if let Some(Exit::IndirectBranch { condition, if_true, target, .. }) = self.decompiler.exits.get(&instruction.address) {
+                    let test = if *if_true { "!=" } else { "==" };
+                    lines.push(format!(
+                        "if {} {} 0 {{ goto *{} }}",
+                        self.decompiler.name(condition, frame, self.parameters),
+                        test,
+                        self.decompiler.name(target, frame, self.parameters)
+                    ));
+                }
+                continue;
+            }
+            if let Some(line) = self.line(instruction, frame) {
+                lines.push(line);
+            }
+        }
+
+        if let Some((target, arguments)) = call {
+            let arguments: Vec<String> = arguments.iter()
+                .map(|argument| self.expression(argument, frame).unwrap_or_default())
+                .collect();
+            lines.push(format!("f{}({})", target, arguments.join(", ")));
+        }
+        (lines, block_info.exit.clone())
+    }
+
+    fn line(&self, instruction: &Instruction, frame: Option<i64>) -> Option<String> {
+        let name = |operand: &Operand| self.decompiler.name(operand, frame, self.parameters);
+        match instruction.op_code {
+            OpCode::Output => Some(format!("output({})", name(&instruction.operands[0]))),
+            OpCode::Halt | OpCode::JumpIfTrue | OpCode::JumpIfFalse | OpCode::AdjustRelativeBase => None,
+            _ => {
+                let destination = name(instruction.destination()?);
+                let expression = self.expression(instruction, frame)?;
+                // Write updates of a cell in place
+                let update = match (instruction.op_code, &instruction.operands[..]) {
+                    (OpCode::Plus, [left, Operand::Immediate(value), _]) if name(left) == destination => {
+                        Some(if value.is_negative() { format!("-= {}", -value) } else { format!("+= {}", value) })
+                    }
+                    (OpCode::Plus, [left, right, _]) if name(left) == destination => Some(format!("+= {}", name(right))),
+                    (OpCode::Times, [left, right, _]) if name(left) == destination && ![-1, 0, 1].iter().any(|value| is_immediate(right, *value)) => Some(format!("*= {}", name(right))),
+                    _ => None,
+                };
+                Some(match update {
+                    Some(update) => format!("{} {}", destination, update),
+                    None => format!("{} = {}", destination, expression),
+                })
+            }
+        }
+    }
+
+    /// The value an instruction writes, simplifying identities.
+    fn expression(&self, instruction: &Instruction, frame: Option<i64>) -> Option<String> {
+        let name = |operand: &Operand| self.decompiler.name(operand, frame, self.parameters);
+        let operands = &instruction.operands;
+        Some(match instruction.op_code {
+            OpCode::Input => "input()".to_string(),
+            OpCode::Plus => match (&operands[0], &operands[1]) {
+                (Operand::Immediate(a), Operand::Immediate(b)) => (a + b).to_string(),
+                (zero, other) | (other, zero) if is_immediate(zero, 0) => name(other),
+                (left, Operand::Immediate(value)) if value.is_negative() => format!("{} - {}", name(left), -value),
+                (left, right) => format!("{} + {}", name(left), name(right)),
+            },
+            OpCode::Times => match (&operands[0], &operands[1]) {
+                (Operand::Immediate(a), Operand::Immediate(b)) => (a * b).to_string(),
+                (zero, _) | (_, zero) if is_immediate(zero, 0) => "0".to_string(),
+                (one, other) | (other, one) if is_immediate(one, 1) => name(other),
+                (minus_one, other) | (other, minus_one) if is_immediate(minus_one, -1) => format!("-{}", name(other)),
+                (left, right) => format!("{} * {}", name(left), name(right)),
+            },
+            OpCode::LessThan => format!("{} < {}", name(&operands[0]), name(&operands[1])),
+            OpCode::Equals => format!("{} == {}", name(&operands[0]), name(&operands[1])),
+            _ => return None,
+        })
+    }
+}
+
+/// The relative base after an instruction, relative to the function's entry, if known.
+fn adjust_frame(frame: Option<i64>, instruction: &Instruction) -> Option<i64> {
+    match (instruction.op_code, &instruction.operands[..]) {
+        (OpCode::AdjustRelativeBase, [Operand::Immediate(offset)]) => Some(frame? + offset.to_i64()?),
+        (OpCode::AdjustRelativeBase, _) => None,
+        _ => frame,
+    }
+}
+
+fn is_immediate(operand: &Operand, value: i64) -> bool {
+    match operand {
+        Operand::Immediate(immediate) => *immediate == BigInt::from(value),
+        _ => false,
+    }
+}
+
+fn if_statement(condition: Condition, then: Vec<Statement>, otherwise: Vec<Statement>) -> Statement {
+    let empty = |statements: &[Statement]| statements.iter().all(|statement| matches!(statement, Statement::Label(_)));
+    if empty(&then) && !empty(&otherwise) {
+        Statement::If { condition: condition.negate(), then: otherwise, otherwise: then }
+    } else {
+        Statement::If { condition, then, otherwise }
+    }
+}
+
+/// Turns loops that test their exit first into `while` loops and drops `continue`s that end a
+/// loop body.
+fn tidy(statements: &mut [Statement]) {
+    for statement in statements.iter_mut() {
+        match statement {
+            Statement::If { then, otherwise, .. } => {
+                tidy(then);
+                tidy(otherwise);
+            }
+            Statement::While { body, .. } => tidy(body),
+            Statement::Loop(body) => {
+                tidy(body);
+                let start = body.iter().position(|statement| !matches!(statement, Statement::Label(_))).unwrap_or(body.len());
+                let exit = match body.get(start) {
+                    Some(Statement::If { condition, then, otherwise }) if then == &[Statement::Break] && otherwise.is_empty() => {
+                        Some(condition.negate())
+                    }
+                    _ => None,
+                };
+                if let Some(condition) = exit {
+                    let mut rest: Vec<Statement> = body.drain(..).enumerate()
+                        .filter(|(i, _)| *i != start)
+                        .map(|(_, statement)| statement)
+                        .collect();
+                    drop_trailing_continue(&mut rest);
+                    *statement = Statement::While { condition, body: rest };
+                } else {
+                    drop_trailing_continue(body);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn drop_trailing_continue(body: &mut Vec<Statement>) {
+    if body.last() == Some(&Statement::Continue) {
+        body.pop();
+    }
+}
+
+impl Display for Decompiled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut targets = HashSet::new();
+        for function in &self.functions {
+            goto_targets(&function.body, &mut targets);
+        }
+
+        for (address, value) in &self.globals {
+            writeln!(f, "v{} = {}", address, value)?;
+        }
+        for function in &self.functions {
+            let parameters: Vec<String> = (1..=function.parameters).map(|i| format!("arg{}", i)).collect();
+            writeln!(f, "\nfn {}({}) {{", function.name, parameters.join(", "))?;
+            write_statements(f, &function.body, 1, &targets)?;
+            writeln!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+fn goto_targets(statements: &[Statement], targets: &mut HashSet<i64>) {
+    for statement in statements {
+        match statement {
+            Statement::Goto(target) => {
+                targets.insert(*target);
+            }
+            Statement::If { then, otherwise, .. } => {
+                goto_targets(then, targets);
+                goto_targets(otherwise, targets);
+            }
+            Statement::While { body, .. } | Statement::Loop(body) => goto_targets(body, targets),
+            _ => {}
+        }
+    }
+}
+
+fn write_statements(f: &mut Formatter<'_>, statements: &[Statement], depth: usize, targets: &HashSet<i64>) -> fmt::Result {
+    let indent = "    ".repeat(depth);
+    for statement in statements {
+        match statement {
+            Statement::Line(line) => writeln!(f, "{}{}", indent, line)?,
+            Statement::If { condition, then, otherwise } => {
+                writeln!(f, "{}if {} {{", indent, condition)?;
+                write_statements(f, then, depth + 1, targets)?;
+                if otherwise.iter().any(|statement| !matches!(statement, Statement::Label(address) if !targets.contains(address))) {
+                    writeln!(f, "{}}} else {{", indent)?;
+                    write_statements(f, otherwise, depth + 1, targets)?;
+                }
+                writeln!(f, "{}}}", indent)?;
+            }
+            Statement::While { condition, body } => {
+                writeln!(f, "{}while {} {{", indent, condition)?;
+                write_statements(f, body, depth + 1, targets)?;
+                writeln!(f, "{}}}", indent)?;
+            }
+            Statement::Loop(body) => {
+                writeln!(f, "{}loop {{", indent)?;
+                write_statements(f, body, depth + 1, targets)?;
+                writeln!(f, "{}}}", indent)?;
+            }
+            Statement::Break => writeln!(f, "{}break", indent)?,
+            Statement::Continue => writeln!(f, "{}continue", indent)?,
+            Statement::Return => writeln!(f, "{}return", indent)?,
+            Statement::Halt => writeln!(f, "{}halt", indent)?,
+            Statement::Goto(target) => writeln!(f, "{}goto L{}", indent, target)?,
+            Statement::Label(address) if targets.contains(address) => writeln!(f, "{}L{}:", &indent[4..], address)?,
+            Statement::Label(_) => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,131 @@
+//! Differences between two `int_code_big::Computer` values.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+use num_bigint::BigInt;
+use num_traits::One;
+
+use crate::int_code_big::{Computer, ComputerState};
+
+/// How a single memory cell differs. Cells are compared as stored in the sparse memory, so a cell
+/// that was never written is reported as added even if it is written with the default zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CellChange {
+    Added(BigInt),
+    Changed(BigInt, BigInt),
+    Removed(BigInt),
+}
+
+impl CellChange {
+    pub fn before(&self) -> Option<&BigInt> {
+        match self {
+            CellChange::Added(_) => None,
+            CellChange::Changed(before, _) | CellChange::Removed(before) => Some(before),
+        }
+    }
+
+    pub fn after(&self) -> Option<&BigInt> {
+        match self {
+            CellChange::Removed(_) => None,
+            CellChange::Changed(_, after) | CellChange::Added(after) => Some(after),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ComputerDiff {
+    pub cells: BTreeMap<BigInt, CellChange>,
+    pub program_counter: Option<(BigInt, BigInt)>,
+    pub relative_base: Option<(BigInt, BigInt)>,
+    pub state: Option<(ComputerState, ComputerState)>,
+}
+
+/// Compares two computers, reporting everything that changed going from `before` to `after`.
+pub fn diff(before: &Computer, after: &Computer) -> ComputerDiff {
+    let mut cells = BTreeMap::new();
+    for (address, value) in before.memory() {
+        match after.memory().get(address) {
+            None => { cells.insert(address.clone(), CellChange::Removed(value.clone())); }
+            Some(other) if other != value => {
+                cells.insert(address.clone(), CellChange::Changed(value.clone(), other.clone()));
+            }
+            Some(_) => {}
+        }
+    }
+    for (address, value) in after.memory() {
+        if !before.memory().contains_key(address) {
+            cells.insert(address.clone(), CellChange::Added(value.clone()));
+        }
+    }
+
+    ComputerDiff {
+        cells,
+        program_counter: changed(before.program_counter(), after.program_counter()),
+        relative_base: changed(before.relative_base(), after.relative_base()),
+        state: changed(before.state(), after.state()),
+    }
+}
+
+fn changed<T: PartialEq + Clone>(before: &T, after: &T) -> Option<(T, T)> {
+    if before == after {
+        None
+    } else {
+        Some((before.clone(), after.clone()))
+    }
+}
+
+impl ComputerDiff {
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty() && self.program_counter.is_none() && self.relative_base.is_none() && self.state.is_none()
+    }
+
+    /// Changed cells split into runs of adjacent addresses, in address order.
+    pub fn groups(&self) -> Vec<Vec<(&BigInt, &CellChange)>> {
+        let mut groups: Vec<Vec<(&BigInt, &CellChange)>> = Vec::new();
+        for (address, change) in &self.cells {
+            match groups.last_mut() {
+                Some(group) if group.last().unwrap().0 + BigInt::one() == *address => group.push((address, change)),
+                _ => groups.push(vec![(address, change)]),
+            }
+        }
+        groups
+    }
+}
+
+/// Renders the diff in a unified diff style, with one `@` header per run of adjacent addresses
+/// followed by the old and new values. `_` marks a cell missing from one side.
+impl Display for ComputerDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        if let Some((before, after)) = &self.program_counter {
+            writeln!(f, "program counter: {} -> {}", before, after)?;
+        }
+        if let Some((before, after)) = &self.relative_base {
+            writeln!(f, "relative base: {} -> {}", before, after)?;
+        }
+        if let Some((before, after)) = &self.state {
+            writeln!(f, "state: {:?} -> {:?}", before, after)?;
+        }
+        for group in self.groups() {
+            let (first, last) = (group[0].0, group[group.len() - 1].0);
+            if first == last {
+                writeln!(f, "@ {}", first)?;
+            } else {
+                writeln!(f, "@ {}..={}", first, last)?;
+            }
+            writeln!(f, "- {}", join(group.iter().map(|(_, change)| change.before())))?;
+            writeln!(f, "+ {}", join(group.iter().map(|(_, change)| change.after())))?;
+        }
+        Ok(())
+    }
+}
+
+fn join<'a, I: Iterator<Item=Option<&'a BigInt>>>(values: I) -> String {
+    values
+        .map(|value| value.map_or_else(|| "_".to_string(), |value| value.to_string()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -0,0 +1,414 @@
+//! Differential testing of `int_code` against `int_code_big` on random programs.
+
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
+
+use crate::int_code;
+use crate::int_code_big::{Computer, ComputerState, OpCode, OpMode};
+
+/// Small deterministic random number generator so runs can be reproduced from a seed.
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> XorShift {
+        XorShift { state: seed.max(1) }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    fn between(&mut self, low: i32, high: i32) -> i32 {
+        low + self.below((high - low + 1) as usize) as i32
+    }
+}
+
+const DATA_CELLS: usize = 4;
+
+/// The highest address a case may write to. The i32 interpreter's memory grows to the highest
+/// address written, so writes much further out only use up memory.
+const MAX_ADDRESS: usize = 1 << 16;
+
+/// Lays out random instructions followed by a halt and a few data cells. Every position
+/// parameter refers to an address inside the program and every jump targets an instruction.
+pub fn generate_program(rng: &mut XorShift) -> Vec<i32> {
+    let num_instructions = 1 + rng.below(10);
+    let op_codes: Vec<i32> = (0..num_instructions).map(|_| 1 + rng.below(8) as i32).collect();
+
+    let mut starts = Vec::new();
+    let mut length = 0;
+    for op_code in &op_codes {
+        starts.push(length);
+        length += 1 + num_params(*op_code);
+    }
+    starts.push(length);
+    let halt = length;
+    let size = halt + 1 + DATA_CELLS;
+
+    let mut program = Vec::with_capacity(size);
+    for op_code in op_codes {
+        let (read_params, write_params) = match op_code {
+            1 | 2 | 7 | 8 => (2, 1),
+            3 => (0, 1),
+            4 => (1, 0),
+            _ => (2, 0),
+        };
+        let is_jump = op_code == 5 || op_code == 6;
+
+        let mut modes = 0;
+        let mut params = Vec::new();
+        for i in 0..read_params {
+            let immediate = rng.below(2) == 0 || (is_jump && i == 1);
+            if immediate {
+                modes += 10i32.pow(i as u32);
+                params.push(if is_jump && i == 1 {
+                    starts[rng.below(starts.len())] as i32
+                } else {
+                    rng.between(-20, 20)
+                });
+            } else {
+                params.push(rng.below(size) as i32);
+            }
+        }
+        for _ in 0..write_params {
+            params.push(rng.below(size) as i32);
+        }
+
+        program.push(modes * 100 + op_code);
+        program.extend(params);
+    }
+    program.push(99);
+    program.extend((0..DATA_CELLS).map(|_| rng.between(-20, 20)));
+    program
+}
+
+pub fn generate_inputs(rng: &mut XorShift, program: &[i32]) -> Vec<i32> {
+    let num_inputs = program.iter().filter(|word| **word % 100 == 3).count() + rng.below(2);
+    (0..num_inputs).map(|_| rng.between(-10, 10)).collect()
+}
+
+fn num_params(op_code: i32) -> usize {
+    match op_code {
+        1 | 2 | 7 | 8 => 3,
+        5 | 6 => 2,
+        3 | 4 => 1,
+        _ => 0,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Halted,
+    OutOfSteps,
+    InputExhausted,
+    /// Stopped before an instruction using relative mode or adjusting the relative base.
+    Unsupported,
+    /// Stopped after writing or outputting a word that doesn't fit in an i32, or before writing
+    /// past `MAX_ADDRESS`.
+    OutOfRange,
+    Error(String),
+}
+
+impl Outcome {
+    /// Errors from the two interpreters have different messages, so only their presence is compared.
+    fn same_kind(&self, other: &Outcome) -> bool {
+        match (self, other) {
+            (Outcome::Error(_), Outcome::Error(_)) => true,
+            _ => self == other,
+        }
+    }
+}
+
+pub struct Run {
+    pub outcome: Outcome,
+    pub outputs: Vec<BigInt>,
+    pub memory: Vec<BigInt>,
+}
+
+/// Which part of the two runs differed. Minimization keeps the signature fixed so it does not
+/// wander off to a different divergence.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Signature {
+    Outcome(String, String),
+    Outputs,
+    Memory,
+}
+
+pub enum Comparison {
+    Agree,
+    OutOfRange,
+    Unsupported,
+    Diverge(Signature),
+}
+
+pub fn compare(program: &[i32], inputs: &[i32], steps: usize) -> Comparison {
+    // The BigInt run goes first as it stops at the first word out of range, where wrapping
+    // arithmetic in a release build could send the i32 run writing far past the program.
+    let big = run_big(program, inputs, steps);
+    match big.outcome {
+        Outcome::OutOfRange => return Comparison::OutOfRange,
+        Outcome::Unsupported => return Comparison::Unsupported,
+        _ => {}
+    }
+
+    let small = run_small(program, inputs, steps);
+    if let Outcome::Error(message) = &small.outcome {
+        if message.contains("overflow") {
+            return Comparison::OutOfRange;
+        }
+    }
+
+    if !small.outcome.same_kind(&big.outcome) {
+        return Comparison::Diverge(Signature::Outcome(outcome_kind(&small.outcome), outcome_kind(&big.outcome)));
+    }
+    if small.outputs != big.outputs {
+        return Comparison::Diverge(Signature::Outputs);
+    }
+    let errored = matches!(small.outcome, Outcome::Error(_));
+    if !errored && !same_memory(&small.memory, &big.memory) {
+        return Comparison::Diverge(Signature::Memory);
+    }
+    Comparison::Agree
+}
+
+/// Coarse classification of an outcome, leaving out error messages.
+fn outcome_kind(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Error(_) => "Error".to_string(),
+        _ => format!("{:?}", outcome),
+    }
+}
+
+/// Whether the memories match, treating words past the end of the shorter one as zero since
+/// the i32 interpreter grows its memory when writing zeros past the end.
+fn same_memory(a: &[BigInt], b: &[BigInt]) -> bool {
+    let zero = BigInt::zero();
+    (0..a.len().max(b.len())).all(|address| a.get(address).unwrap_or(&zero) == b.get(address).unwrap_or(&zero))
+}
+
+struct InputExhausted;
+
+struct FuzzIO {
+    inputs: VecDeque<i32>,
+    outputs: Vec<BigInt>,
+}
+
+impl int_code::IO for FuzzIO {
+    fn get_input(&mut self) -> i32 {
+        match self.inputs.pop_front() {
+            Some(input) => input,
+            None => panic::panic_any(InputExhausted),
+        }
+    }
+
+    fn output(&mut self, output: i32) {
+        self.outputs.push(BigInt::from(output))
+    }
+}
+
+/// Starts of the messages `int_code` panics with when a program is invalid. Arithmetic overflow
+/// is reported too.
+const REPORTED_ERRORS: &[&str] = &["Invalid op", "Invalid mode", "Negative address"];
+
+thread_local! {
+    static QUIET: Cell<bool> = Cell::new(false);
+}
+
+static INSTALL_QUIET_HOOK: Once = Once::new();
+
+/// Wraps the panic hook so reported errors don't print while `QUIET` is set on their thread.
+fn install_quiet_hook() {
+    INSTALL_QUIET_HOOK.call_once(|| {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !QUIET.with(Cell::get) || reported_error(info.payload()).is_none() {
+                hook(info)
+            }
+        }));
+    });
+}
+
+/// The outcome for a panic the i32 interpreter uses to report an error, or `None` for any other
+/// panic.
+fn reported_error(payload: &(dyn Any + Send)) -> Option<Outcome> {
+    if payload.is::<InputExhausted>() {
+        return Some(Outcome::InputExhausted);
+    }
+    let message = match payload.downcast_ref::<String>() {
+        Some(message) => message.clone(),
+        None => payload.downcast_ref::<&str>()?.to_string(),
+    };
+    let reported = message.ends_with("with overflow") || REPORTED_ERRORS.iter().any(|start| message.starts_with(start));
+    if reported {
+        Some(Outcome::Error(message))
+    } else {
+        None
+    }
+}
+
+pub fn run_small(program: &[i32], inputs: &[i32], steps: usize) -> Run {
+    let mut state = int_code::ComputerState::new(program.to_vec());
+    let mut io = FuzzIO { inputs: inputs.iter().cloned().collect(), outputs: Vec::new() };
+
+    // Panics are how the i32 interpreter reports errors, so those are kept quiet here and
+    // reported through the outcome. Any other panic is a bug and is passed on.
+    install_quiet_hook();
+    QUIET.with(|quiet| quiet.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        int_code::execute_computer_with_limit(&mut state, &mut io, &int_code::Ops::new(), Some(steps))
+    }));
+    QUIET.with(|quiet| quiet.set(false));
+    let outcome = match result {
+        Ok(true) => Outcome::Halted,
+        Ok(false) => Outcome::OutOfSteps,
+        Err(payload) => match reported_error(payload.as_ref()) {
+            Some(outcome) => outcome,
+            None => panic::resume_unwind(payload),
+        },
+    };
+
+    Run {
+        outcome,
+        outputs: io.outputs,
+        memory: state.memory().iter().map(|word| BigInt::from(*word)).collect(),
+    }
+}
+
+pub fn run_big(program: &[i32], inputs: &[i32], steps: usize) -> Run {
+    let memory = program.iter().enumerate()
+        .map(|(i, word)| (BigInt::from(i), BigInt::from(*word)))
+        .collect();
+    let mut computer = Computer::from_memory(memory);
+    let mut inputs = inputs.iter();
+    let mut outputs = Vec::new();
+
+    let mut executed = 0;
+    let outcome = loop {
+        let result = match computer.state() {
+            ComputerState::Halted => break Outcome::Halted,
+            ComputerState::Running if executed == steps => break Outcome::OutOfSteps,
+            ComputerState::Running if uses_relative_mode(&computer) => break Outcome::Unsupported,
+            ComputerState::Running => {
+                let written = write_address(&computer);
+                if written.as_ref().map_or(false, |address| *address > BigInt::from(MAX_ADDRESS)) {
+                    break Outcome::OutOfRange;
+                }
+                executed += 1;
+                let result = computer.step();
+                if written.map_or(false, |address| exceeds_i32(&computer[&address])) {
+                    break Outcome::OutOfRange;
+                }
+                result
+            }
+            ComputerState::WaitingForInput => match inputs.next() {
+                Some(input) => computer.input(BigInt::from(*input)),
+                None => break Outcome::InputExhausted,
+            },
+            ComputerState::WaitingToOutput(_) => match computer.output() {
+                Ok(output) if exceeds_i32(&output) => break Outcome::OutOfRange,
+                result => result.map(|output| outputs.push(output)),
+            },
+        };
+        if let Err(err) = result {
+            break Outcome::Error(err.to_string());
+        }
+    };
+
+    Run { outcome, outputs, memory: dense_memory(&computer, program.len()) }
+}
+
+fn exceeds_i32(value: &BigInt) -> bool {
+    value.to_i32().is_none()
+}
+
+/// The address the instruction at the program counter writes to, if it writes at all.
+fn write_address(computer: &Computer) -> Option<BigInt> {
+    let (op_code, modes) = computer.op_code_and_modes().ok()?;
+    match op_code {
+        OpCode::Plus | OpCode::Times | OpCode::LessThan | OpCode::Equals => computer.position_arg(2, &modes).ok(),
+        OpCode::Input => computer.position_arg(0, &modes).ok(),
+        _ => None,
+    }
+}
+
+fn uses_relative_mode(computer: &Computer) -> bool {
+    match computer.op_code_and_modes() {
+        Ok((OpCode::AdjustRelativeBase, _)) => true,
+        Ok((op_code, modes)) => (0..op_code.num_args() as usize).any(|arg| matches!(modes[arg], OpMode::Relative)),
+        Err(_) => false,
+    }
+}
+
+/// Memory from address zero up to the program length or the highest written address.
+fn dense_memory(computer: &Computer, program_len: usize) -> Vec<BigInt> {
+    let len = computer.memory().iter()
+        .filter(|(_, value)| !value.is_zero())
+        .filter_map(|(address, _)| address.to_usize())
+        .map(|address| address + 1)
+        .fold(program_len, usize::max);
+    (0..len).map(|address| computer[&BigInt::from(address)].clone()).collect()
+}
+
+/// Greedily shrinks the inputs and the program while the programs still diverge with the same
+/// signature, by removing inputs and words and by making words smaller.
+pub fn minimize(mut program: Vec<i32>, mut inputs: Vec<i32>, steps: usize, signature: &Signature) -> (Vec<i32>, Vec<i32>) {
+    let still_diverges = |program: &[i32], inputs: &[i32]| match compare(program, inputs, steps) {
+        Comparison::Diverge(found) => found == *signature,
+        _ => false,
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        for i in (0..inputs.len()).rev() {
+            let mut candidate = inputs.clone();
+            candidate.remove(i);
+            if still_diverges(&program, &candidate) {
+                inputs = candidate;
+                changed = true;
+            }
+        }
+
+        for i in (0..program.len()).rev() {
+            let mut candidate = program.clone();
+            candidate.remove(i);
+            if still_diverges(&candidate, &inputs) {
+                program = candidate;
+                changed = true;
+            }
+        }
+
+        for i in 0..program.len() {
+            for smaller in &[0, program[i] / 2] {
+                if *smaller == program[i] {
+                    continue;
+                }
+                let mut candidate = program.clone();
+                candidate[i] = *smaller;
+                if still_diverges(&candidate, &inputs) {
+                    program = candidate;
+                    changed = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    (program, inputs)
+}
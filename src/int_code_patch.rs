@@ -0,0 +1,75 @@
+//! Patches setting memory words of an Intcode program before it runs.
+//!
+//! A patch file has one `address=value` patch per line. Whitespace around either side is
+//! ignored, `#` starts a comment and blank lines are skipped. The same patches can be given
+//! inline, separated by commas, as in `1=12,2=2`, and `@file` names a patch file instead.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use failure_derive::Fail;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch {
+    pub address: BigInt,
+    pub value: BigInt,
+    /// Line of the patch in its file, or position in an inline list, counting from 1.
+    pub line: usize,
+}
+
+#[derive(Debug, Fail)]
+pub enum PatchError {
+    #[fail(display = "Problem reading patch file")]
+    IoError(#[cause] io::Error),
+    #[fail(display = "Line {}: expected address=value but found {:?}", line, text)]
+    InvalidPatch { line: usize, text: String },
+    #[fail(display = "Line {}: patch {}={} is out of range", line, address, value)]
+    OutOfRange { line: usize, address: BigInt, value: BigInt },
+}
+
+/// Reads patches from the file after a leading `@`, or parses them inline otherwise, so that a
+/// single command line option can take either.
+pub fn load_patches(patches: &str) -> Result<Vec<Patch>, PatchError> {
+    if let Some(file) = patches.strip_prefix('@') {
+        read_patches(file)
+    } else {
+        parse_patches(&patches.replace(',', "\n"))
+    }
+}
+
+pub fn read_patches<T: AsRef<Path>>(file: T) -> Result<Vec<Patch>, PatchError> {
+    parse_patches(&fs::read_to_string(file).map_err(PatchError::IoError)?)
+}
+
+pub fn parse_patches(patches: &str) -> Result<Vec<Patch>, PatchError> {
+    let mut parsed = Vec::new();
+    for (i, line) in patches.lines().enumerate() {
+        let text = line.split('#').next().unwrap().trim();
+        if text.is_empty() {
+            continue;
+        }
+        let invalid = || PatchError::InvalidPatch { line: i + 1, text: text.to_string() };
+        let mut parts = text.splitn(2, '=');
+        let address = parts.next().unwrap().trim().parse::<BigInt>().map_err(|_| invalid())?;
+        let value = parts.next().ok_or_else(invalid)?.trim().parse::<BigInt>().map_err(|_| invalid())?;
+        parsed.push(Patch { address, value, line: i + 1 });
+    }
+    Ok(parsed)
+}
+
+/// Converts patches to the address and word types of `int_code`.
+pub fn to_small(patches: &[Patch]) -> Result<Vec<(usize, i32)>, PatchError> {
+    patches.iter()
+        .map(|patch| match (patch.address.to_usize(), patch.value.to_i32()) {
+            (Some(address), Some(value)) => Ok((address, value)),
+            _ => Err(PatchError::OutOfRange {
+                line: patch.line,
+                address: patch.address.clone(),
+                value: patch.value.clone(),
+            }),
+        })
+        .collect()
+}
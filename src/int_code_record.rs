@@ -0,0 +1,293 @@
+//! Logs of the inputs and outputs of an Intcode run, for replaying it later.
+//!
+//! Each line holds one event as `input <instruction> <value>` or `output <instruction> <value>`,
+//! where the instruction is the 1-based count of instructions executed when the event happened.
+//! Blank lines and lines starting with `#` are ignored.
+//!
+//! `RecordingIO` writes a log from either interpreter's IO and `Replayer` checks a run against one.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use failure_derive::Fail;
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
+
+use crate::{int_code, int_code_big};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Input,
+    Output,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub direction: Direction,
+    pub instruction: u64,
+    pub value: BigInt,
+}
+
+#[derive(Debug, Fail)]
+pub enum RecordError {
+    #[fail(display = "Problem reading log")]
+    IoError(#[cause] io::Error),
+    #[fail(display = "Line {}: {}", line, message)]
+    InvalidEvent { line: usize, message: String },
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let direction = match self.direction {
+            Direction::Input => "input",
+            Direction::Output => "output",
+        };
+        write!(f, "{} {} {}", direction, self.instruction, self.value)
+    }
+}
+
+impl FromStr for Event {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let (direction, instruction, value) = match parts.as_slice() {
+            [direction, instruction, value] => (direction, instruction, value),
+            _ => return Err(format!("Expected 3 fields but found {}", parts.len())),
+        };
+        let direction = match *direction {
+            "input" => Direction::Input,
+            "output" => Direction::Output,
+            other => return Err(format!("Unknown event {}", other)),
+        };
+        Ok(Event {
+            direction,
+            instruction: instruction.parse().map_err(|_| format!("Invalid instruction count {}", instruction))?,
+            value: value.parse().map_err(|_| format!("Invalid value {}", value))?,
+        })
+    }
+}
+
+pub fn read_log<T: AsRef<Path>>(file: T) -> Result<Vec<Event>, RecordError> {
+    fs::read_to_string(file)
+        .map_err(RecordError::IoError)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| line.parse().map_err(|message| RecordError::InvalidEvent { line: i + 1, message }))
+        .collect()
+}
+
+/// Writes events to a log as they happen. Every event is flushed straight away so that the log
+/// is complete even if the run is cut short.
+#[derive(Debug)]
+pub struct Recorder {
+    file: File,
+}
+
+impl Recorder {
+    pub fn create<T: AsRef<Path>>(file: T) -> io::Result<Self> {
+        Ok(Recorder { file: File::create(file)? })
+    }
+
+    /// Appends an event, panicking if the log can't be written since the IO traits have no way
+    /// to report errors.
+    pub fn record(&mut self, direction: Direction, instruction: u64, value: BigInt) {
+        let event = Event { direction, instruction, value };
+        writeln!(self.file, "{}", event).expect("Failed to write IO log");
+    }
+}
+
+/// Wraps an IO of either interpreter, logging every input and output.
+pub struct RecordingIO<T> {
+    io: T,
+    recorder: Recorder,
+    instructions: u64,
+}
+
+impl<T> RecordingIO<T> {
+    pub fn new(io: T, recorder: Recorder) -> Self {
+        RecordingIO { io, recorder, instructions: 0 }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.io
+    }
+
+    fn record<V: Into<BigInt>>(&mut self, direction: Direction, value: V) {
+        self.recorder.record(direction, self.instructions, value.into());
+    }
+}
+
+impl<T: int_code::IO> int_code::IO for RecordingIO<T> {
+    fn get_input(&mut self) -> i32 {
+        let input = self.io.get_input();
+        self.record(Direction::Input, input);
+        input
+    }
+
+    fn output(&mut self, output: i32) {
+        self.record(Direction::Output, output);
+        self.io.output(output)
+    }
+
+    fn at_instruction(&mut self, instructions: u64) {
+        self.instructions = instructions;
+        self.io.at_instruction(instructions)
+    }
+}
+
+impl<T: int_code_big::IO> int_code_big::IO for RecordingIO<T> {
+    fn get_input(&mut self) -> BigInt {
+        let input = self.io.get_input();
+        self.record(Direction::Input, input.clone());
+        input
+    }
+
+    fn output(&mut self, output: BigInt) {
+        self.record(Direction::Output, output.clone());
+        self.io.output(output)
+    }
+
+    fn at_instruction(&mut self, instructions: u64) {
+        self.instructions = instructions;
+        self.io.at_instruction(instructions)
+    }
+}
+
+/// Feeds the inputs of a log to a program and checks its IO against the log, stopping at the
+/// first divergence.
+pub struct Replayer {
+    events: Vec<Event>,
+    next: usize,
+    instructions: u64,
+    divergence: Option<String>,
+}
+
+impl Replayer {
+    pub fn new(events: Vec<Event>) -> Self {
+        Replayer { events, next: 0, instructions: 0, divergence: None }
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// The number of events matched so far.
+    pub fn matched(&self) -> usize {
+        self.next
+    }
+
+    /// How the run first differed from the log, if it has.
+    pub fn divergence(&self) -> Option<&str> {
+        self.divergence.as_deref()
+    }
+
+    /// Replays with the `int_code_big` interpreter until the program halts, runs out of
+    /// recorded input or diverges.
+    pub fn replay_big(&mut self, computer: &mut int_code_big::Computer) -> Result<(), int_code_big::ComputerExecutionError> {
+        while self.divergence.is_none() {
+            match computer.state() {
+                int_code_big::ComputerState::Running => {
+                    self.instructions = computer.instructions() + 1;
+                    computer.step()?
+                }
+                int_code_big::ComputerState::WaitingForInput => match self.input() {
+                    Some(input) => computer.input(input)?,
+                    None => break,
+                },
+                int_code_big::ComputerState::WaitingToOutput(_) => {
+                    let output = computer.output()?;
+                    self.output(output)
+                }
+                int_code_big::ComputerState::Halted => {
+                    self.halted();
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replays with the `int_code` interpreter until the program halts, runs out of recorded
+    /// input or diverges.
+    pub fn replay_small(&mut self, state: &mut int_code::ComputerState) {
+        while self.divergence.is_none() {
+            if int_code::execute_computer_with_limit(state, self, &int_code::Ops::new(), Some(1)) {
+                self.halted();
+                break;
+            }
+        }
+    }
+
+    fn input(&mut self) -> Option<BigInt> {
+        let value = self.expect(Direction::Input, None)?;
+        self.next += 1;
+        Some(value)
+    }
+
+    fn output(&mut self, output: BigInt) {
+        if self.expect(Direction::Output, Some(&output)).is_some() {
+            self.next += 1;
+        }
+    }
+
+    fn halted(&mut self) {
+        if self.divergence.is_none() && self.next < self.events.len() {
+            self.divergence = Some(format!(
+                "halted at instruction {} with {} events left",
+                self.instructions,
+                self.events.len() - self.next
+            ));
+        }
+    }
+
+    /// Checks that the next recorded event matches, returning its value or recording the
+    /// divergence.
+    fn expect(&mut self, direction: Direction, output: Option<&BigInt>) -> Option<BigInt> {
+        let actual = match output {
+            Some(output) => format!("output {} at instruction {}", output, self.instructions),
+            None => format!("input at instruction {}", self.instructions),
+        };
+        let event = match self.events.get(self.next) {
+            Some(event) => event,
+            None => {
+                self.divergence = Some(format!("{} after the end of the log", actual));
+                return None;
+            }
+        };
+        let matches = event.direction == direction
+            && event.instruction == self.instructions
+            && output.map_or(true, |output| *output == event.value);
+        if matches {
+            Some(event.value.clone())
+        } else {
+            self.divergence = Some(format!("{} but the log has {}", actual, event));
+            None
+        }
+    }
+}
+
+impl int_code::IO for Replayer {
+    fn get_input(&mut self) -> i32 {
+        match self.input().map(|input| input.to_i32()) {
+            Some(Some(input)) => input,
+            Some(None) => {
+                self.divergence = Some("recorded input does not fit in 32 bits".to_string());
+                Zero::zero()
+            }
+            None => Zero::zero(),
+        }
+    }
+
+    fn output(&mut self, output: i32) {
+        Replayer::output(self, output.into())
+    }
+
+    fn at_instruction(&mut self, instructions: u64) {
+        self.instructions = instructions;
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Mul, Neg, RangeInclusive, Sub};
+
+use failure_derive::Fail;
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+/// Variable indices paired with their exponents, sorted by variable.
+type Monomial = Vec<(usize, u32)>;
+
+/// A polynomial with integer coefficients over numbered variables.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Polynomial {
+    // Never holds zero coefficients
+    terms: BTreeMap<Monomial, BigInt>,
+}
+
+impl Polynomial {
+    pub fn constant<T: Into<BigInt>>(value: T) -> Polynomial {
+        let mut polynomial = Polynomial::default();
+        polynomial.add_term(Vec::new(), value.into());
+        polynomial
+    }
+
+    pub fn variable(index: usize) -> Polynomial {
+        let mut polynomial = Polynomial::default();
+        polynomial.add_term(vec![(index, 1)], One::one());
+        polynomial
+    }
+
+    /// The value of the polynomial if it does not depend on any variable.
+    pub fn as_constant(&self) -> Option<BigInt> {
+        match self.terms.len() {
+            0 => Some(Zero::zero()),
+            1 => self.terms.get(&Vec::new()).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Variables the polynomial depends on, in increasing order.
+    pub fn variables(&self) -> Vec<usize> {
+        let mut variables: Vec<usize> = self.terms.keys()
+            .flat_map(|monomial| monomial.iter().map(|(variable, _)| *variable))
+            .collect();
+        variables.sort();
+        variables.dedup();
+        variables
+    }
+
+    pub fn degree_in(&self, variable: usize) -> u32 {
+        self.terms.keys()
+            .flat_map(|monomial| monomial.iter())
+            .filter(|(v, _)| *v == variable)
+            .map(|(_, exponent)| *exponent)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Evaluates the polynomial with variable `i` set to `values[i]`.
+    pub fn evaluate(&self, values: &[BigInt]) -> BigInt {
+        self.terms.iter()
+            .map(|(monomial, coefficient)| {
+                monomial.iter().fold(coefficient.clone(), |product, (variable, exponent)| {
+                    product * num_traits::pow(values[*variable].clone(), *exponent as usize)
+                })
+            })
+            .fold(Zero::zero(), |sum: BigInt, term| sum + term)
+    }
+
+    /// Replaces `variable` with a constant.
+    pub fn substitute(&self, variable: usize, value: &BigInt) -> Polynomial {
+        let mut result = Polynomial::default();
+        for (monomial, coefficient) in &self.terms {
+            let mut coefficient = coefficient.clone();
+            let mut remaining = Vec::new();
+            for (v, exponent) in monomial {
+                if *v == variable {
+                    coefficient *= num_traits::pow(value.clone(), *exponent as usize);
+                } else {
+                    remaining.push((*v, *exponent));
+                }
+            }
+            result.add_term(remaining, coefficient);
+        }
+        result
+    }
+
+    /// Coefficients of the powers of `variable`, lowest first, if it is the only variable left.
+    pub fn univariate_coefficients(&self, variable: usize) -> Option<Vec<BigInt>> {
+        let mut coefficients = vec![BigInt::zero(); self.degree_in(variable) as usize + 1];
+        for (monomial, coefficient) in &self.terms {
+            match monomial.as_slice() {
+                [] => coefficients[0] += coefficient,
+                [(v, exponent)] if *v == variable => coefficients[*exponent as usize] += coefficient,
+                _ => return None,
+            }
+        }
+        Some(coefficients)
+    }
+
+    /// Formats the polynomial using `names[i]` for variable `i`.
+    pub fn to_string_with(&self, names: &[&str]) -> String {
+        if self.terms.is_empty() {
+            return "0".to_string();
+        }
+
+        // Highest degree terms first, then by variable, reads most naturally
+        let mut terms: Vec<_> = self.terms.iter().collect();
+        terms.sort_by_key(|(monomial, _)| (std::cmp::Reverse(monomial.iter().map(|(_, exponent)| exponent).sum::<u32>()), *monomial));
+
+        let mut out = String::new();
+        for (i, (monomial, coefficient)) in terms.into_iter().enumerate() {
+            if i == 0 {
+                if coefficient.is_negative() {
+                    out.push('-');
+                }
+            } else {
+                out.push_str(if coefficient.is_negative() { " - " } else { " + " });
+            }
+
+            let magnitude = coefficient.abs();
+            let factors: Vec<String> = monomial.iter()
+                .map(|(variable, exponent)| {
+                    let name = names.get(*variable).map_or_else(|| format!("v{}", variable), |name| name.to_string());
+                    if *exponent == 1 { name } else { format!("{}^{}", name, exponent) }
+                })
+                .collect();
+            if factors.is_empty() || !magnitude.is_one() {
+                out.push_str(&magnitude.to_string());
+                if !factors.is_empty() {
+                    out.push('*');
+                }
+            }
+            out.push_str(&factors.join("*"));
+        }
+        out
+    }
+
+    fn add_term(&mut self, monomial: Monomial, coefficient: BigInt) {
+        let sum = self.terms.remove(&monomial).unwrap_or_else(Zero::zero) + coefficient;
+        if !sum.is_zero() {
+            self.terms.insert(monomial, sum);
+        }
+    }
+}
+
+impl Display for Polynomial {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_string_with(&[]))
+    }
+}
+
+impl Add for &Polynomial {
+    type Output = Polynomial;
+
+    fn add(self, other: &Polynomial) -> Polynomial {
+        let mut result = self.clone();
+        for (monomial, coefficient) in &other.terms {
+            result.add_term(monomial.clone(), coefficient.clone());
+        }
+        result
+    }
+}
+
+impl Neg for &Polynomial {
+    type Output = Polynomial;
+
+    fn neg(self) -> Polynomial {
+        Polynomial {
+            terms: self.terms.iter().map(|(monomial, coefficient)| (monomial.clone(), -coefficient)).collect(),
+        }
+    }
+}
+
+impl Sub for &Polynomial {
+    type Output = Polynomial;
+
+    fn sub(self, other: &Polynomial) -> Polynomial {
+        self + &-other
+    }
+}
+
+impl Mul for &Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, other: &Polynomial) -> Polynomial {
+        let mut result = Polynomial::default();
+        for (a, a_coefficient) in &self.terms {
+            for (b, b_coefficient) in &other.terms {
+                let mut exponents: BTreeMap<usize, u32> = a.iter().cloned().collect();
+                for (variable, exponent) in b {
+                    *exponents.entry(*variable).or_insert(0) += exponent;
+                }
+                result.add_term(exponents.into_iter().collect(), a_coefficient * b_coefficient);
+            }
+        }
+        result
+    }
+}
+
+/// A memory cell during symbolic evaluation. Cells read through a symbolic address are `Unknown`,
+/// which is only a problem if the unknown value is used later.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Known(Polynomial),
+    Unknown,
+}
+
+#[derive(Debug, Fail)]
+pub enum SymbolicError {
+    #[fail(display = "Op code at {} depends on the variables", pc)]
+    SymbolicOpCode { pc: usize },
+    #[fail(display = "Instruction at {} writes to an address that depends on the variables", pc)]
+    SymbolicAddress { pc: usize },
+    #[fail(display = "Op code {} at {} is not straight-line code", op, pc)]
+    UnsupportedOpCode { pc: usize, op: BigInt },
+    #[fail(display = "Instruction at {} accesses address {} outside of memory", pc, address)]
+    OutOfRange { pc: usize, address: BigInt },
+    #[fail(display = "Cell {} depends on a value read through a symbolic address", address)]
+    UnknownResult { address: usize },
+    #[fail(display = "Variable {} is at address {} outside of memory", variable, address)]
+    VariableOutOfRange { variable: usize, address: usize },
+    #[fail(display = "Cell {} is outside of memory", address)]
+    CellOutOfRange { address: usize },
+}
+
+/// Runs a program made of add (1), multiply (2) and halt (99) instructions with the cells at
+/// `variables` treated as variables, where `variables[i]` becomes variable `i`. Returns the
+/// symbolic memory after halting.
+pub fn evaluate(memory: &[BigInt], variables: &[usize]) -> Result<Vec<Value>, SymbolicError> {
+    let mut cells: Vec<Value> = memory.iter().map(|word| Value::Known(Polynomial::constant(word.clone()))).collect();
+    for (index, address) in variables.iter().enumerate() {
+        let cell = cells.get_mut(*address)
+            .ok_or(SymbolicError::VariableOutOfRange { variable: index, address: *address })?;
+        *cell = Value::Known(Polynomial::variable(index));
+    }
+
+    let mut pc = 0;
+    loop {
+        let op = match cells.get(pc).map(constant) {
+            Some(Some(op)) => op,
+            Some(None) => return Err(SymbolicError::SymbolicOpCode { pc }),
+            None => return Err(SymbolicError::OutOfRange { pc, address: BigInt::from(pc) }),
+        };
+        if op == BigInt::from(99) {
+            return Ok(cells);
+        }
+        if op != BigInt::one() && op != BigInt::from(2) {
+            return Err(SymbolicError::UnsupportedOpCode { pc, op });
+        }
+
+        let read = |offset: usize| -> Result<Value, SymbolicError> {
+            let address = cells.get(pc + offset).ok_or(SymbolicError::OutOfRange { pc, address: BigInt::from(pc + offset) })?;
+            match constant(address) {
+                Some(address) => address.to_usize()
+                    .and_then(|address| cells.get(address))
+                    .cloned()
+                    .ok_or(SymbolicError::OutOfRange { pc, address }),
+                None => Ok(Value::Unknown),
+            }
+        };
+        let result = match (read(1)?, read(2)?) {
+            (Value::Known(a), Value::Known(b)) => Value::Known(if op.is_one() { &a + &b } else { &a * &b }),
+            _ => Value::Unknown,
+        };
+
+        let target = cells.get(pc + 3).ok_or(SymbolicError::OutOfRange { pc, address: BigInt::from(pc + 3) })?;
+        let target = constant(target).ok_or(SymbolicError::SymbolicAddress { pc })?;
+        match target.to_usize().filter(|target| *target < cells.len()) {
+            Some(target) => cells[target] = result,
+            None => return Err(SymbolicError::OutOfRange { pc, address: target }),
+        }
+        pc += 4;
+    }
+}
+
+/// Evaluates the program and returns `memory[cell]` as a polynomial in the variables.
+pub fn evaluate_cell(memory: &[BigInt], variables: &[usize], cell: usize) -> Result<Polynomial, SymbolicError> {
+    let mut cells = evaluate(memory, variables)?;
+    if cell >= cells.len() {
+        return Err(SymbolicError::CellOutOfRange { address: cell });
+    }
+    match cells.swap_remove(cell) {
+        Value::Known(polynomial) => Ok(polynomial),
+        Value::Unknown => Err(SymbolicError::UnknownResult { address: cell }),
+    }
+}
+
+fn constant(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Known(polynomial) => polynomial.as_constant(),
+        Value::Unknown => None,
+    }
+}
+
+/// Finds every assignment with variable `i` taken from `domains[i]` for which the polynomial
+/// equals `target`. All but the last variable are enumerated; the last is solved for directly
+/// when the polynomial is linear in it.
+pub fn solve(polynomial: &Polynomial, target: &BigInt, domains: &[RangeInclusive<i64>]) -> Vec<Vec<i64>> {
+    let mut solutions = Vec::new();
+    let mut assignment = Vec::with_capacity(domains.len());
+    solve_from(polynomial, target, domains, &mut assignment, &mut solutions);
+    solutions
+}
+
+fn solve_from(polynomial: &Polynomial, target: &BigInt, domains: &[RangeInclusive<i64>], assignment: &mut Vec<i64>, solutions: &mut Vec<Vec<i64>>) {
+    let variable = assignment.len();
+    if variable + 1 < domains.len() {
+        for value in domains[variable].clone() {
+            assignment.push(value);
+            solve_from(&polynomial.substitute(variable, &BigInt::from(value)), target, domains, assignment, solutions);
+            assignment.pop();
+        }
+        return;
+    }
+    if domains.is_empty() {
+        if polynomial.as_constant().as_ref() == Some(target) {
+            solutions.push(Vec::new());
+        }
+        return;
+    }
+
+    let domain = &domains[variable];
+    let coefficients = polynomial.univariate_coefficients(variable)
+        .expect("all other variables have been substituted");
+    let mut push = |value: i64| {
+        let mut solution = assignment.clone();
+        solution.push(value);
+        solutions.push(solution);
+    };
+
+    match coefficients.as_slice() {
+        [constant] => {
+            if constant == target {
+                domain.clone().for_each(push);
+            }
+        }
+        [constant, slope] => {
+            let difference = target - constant;
+            if (&difference % slope).is_zero() {
+                if let Some(value) = (difference / slope).to_i64().filter(|value| domain.contains(value)) {
+                    push(value);
+                }
+            }
+        }
+        _ => {
+            for value in domain.clone() {
+                if polynomial.evaluate(&[vec![BigInt::zero(); variable], vec![BigInt::from(value)]].concat()) == *target {
+                    push(value);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! A gravity simulator for bodies on an integer grid, as in day 12.
+//!
+//! On each step every pair of bodies pulls each other one unit closer along every axis they
+//! differ on, changing their velocities, and then every body moves by its velocity. The axes
+//! never affect each other.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use failure_derive::Fail;
+use gcd::Gcd;
+
+pub const AXES: [char; 3] = ['x', 'y', 'z'];
+
+#[derive(Debug, Fail, PartialEq, Eq)]
+pub enum MoonParseError {
+    #[fail(display = "Line {}: expected <x=..., y=..., z=...> but found {:?}", line, text)]
+    InvalidMoon { line: usize, text: String },
+    #[fail(display = "Line {}: invalid coordinate {:?}", line, text)]
+    InvalidCoordinate { line: usize, text: String },
+    #[fail(display = "Line {}: unknown axis {:?}", line, axis)]
+    UnknownAxis { line: usize, axis: String },
+    #[fail(display = "Line {}: axis {} given twice", line, axis)]
+    DuplicateAxis { line: usize, axis: char },
+    #[fail(display = "Line {}: axis {} missing", line, axis)]
+    MissingAxis { line: usize, axis: char },
+    #[fail(display = "No moons given")]
+    NoMoons,
+}
+
+/// Parses one `<x=-1, y=0, z=2>` moon position per line. The axes can come in any order and
+/// whitespace is allowed anywhere between the parts. `#` starts a comment and blank lines are
+/// skipped, but there must be at least one moon.
+pub fn parse_moons(text: &str) -> Result<Vec<[i64; 3]>, MoonParseError> {
+    let mut moons = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let text = line.split('#').next().unwrap().trim();
+        if !text.is_empty() {
+            moons.push(parse_moon(i + 1, text)?);
+        }
+    }
+    if moons.is_empty() {
+        return Err(MoonParseError::NoMoons);
+    }
+    Ok(moons)
+}
+
+fn parse_moon(line: usize, text: &str) -> Result<[i64; 3], MoonParseError> {
+    let invalid = || MoonParseError::InvalidMoon { line, text: text.to_string() };
+    let inner = text.strip_prefix('<').and_then(|text| text.strip_suffix('>')).ok_or_else(invalid)?;
+
+    let mut position = [None; 3];
+    for coordinate in inner.split(',') {
+        let mut parts = coordinate.splitn(2, '=');
+        let name = parts.next().unwrap().trim();
+        let value = parts.next().ok_or_else(invalid)?.trim();
+        let axis = AXES.iter()
+            .position(|axis| name.len() == 1 && name.starts_with(*axis))
+            .ok_or_else(|| MoonParseError::UnknownAxis { line, axis: name.to_string() })?;
+        if position[axis].is_some() {
+            return Err(MoonParseError::DuplicateAxis { line, axis: AXES[axis] });
+        }
+        let value = value.parse::<i64>()
+            .map_err(|_| MoonParseError::InvalidCoordinate { line, text: value.to_string() })?;
+        position[axis] = Some(value);
+    }
+
+    let mut parsed = [0; 3];
+    for axis in 0..3 {
+        parsed[axis] = position[axis].ok_or(MoonParseError::MissingAxis { line, axis: AXES[axis] })?;
+    }
+    Ok(parsed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Body<const N: usize> {
+    pub position: [i64; N],
+    pub velocity: [i64; N],
+}
+
+impl<const N: usize> Body<N> {
+    /// A body at rest.
+    pub fn new(position: [i64; N]) -> Self {
+        Body { position, velocity: [0; N] }
+    }
+
+    pub fn potential_energy(&self) -> i64 {
+        self.position.iter().map(|coordinate| coordinate.abs()).sum()
+    }
+
+    pub fn kinetic_energy(&self) -> i64 {
+        self.velocity.iter().map(|coordinate| coordinate.abs()).sum()
+    }
+
+    /// Potential times kinetic energy.
+    pub fn energy(&self) -> i64 {
+        self.potential_energy() * self.kinetic_energy()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A header line, then a `step,body,position...,velocity...` line per body per step.
+    Csv,
+    /// A `{"step":0,"bodies":[{"position":[...],"velocity":[...]},...]}` line per step.
+    Jsonl,
+}
+
+/// Bodies moving in `N` dimensions. Equality and hashing only consider the bodies, not the
+/// number of steps taken.
+#[derive(Debug, Clone)]
+pub struct Simulation<const N: usize> {
+    bodies: Vec<Body<N>>,
+    steps: u64,
+}
+
+impl<const N: usize> PartialEq for Simulation<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bodies == other.bodies
+    }
+}
+
+impl<const N: usize> Eq for Simulation<N> {}
+
+impl<const N: usize> std::hash::Hash for Simulation<N> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.bodies.hash(state)
+    }
+}
+
+impl<const N: usize> Simulation<N> {
+    /// Starts with every body at rest.
+    pub fn new(positions: Vec<[i64; N]>) -> Self {
+        Simulation { bodies: positions.into_iter().map(Body::new).collect(), steps: 0 }
+    }
+
+    pub fn bodies(&self) -> &[Body<N>] {
+        &self.bodies
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn step(&mut self) {
+        for i in 0..self.bodies.len() {
+            for j in 0..self.bodies.len() {
+                for axis in 0..N {
+                    let pull = (self.bodies[j].position[axis] - self.bodies[i].position[axis]).signum();
+                    self.bodies[i].velocity[axis] += pull;
+                }
+            }
+        }
+
+        for body in &mut self.bodies {
+            for axis in 0..N {
+                body.position[axis] += body.velocity[axis];
+            }
+        }
+        self.steps += 1;
+    }
+
+    pub fn run(&mut self, steps: u64) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    pub fn total_energy(&self) -> i64 {
+        self.bodies.iter().map(Body::energy).sum()
+    }
+
+    /// The bodies along a single axis, which can be simulated on their own.
+    pub fn axis(&self, axis: usize) -> Simulation<1> {
+        Simulation {
+            bodies: self.bodies.iter()
+                .map(|body| Body { position: [body.position[axis]], velocity: [body.velocity[axis]] })
+                .collect(),
+            steps: self.steps,
+        }
+    }
+
+    /// The number of steps until the bodies are back in their current state. A step can be
+    /// undone by working out the previous velocities from the previous positions, so the first
+    /// state to repeat is always the current one.
+    pub fn period(&self) -> u64 {
+        let mut simulation = self.clone();
+        loop {
+            simulation.step();
+            if simulation == *self {
+                return simulation.steps - self.steps;
+            }
+        }
+    }
+
+    /// The period found from each axis's period on its own, which is far quicker than `period`
+    /// as the axes take much less time to repeat.
+    pub fn period_by_axis(&self) -> u64 {
+        (0..N).map(|axis| self.axis(axis).period()).fold(1, lcm)
+    }
+
+    /// Steps until a state repeats, by remembering every state seen, returning the steps to the
+    /// first state that repeats and to its repeat. Gives up after `max_steps`.
+    ///
+    /// As steps are reversible the first state to repeat is always the current one, so this finds
+    /// the same period as `period` while storing every state on the way. It is only useful as an
+    /// independent check of `period` and `period_by_axis` on small simulations.
+    pub fn find_repeat(&self, max_steps: u64) -> Option<(u64, u64)> {
+        let mut seen = HashMap::new();
+        let mut simulation = self.clone();
+        for _ in 0..=max_steps {
+            if let Some(&first) = seen.get(&simulation) {
+                return Some((first, simulation.steps));
+            }
+            seen.insert(simulation.clone(), simulation.steps);
+            simulation.step();
+        }
+        None
+    }
+
+    /// Writes the current state followed by the state after each of `steps` further steps.
+    pub fn export<W: Write>(&mut self, out: &mut W, format: Format, steps: u64) -> io::Result<()> {
+        if format == Format::Csv {
+            let positions = (0..N).map(|axis| format!("p{}", axis));
+            let velocities = (0..N).map(|axis| format!("v{}", axis));
+            let columns: Vec<String> = positions.chain(velocities).collect();
+            writeln!(out, "step,body,{}", columns.join(","))?;
+        }
+        for step in 0..=steps {
+            if step > 0 {
+                self.step();
+            }
+            match format {
+                Format::Csv => self.write_csv(out)?,
+                Format::Jsonl => self.write_jsonl(out)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for (index, body) in self.bodies.iter().enumerate() {
+            let values: Vec<String> = body.position.iter().chain(&body.velocity).map(i64::to_string).collect();
+            writeln!(out, "{},{},{}", self.steps, index, values.join(","))?;
+        }
+        Ok(())
+    }
+
+    fn write_jsonl<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let list = |values: &[i64; N]| values.iter().map(i64::to_string).collect::<Vec<_>>().join(",");
+        let bodies: Vec<String> = self.bodies.iter()
+            .map(|body| format!("{{\"position\":[{}],\"velocity\":[{}]}}", list(&body.position), list(&body.velocity)))
+            .collect();
+        writeln!(out, "{{\"step\":{},\"bodies\":[{}]}}", self.steps, bodies.join(","))
+    }
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    a / a.gcd(b) * b
+}
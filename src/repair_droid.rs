@@ -0,0 +1,187 @@
+//! The oxygen system repair droid of day 15.
+//!
+//! The droid reads a movement command and replies whether it hit a wall, moved, or moved onto
+//! the oxygen system. Its maze is explored breadth first by cloning the droid's computer at each
+//! newly reached position, so no droid ever has to walk back.
+
+use std::collections::{HashMap, VecDeque};
+
+use enum_primitive_derive::Primitive;
+use failure_derive::Fail;
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, ToPrimitive};
+
+use crate::int_code_big::{Computer, ComputerExecutionError, ComputerState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Movement {
+    North = 1,
+    South = 2,
+    West = 3,
+    East = 4,
+}
+
+pub const MOVEMENTS: [Movement; 4] = [Movement::North, Movement::South, Movement::West, Movement::East];
+
+impl Movement {
+    /// Moves from `position`, with y increasing to the south.
+    pub fn apply(self, (x, y): (i64, i64)) -> (i64, i64) {
+        match self {
+            Movement::North => (x, y - 1),
+            Movement::South => (x, y + 1),
+            Movement::West => (x - 1, y),
+            Movement::East => (x + 1, y),
+        }
+    }
+}
+
+#[derive(Primitive, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Wall = 0,
+    Moved = 1,
+    FoundOxygen = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Wall,
+    Open,
+    Oxygen,
+}
+
+#[derive(Debug, Fail)]
+pub enum DroidError {
+    #[fail(display = "Droid failed")]
+    ExecutionError(#[cause] ComputerExecutionError),
+    #[fail(display = "Invalid status {}", value)]
+    InvalidStatus { value: BigInt },
+    #[fail(display = "Droid halted")]
+    Halted,
+}
+
+impl From<ComputerExecutionError> for DroidError {
+    fn from(err: ComputerExecutionError) -> Self {
+        DroidError::ExecutionError(err)
+    }
+}
+
+/// Sends one movement command to the droid and waits for its status.
+pub fn command(droid: &mut Computer, movement: Movement) -> Result<Status, DroidError> {
+    let mut sent = false;
+    loop {
+        match droid.state() {
+            ComputerState::Running => droid.step()?,
+            ComputerState::WaitingForInput if !sent => {
+                droid.input(BigInt::from(movement as u8))?;
+                sent = true;
+            }
+            ComputerState::WaitingToOutput(_) if sent => {
+                let value = droid.output()?;
+                return value.to_u8()
+                    .and_then(Status::from_u8)
+                    .ok_or(DroidError::InvalidStatus { value });
+            }
+            _ => return Err(DroidError::Halted),
+        }
+    }
+}
+
+/// Maps the whole maze reachable from the droid's starting position, which is the origin.
+pub fn explore(droid: Computer) -> Result<Map, DroidError> {
+    let mut cells = HashMap::new();
+    cells.insert((0, 0), Cell::Open);
+    let mut queue = VecDeque::new();
+    queue.push_back(((0, 0), droid));
+
+    while let Some((position, droid)) = queue.pop_front() {
+        for &movement in MOVEMENTS.iter() {
+            let next = movement.apply(position);
+            if cells.contains_key(&next) {
+                continue;
+            }
+            let mut fork = droid.clone();
+            let cell = match command(&mut fork, movement)? {
+                Status::Wall => Cell::Wall,
+                Status::Moved => Cell::Open,
+                Status::FoundOxygen => Cell::Oxygen,
+            };
+            cells.insert(next, cell);
+            if cell != Cell::Wall {
+                queue.push_back((next, fork));
+            }
+        }
+    }
+
+    Ok(Map { cells })
+}
+
+#[derive(Debug, Clone)]
+pub struct Map {
+    cells: HashMap<(i64, i64), Cell>,
+}
+
+impl Map {
+    /// Unexplored cells are reported as walls.
+    pub fn cell(&self, position: (i64, i64)) -> Cell {
+        self.cells.get(&position).cloned().unwrap_or(Cell::Wall)
+    }
+
+    pub fn oxygen(&self) -> Option<(i64, i64)> {
+        self.cells.iter()
+            .find(|(_, &cell)| cell == Cell::Oxygen)
+            .map(|(&position, _)| position)
+    }
+
+    /// The number of moves from `start` to every open cell reachable from it.
+    pub fn distances(&self, start: (i64, i64)) -> HashMap<(i64, i64), usize> {
+        let mut distances = HashMap::new();
+        distances.insert(start, 0);
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(position) = queue.pop_front() {
+            let distance = distances[&position];
+            for &movement in MOVEMENTS.iter() {
+                let next = movement.apply(position);
+                if self.cell(next) != Cell::Wall && !distances.contains_key(&next) {
+                    distances.insert(next, distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+
+    /// The fewest moves from the droid's starting position to the oxygen system.
+    pub fn oxygen_distance(&self) -> Option<usize> {
+        self.distances((0, 0)).get(&self.oxygen()?).cloned()
+    }
+
+    /// The minutes taken for oxygen to spread from the oxygen system to every open cell, one
+    /// cell per minute.
+    pub fn fill_time(&self) -> Option<usize> {
+        self.distances(self.oxygen()?).values().max().cloned()
+    }
+
+    /// Draws walls as `#`, open cells as `.`, the oxygen system as `O` and the droid's starting
+    /// position as `D`.
+    pub fn render(&self) -> String {
+        let min_x = self.cells.keys().map(|position| position.0).min().unwrap_or(0);
+        let max_x = self.cells.keys().map(|position| position.0).max().unwrap_or(0);
+        let min_y = self.cells.keys().map(|position| position.1).min().unwrap_or(0);
+        let max_y = self.cells.keys().map(|position| position.1).max().unwrap_or(0);
+        let mut map = String::new();
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                map.push(match (self.cells.get(&(x, y)), (x, y) == (0, 0)) {
+                    (_, true) => 'D',
+                    (Some(Cell::Wall), _) => '#',
+                    (Some(Cell::Open), _) => '.',
+                    (Some(Cell::Oxygen), _) => 'O',
+                    (None, _) => ' ',
+                });
+            }
+            map.push('\n');
+        }
+        map
+    }
+}
@@ -0,0 +1,272 @@
+//! The scaffold camera and vacuum robot of day 17.
+//!
+//! The camera program prints the scaffold as ASCII art, with the robot drawn as `^`, `v`, `<` or
+//! `>`. Woken up by patching address 0, the robot reads a main routine of calls to the movement
+//! functions A, B and C, then each function, then whether to show a video feed, and finally
+//! reports the amount of dust it collected as a single non-ASCII output.
+
+use std::fmt;
+
+use failure_derive::Fail;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::int_code_big::{Computer, ComputerExecutionError, ComputerState};
+
+/// Writing 2 here wakes the robot up instead of just running the camera.
+pub const WAKE_ADDRESS: i64 = 0;
+pub const WAKE_VALUE: i64 = 2;
+
+/// The most characters the robot accepts on each routine's line, not counting the newline.
+pub const MAX_ROUTINE_LENGTH: usize = 20;
+
+#[derive(Debug, Fail)]
+pub enum ScaffoldError {
+    #[fail(display = "Robot failed")]
+    ExecutionError(#[cause] ComputerExecutionError),
+    #[fail(display = "Invalid camera output {}", value)]
+    InvalidOutput { value: BigInt },
+    #[fail(display = "No robot in camera view")]
+    NoRobot,
+    #[fail(display = "Path can't be split into three movement functions")]
+    NoRoutines,
+    #[fail(display = "Robot halted without reporting dust")]
+    NoDust,
+}
+
+impl From<ComputerExecutionError> for ScaffoldError {
+    fn from(err: ComputerExecutionError) -> Self {
+        ScaffoldError::ExecutionError(err)
+    }
+}
+
+/// Runs the camera program until it halts and returns everything it printed.
+pub fn read_camera(mut camera: Computer) -> Result<String, ScaffoldError> {
+    let mut text = String::new();
+    loop {
+        match camera.state() {
+            ComputerState::Running => camera.step()?,
+            ComputerState::WaitingToOutput(_) => {
+                let value = camera.output()?;
+                match value.to_u8().filter(u8::is_ascii) {
+                    Some(byte) => text.push(byte as char),
+                    None => return Err(ScaffoldError::InvalidOutput { value }),
+                }
+            }
+            ComputerState::WaitingForInput | ComputerState::Halted => return Ok(text),
+        }
+    }
+}
+
+/// The camera's picture, with x increasing to the right and y increasing downwards.
+#[derive(Debug, Clone)]
+pub struct View {
+    rows: Vec<Vec<u8>>,
+}
+
+impl View {
+    pub fn parse(text: &str) -> Self {
+        View { rows: text.lines().filter(|line| !line.is_empty()).map(|line| line.bytes().collect()).collect() }
+    }
+
+    fn at(&self, (x, y): (i64, i64)) -> u8 {
+        if x < 0 || y < 0 {
+            return b'.';
+        }
+        self.rows.get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .cloned()
+            .unwrap_or(b'.')
+    }
+
+    /// Whether there is scaffold at the position, including underneath the robot.
+    pub fn is_scaffold(&self, position: (i64, i64)) -> bool {
+        matches!(self.at(position), b'#' | b'^' | b'v' | b'<' | b'>')
+    }
+
+    fn positions(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.rows.iter().enumerate()
+            .flat_map(|(y, row)| (0..row.len()).map(move |x| (x as i64, y as i64)))
+    }
+
+    /// Scaffold positions with scaffold on all four sides.
+    pub fn intersections(&self) -> Vec<(i64, i64)> {
+        self.positions()
+            .filter(|&(x, y)| {
+                [(x, y), (x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].iter()
+                    .all(|&position| self.is_scaffold(position))
+            })
+            .collect()
+    }
+
+    /// The sum of each intersection's x times y.
+    pub fn alignment_sum(&self) -> i64 {
+        self.intersections().iter().map(|(x, y)| x * y).sum()
+    }
+
+    /// The robot's position and the direction it faces.
+    pub fn robot(&self) -> Option<((i64, i64), (i64, i64))> {
+        self.positions().find_map(|position| {
+            let direction = match self.at(position) {
+                b'^' => (0, -1),
+                b'v' => (0, 1),
+                b'<' => (-1, 0),
+                b'>' => (1, 0),
+                _ => return None,
+            };
+            Some((position, direction))
+        })
+    }
+
+    /// The moves taking the robot from its position to the end of the scaffold, going straight
+    /// on at every intersection. Only the first move can be without a turn, when the robot
+    /// already faces along the scaffold.
+    pub fn path(&self) -> Result<Vec<Move>, ScaffoldError> {
+        let (mut position, mut direction) = self.robot().ok_or(ScaffoldError::NoRobot)?;
+        let step = |(x, y): (i64, i64), (dx, dy): (i64, i64)| (x + dx, y + dy);
+        let mut moves = Vec::new();
+        loop {
+            let (dx, dy) = direction;
+            let (turn, turned) = if moves.is_empty() && self.is_scaffold(step(position, direction)) {
+                (None, direction)
+            } else if self.is_scaffold(step(position, (dy, -dx))) {
+                (Some(Turn::Left), (dy, -dx))
+            } else if self.is_scaffold(step(position, (-dy, dx))) {
+                (Some(Turn::Right), (-dy, dx))
+            } else {
+                return Ok(moves);
+            };
+            direction = turned;
+            let mut distance = 0;
+            while self.is_scaffold(step(position, direction)) {
+                position = step(position, direction);
+                distance += 1;
+            }
+            moves.push(Move { turn, distance });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    Left,
+    Right,
+}
+
+/// An optional turn followed by moving forward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub turn: Option<Turn>,
+    pub distance: usize,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.turn {
+            Some(Turn::Left) => write!(f, "L,{}", self.distance),
+            Some(Turn::Right) => write!(f, "R,{}", self.distance),
+            None => write!(f, "{}", self.distance),
+        }
+    }
+}
+
+fn join<T: fmt::Display>(items: &[T]) -> String {
+    items.iter().map(|item| item.to_string()).collect::<Vec<_>>().join(",")
+}
+
+/// A main routine of calls to three movement functions, where function 0 is called as A.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Routines {
+    pub main: Vec<usize>,
+    pub functions: [Vec<Move>; 3],
+}
+
+impl Routines {
+    /// Splits a path into a main routine and movement functions which all fit the robot's
+    /// memory, or `None` if there is no way to or the path is empty. The robot wants every
+    /// function defined, so functions the main routine doesn't need repeat A, as they are never
+    /// called and A is known to fit.
+    pub fn compress(path: &[Move]) -> Option<Self> {
+        let mut main = Vec::new();
+        let mut functions = Vec::new();
+        if path.is_empty() || !compress(path, &mut main, &mut functions) {
+            return None;
+        }
+        let first = functions[0];
+        functions.resize(3, first);
+        Some(Routines {
+            main,
+            functions: [functions[0].to_vec(), functions[1].to_vec(), functions[2].to_vec()],
+        })
+    }
+
+    /// The robot's input: each routine on its own line.
+    pub fn lines(&self) -> Vec<String> {
+        let calls: Vec<char> = self.main.iter().map(|&function| (b'A' + function as u8) as char).collect();
+        let mut lines = vec![join(&calls)];
+        lines.extend(self.functions.iter().map(|function| join(function)));
+        lines
+    }
+}
+
+/// Depth first search for routines, trying the functions already defined before defining a new
+/// one from the next moves of the path.
+fn compress<'a>(path: &'a [Move], main: &mut Vec<usize>, functions: &mut Vec<&'a [Move]>) -> bool {
+    if path.is_empty() {
+        return true;
+    }
+    if 2 * main.len() + 1 > MAX_ROUTINE_LENGTH {
+        return false;
+    }
+    for function in 0..functions.len() {
+        if path.starts_with(functions[function]) {
+            main.push(function);
+            if compress(&path[functions[function].len()..], main, functions) {
+                return true;
+            }
+            main.pop();
+        }
+    }
+    if functions.len() < 3 {
+        for length in 1..=path.len() {
+            if join(&path[..length]).len() > MAX_ROUTINE_LENGTH {
+                break;
+            }
+            main.push(functions.len());
+            functions.push(&path[..length]);
+            if compress(&path[length..], main, functions) {
+                return true;
+            }
+            functions.pop();
+            main.pop();
+        }
+    }
+    false
+}
+
+/// Wakes the robot up, feeds it the routines and returns the dust it collected. With `video`
+/// the robot is asked for its video feed, which is ignored.
+pub fn wake(mut robot: Computer, routines: &Routines, video: bool) -> Result<BigInt, ScaffoldError> {
+    robot.patch(BigInt::from(WAKE_ADDRESS), BigInt::from(WAKE_VALUE));
+    let mut input = routines.lines();
+    input.push(if video { "y" } else { "n" }.to_string());
+    let mut input = input.iter().flat_map(|line| line.bytes().chain(Some(b'\n')));
+
+    let mut dust = None;
+    loop {
+        match robot.state() {
+            ComputerState::Running => robot.step()?,
+            ComputerState::WaitingForInput => match input.next() {
+                Some(byte) => robot.input(BigInt::from(byte))?,
+                None => return Err(ScaffoldError::NoDust),
+            },
+            ComputerState::WaitingToOutput(_) => {
+                let value = robot.output()?;
+                if value.to_u8().map_or(true, |byte| !byte.is_ascii()) {
+                    dust = Some(value);
+                }
+            }
+            ComputerState::Halted => return dust.ok_or(ScaffoldError::NoDust),
+        }
+    }
+}
@@ -0,0 +1,441 @@
+//! The springdroid of day 21.
+//!
+//! The droid is programmed in springscript: up to 15 `AND`, `OR` and `NOT` instructions reading
+//! the hull sensors A to I, which see ground 1 to 9 tiles ahead, and the registers T and J, and
+//! writing T or J. The script ends with `WALK`, which only powers sensors A to D, or `RUN`. At
+//! every tile the script runs with T and J false and the droid jumps four tiles ahead if J ends
+//! up true. A droid that makes it across reports the hull damage as a single non-ASCII output,
+//! while one that falls prints an ASCII animation of its last moments.
+
+use std::fmt;
+use std::str::FromStr;
+
+use failure_derive::Fail;
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+
+use crate::int_code_big::{Computer, ComputerExecutionError, ComputerState};
+
+pub const MAX_INSTRUCTIONS: usize = 15;
+
+/// Limits on the jump conditions tried by `search`, as clauses of sensor readings that must all
+/// hold for the droid to jump.
+pub const MAX_CLAUSES: usize = 3;
+pub const MAX_CLAUSE_SENSORS: usize = 3;
+
+pub const SENSORS: [Register; 9] = [
+    Register::A, Register::B, Register::C, Register::D, Register::E,
+    Register::F, Register::G, Register::H, Register::I,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    A, B, C, D, E, F, G, H, I,
+    T,
+    J,
+}
+
+impl Register {
+    pub fn is_writable(self) -> bool {
+        self == Register::T || self == Register::J
+    }
+
+    /// How many tiles ahead a sensor looks, or `None` for T and J.
+    pub fn distance(self) -> Option<usize> {
+        SENSORS.iter().position(|&sensor| sensor == self).map(|index| index + 1)
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for Register {
+    type Err = SpringdroidError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "T" => Ok(Register::T),
+            "J" => Ok(Register::J),
+            _ => SENSORS.iter()
+                .find(|sensor| sensor.to_string() == s)
+                .cloned()
+                .ok_or_else(|| SpringdroidError::InvalidRegister { name: s.to_string() }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    And,
+    Or,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub op: Op,
+    pub source: Register,
+    pub target: Register,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.op {
+            Op::And => "AND",
+            Op::Or => "OR",
+            Op::Not => "NOT",
+        };
+        write!(f, "{} {} {}", op, self.source, self.target)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Walk,
+    Run,
+}
+
+impl Mode {
+    /// The sensors powered in this mode.
+    pub fn sensors(self) -> &'static [Register] {
+        match self {
+            Mode::Walk => &SENSORS[..4],
+            Mode::Run => &SENSORS,
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mode::Walk => write!(f, "WALK"),
+            Mode::Run => write!(f, "RUN"),
+        }
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum SpringdroidError {
+    #[fail(display = "Droid failed")]
+    ExecutionError(#[cause] ComputerExecutionError),
+    #[fail(display = "Invalid register {:?}", name)]
+    InvalidRegister { name: String },
+    #[fail(display = "Line {}: invalid instruction {:?}", line, text)]
+    InvalidInstruction { line: usize, text: String },
+    #[fail(display = "Script must end with WALK or RUN")]
+    MissingMode,
+    #[fail(display = "Script has {} instructions but at most {} fit", count, max)]
+    TooManyInstructions { count: usize, max: usize },
+    #[fail(display = "Instruction {} writes to read only register {}", index, register)]
+    NotWritable { index: usize, register: Register },
+    #[fail(display = "Instruction {} reads sensor {} which isn't powered in {} mode", index, register, mode)]
+    UnpoweredSensor { index: usize, register: Register, mode: Mode },
+    #[fail(display = "Invalid droid output {}", value)]
+    InvalidOutput { value: BigInt },
+}
+
+impl From<ComputerExecutionError> for SpringdroidError {
+    fn from(err: ComputerExecutionError) -> Self {
+        SpringdroidError::ExecutionError(err)
+    }
+}
+
+/// A springscript program, built with `and`, `or` and `not`:
+///
+/// ```ignore
+/// let script = Script::new(Mode::Walk).not(Register::A, Register::J);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    pub instructions: Vec<Instruction>,
+    pub mode: Mode,
+}
+
+impl Script {
+    pub fn new(mode: Mode) -> Self {
+        Script { instructions: Vec::new(), mode }
+    }
+
+    pub fn and(self, source: Register, target: Register) -> Self {
+        self.push(Op::And, source, target)
+    }
+
+    pub fn or(self, source: Register, target: Register) -> Self {
+        self.push(Op::Or, source, target)
+    }
+
+    pub fn not(self, source: Register, target: Register) -> Self {
+        self.push(Op::Not, source, target)
+    }
+
+    fn push(mut self, op: Op, source: Register, target: Register) -> Self {
+        self.instructions.push(Instruction { op, source, target });
+        self
+    }
+
+    /// Parses one instruction per line followed by `WALK` or `RUN`, skipping blank lines.
+    pub fn parse(text: &str) -> Result<Self, SpringdroidError> {
+        let mut instructions = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let invalid = || SpringdroidError::InvalidInstruction { line: i + 1, text: line.to_string() };
+            let op = match words.as_slice() {
+                [] => continue,
+                ["WALK"] => return Ok(Script { instructions, mode: Mode::Walk }),
+                ["RUN"] => return Ok(Script { instructions, mode: Mode::Run }),
+                ["AND", _, _] => Op::And,
+                ["OR", _, _] => Op::Or,
+                ["NOT", _, _] => Op::Not,
+                _ => return Err(invalid()),
+            };
+            let source = words[1].parse().map_err(|_| invalid())?;
+            let target = words[2].parse().map_err(|_| invalid())?;
+            instructions.push(Instruction { op, source, target });
+        }
+        Err(SpringdroidError::MissingMode)
+    }
+
+    /// Checks the script fits in the droid, only writes T and J and only reads powered sensors.
+    pub fn validate(&self) -> Result<(), SpringdroidError> {
+        if self.instructions.len() > MAX_INSTRUCTIONS {
+            return Err(SpringdroidError::TooManyInstructions { count: self.instructions.len(), max: MAX_INSTRUCTIONS });
+        }
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            if !instruction.target.is_writable() {
+                return Err(SpringdroidError::NotWritable { index, register: instruction.target });
+            }
+            let source = instruction.source;
+            if !source.is_writable() && !self.mode.sensors().contains(&source) {
+                return Err(SpringdroidError::UnpoweredSensor { index, register: source, mode: self.mode });
+            }
+        }
+        Ok(())
+    }
+
+    /// The droid's ASCII input for the script, after validating it.
+    pub fn compile(&self) -> Result<String, SpringdroidError> {
+        self.validate()?;
+        Ok(self.to_string())
+    }
+
+    /// Runs the script for the sensor readings, where bit n of `ground` is set if sensor n + 1
+    /// tiles ahead sees ground, returning whether the droid jumps.
+    pub fn jumps(&self, ground: u16) -> bool {
+        let (mut t, mut j) = (false, false);
+        for instruction in &self.instructions {
+            let source = match instruction.source {
+                Register::T => t,
+                Register::J => j,
+                sensor => ground & (1 << (sensor.distance().unwrap() - 1)) != 0,
+            };
+            let target = if instruction.target == Register::T { &mut t } else { &mut j };
+            *target = match instruction.op {
+                Op::And => source && *target,
+                Op::Or => source || *target,
+                Op::Not => !source,
+            };
+        }
+        j
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instruction in &self.instructions {
+            writeln!(f, "{}", instruction)?;
+        }
+        writeln!(f, "{}", self.mode)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The droid made it across and reported the hull damage.
+    Damage(BigInt),
+    /// The droid fell into space, with the animation it printed.
+    Fell(String),
+}
+
+impl Outcome {
+    /// The hull in the first frame of a fall's animation, as whether there is ground at each
+    /// tile starting from the droid's.
+    pub fn hull(&self) -> Option<Vec<bool>> {
+        let animation = match self {
+            Outcome::Damage(_) => return None,
+            Outcome::Fell(animation) => animation,
+        };
+        animation.split("\n\n")
+            .map(|frame| frame.trim_matches('\n'))
+            .find(|frame| frame.lines().any(|line| line.contains('@')))?
+            .lines()
+            .last()
+            .map(|row| row.chars().map(|tile| tile == '#').collect())
+    }
+}
+
+/// Programs the droid with the script and runs it.
+pub fn run(mut droid: Computer, script: &Script) -> Result<Outcome, SpringdroidError> {
+    let mut input = script.compile()?.into_bytes().into_iter();
+    let mut animation = String::new();
+    loop {
+        match droid.state() {
+            ComputerState::Running => droid.step()?,
+            ComputerState::WaitingForInput => match input.next() {
+                Some(byte) => droid.input(BigInt::from(byte))?,
+                None => return Ok(Outcome::Fell(animation)),
+            },
+            ComputerState::WaitingToOutput(_) => {
+                let value = droid.output()?;
+                match value.to_u8().filter(u8::is_ascii) {
+                    Some(byte) => animation.push(byte as char),
+                    None if value.is_positive() => return Ok(Outcome::Damage(value)),
+                    None => return Err(SpringdroidError::InvalidOutput { value }),
+                }
+            }
+            ComputerState::Halted => return Ok(Outcome::Fell(animation)),
+        }
+    }
+}
+
+/// At least one of the sensors seeing ground, or with `negated`, at least one seeing a hole. The
+/// sensors are a bit mask as in `Script::jumps`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Clause {
+    sensors: u16,
+    negated: bool,
+}
+
+impl Clause {
+    fn registers(self) -> Vec<Register> {
+        SENSORS.iter().enumerate()
+            .filter(|(index, _)| self.sensors & (1 << index) != 0)
+            .map(|(_, &sensor)| sensor)
+            .collect()
+    }
+
+    /// Instructions leaving the clause in `target`, which may hold anything beforehand.
+    fn compile(self, script: Script, target: Register) -> Script {
+        let registers = self.registers();
+        let mut script = script.not(registers[0], target);
+        if registers.len() == 1 && self.negated {
+            return script;
+        }
+        script = script.not(target, target);
+        for &register in &registers[1..] {
+            script = if self.negated { script.and(register, target) } else { script.or(register, target) };
+        }
+        if self.negated { script.not(target, target) } else { script }
+    }
+}
+
+/// A jump condition as clauses that must all hold.
+fn condition_script(clauses: &[Clause], mode: Mode) -> Script {
+    let mut script = clauses[0].compile(Script::new(mode), Register::J);
+    for &clause in &clauses[1..] {
+        let registers = clause.registers();
+        script = match (registers.as_slice(), clause.negated) {
+            ([register], false) => script.and(*register, Register::J),
+            _ => clause.compile(script, Register::T).and(Register::T, Register::J),
+        };
+    }
+    script
+}
+
+/// Whether a droid running the script makes it across the hull, where tiles past the end are
+/// taken to be ground.
+fn crosses(script: &Script, hull: &[bool]) -> bool {
+    let ground_at = |tile: usize| hull.get(tile).cloned().unwrap_or(true);
+    let mut position = 0;
+    while position < hull.len() {
+        let ground = (0..SENSORS.len())
+            .filter(|&index| ground_at(position + index + 1))
+            .fold(0, |ground, index| ground | 1 << index);
+        position += if script.jumps(ground) { 4 } else { 1 };
+        if !ground_at(position) {
+            return false;
+        }
+    }
+    true
+}
+
+/// All the clauses over the mode's sensors, smallest first.
+fn all_clauses(mode: Mode) -> Vec<Clause> {
+    let sensors = mode.sensors().len();
+    let mut clauses: Vec<Clause> = (1..(1u16 << sensors))
+        .filter(|mask| mask.count_ones() as usize <= MAX_CLAUSE_SENSORS)
+        .flat_map(|sensors| vec![Clause { sensors, negated: false }, Clause { sensors, negated: true }])
+        .collect();
+    clauses.sort_by_key(|clause| clause.sensors.count_ones());
+    clauses
+}
+
+/// Calls `found` with every set of up to `MAX_CLAUSES` clauses using `sensors` sensors in total,
+/// until it returns a value.
+fn each_condition<T>(
+    clauses: &[Clause],
+    sensors: u32,
+    chosen: &mut Vec<Clause>,
+    found: &mut dyn FnMut(&[Clause]) -> Option<T>,
+) -> Option<T> {
+    if sensors == 0 {
+        return if chosen.is_empty() { None } else { found(chosen) };
+    }
+    if chosen.len() == MAX_CLAUSES {
+        return None;
+    }
+    for (index, &clause) in clauses.iter().enumerate() {
+        let size = clause.sensors.count_ones();
+        if size > sensors {
+            break;
+        }
+        chosen.push(clause);
+        let result = each_condition(&clauses[index + 1..], sensors - size, chosen, found);
+        chosen.pop();
+        if result.is_some() {
+            return result;
+        }
+    }
+    None
+}
+
+/// Searches for a script that gets the droid across, returning it with the hull damage.
+pub fn search(droid: &Computer, mode: Mode) -> Result<Option<(Script, BigInt)>, SpringdroidError> {
+    search_with(mode, |script| run(droid.clone(), script))
+}
+
+/// Searches for a script that `attempt` reports making it across.
+///
+/// Jump conditions are tried smallest first. Each is compiled and its script simulated with
+/// `Script::jumps` on the hulls fallen on so far before it is attempted, and every new fall adds
+/// its hull to those checked, so only scripts which might work are attempted.
+pub fn search_with<F>(mode: Mode, mut attempt: F) -> Result<Option<(Script, BigInt)>, SpringdroidError>
+where
+    F: FnMut(&Script) -> Result<Outcome, SpringdroidError>,
+{
+    let clauses = all_clauses(mode);
+    let mut hulls: Vec<Vec<bool>> = Vec::new();
+    let max_sensors = (MAX_CLAUSES * MAX_CLAUSE_SENSORS) as u32;
+    for sensors in 1..=max_sensors {
+        let found = each_condition(&clauses, sensors, &mut Vec::new(), &mut |condition| {
+            let script = condition_script(condition, mode);
+            if script.instructions.len() > MAX_INSTRUCTIONS || !hulls.iter().all(|hull| crosses(&script, hull)) {
+                return None;
+            }
+            match attempt(&script) {
+                Ok(Outcome::Damage(damage)) => Some(Ok((script, damage))),
+                Ok(fell) => {
+                    hulls.extend(fell.hull());
+                    None
+                }
+                Err(err) => Some(Err(err)),
+            }
+        });
+        if let Some(found) = found {
+            return found.map(Some);
+        }
+    }
+    Ok(None)
+}
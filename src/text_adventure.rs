@@ -0,0 +1,356 @@
+//! The cryostasis droid text adventure of day 25.
+//!
+//! The droid prints a description of each room it enters, then `Command?` when it wants a line
+//! of input, such as a direction to move, `take <item>` or `drop <item>`. Walking onto the
+//! pressure-sensitive floor while carrying the wrong weight ejects the droid back to the security
+//! checkpoint; with the right items it gets the airlock password and the program halts.
+
+use std::collections::{HashMap, HashSet};
+
+use failure_derive::Fail;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use regex::Regex;
+
+use crate::int_code_big::{Computer, ComputerExecutionError, ComputerState};
+
+/// Items that end the game or trap the droid when taken.
+pub const DANGEROUS_ITEMS: [&str; 5] = ["escape pod", "giant electromagnet", "infinite loop", "molten lava", "photons"];
+
+/// Steps a single command may take before the droid is assumed to be stuck in a loop.
+pub const MAX_STEPS_PER_COMMAND: u64 = 1_000_000;
+
+/// Responses to taking an item that mean the droid is trapped even though it keeps running.
+pub const TRAP_MESSAGES: [&str; 2] = ["You can't move", "It is suddenly completely dark"];
+
+/// Part of the response when the pressure-sensitive floor sends the droid back to the checkpoint.
+pub const EJECTED_MESSAGE: &str = "ejected back to the checkpoint";
+
+/// The direction leading back, for the compass directions the droid understands.
+pub fn opposite(direction: &str) -> &str {
+    match direction {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        "west" => "east",
+        other => other,
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum AdventureError {
+    #[fail(display = "Droid failed")]
+    ExecutionError(#[cause] ComputerExecutionError),
+    #[fail(display = "Invalid droid output {}", value)]
+    InvalidOutput { value: BigInt },
+    #[fail(display = "Droid halted:\n{}", output)]
+    Halted { output: String },
+    #[fail(display = "Droid took more than {} steps for a command", steps)]
+    StepLimit { steps: u64 },
+    #[fail(display = "Expected a room description but found:\n{}", output)]
+    NoRoom { output: String },
+    #[fail(display = "Never found the pressure-sensitive floor")]
+    NoCheckpoint,
+    #[fail(display = "No combination of items gets past the pressure-sensitive floor")]
+    NoCombination,
+}
+
+impl From<ComputerExecutionError> for AdventureError {
+    fn from(err: ComputerExecutionError) -> Self {
+        AdventureError::ExecutionError(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Room {
+    pub name: String,
+    pub description: String,
+    pub doors: Vec<String>,
+    pub items: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Description,
+    Doors,
+    Items,
+    Other,
+}
+
+/// Parses the last room described in the output, as being ejected from the pressure-sensitive
+/// floor describes both it and the checkpoint.
+pub fn parse_room(output: &str) -> Option<Room> {
+    let start = output.rfind("== ")?;
+    let mut lines = output[start..].lines();
+    let name = lines.next()?.trim().trim_start_matches("== ").trim_end_matches(" ==").to_string();
+
+    let mut room = Room { name, description: String::new(), doors: Vec::new(), items: Vec::new() };
+    let mut section = Section::Description;
+    for line in lines {
+        match (line.trim(), line.trim().strip_prefix("- "), section) {
+            ("Command?", _, _) => break,
+            ("Doors here lead:", _, _) => section = Section::Doors,
+            ("Items here:", _, _) => section = Section::Items,
+            ("", _, Section::Description) => {}
+            ("", _, _) => section = Section::Other,
+            (_, Some(door), Section::Doors) => room.doors.push(door.to_string()),
+            (_, Some(item), Section::Items) => room.items.push(item.to_string()),
+            (text, _, Section::Description) => {
+                if !room.description.is_empty() {
+                    room.description.push('\n');
+                }
+                room.description.push_str(text);
+            }
+            _ => {}
+        }
+    }
+    Some(room)
+}
+
+/// A droid that takes commands a line at a time. Cloning it saves its state, so the adventure
+/// can go back to a clone when something goes wrong.
+pub trait Terminal: Clone {
+    /// Runs until the droid wants a command or halts, returning what it printed.
+    fn start(&mut self) -> Result<String, AdventureError>;
+
+    /// Sends a command and returns the droid's response.
+    fn command(&mut self, command: &str) -> Result<String, AdventureError>;
+
+    fn is_halted(&self) -> bool;
+}
+
+/// The droid's program, run a command at a time.
+#[derive(Clone)]
+pub struct Droid {
+    computer: Computer,
+}
+
+impl Droid {
+    pub fn new(computer: Computer) -> Self {
+        Droid { computer }
+    }
+
+    fn run(&mut self, line: &[u8]) -> Result<String, AdventureError> {
+        let mut input = line.iter();
+        let mut output = String::new();
+        let mut steps = 0;
+        loop {
+            match self.computer.state() {
+                ComputerState::Running => {
+                    if steps == MAX_STEPS_PER_COMMAND {
+                        return Err(AdventureError::StepLimit { steps });
+                    }
+                    self.computer.step()?;
+                    steps += 1;
+                }
+                ComputerState::WaitingForInput => match input.next() {
+                    Some(&byte) => self.computer.input(BigInt::from(byte))?,
+                    None => return Ok(output),
+                },
+                ComputerState::WaitingToOutput(_) => {
+                    let value = self.computer.output()?;
+                    match value.to_u8().filter(u8::is_ascii) {
+                        Some(byte) => output.push(byte as char),
+                        None => return Err(AdventureError::InvalidOutput { value }),
+                    }
+                }
+                ComputerState::Halted => return Ok(output),
+            }
+        }
+    }
+}
+
+impl Terminal for Droid {
+    fn start(&mut self) -> Result<String, AdventureError> {
+        self.run(&[])
+    }
+
+    fn command(&mut self, command: &str) -> Result<String, AdventureError> {
+        let mut line = command.as_bytes().to_vec();
+        line.push(b'\n');
+        self.run(&line)
+    }
+
+    fn is_halted(&self) -> bool {
+        *self.computer.state() == ComputerState::Halted
+    }
+}
+
+/// The way through the pressure-sensitive floor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub items: Vec<String>,
+    pub password: Option<String>,
+    pub message: String,
+}
+
+/// Explores the ship collecting items, then finds which items get past the pressure-sensitive
+/// floor.
+pub struct Adventure<T = Droid> {
+    droid: T,
+    dangerous: HashSet<String>,
+    rooms: HashMap<String, Room>,
+    inventory: Vec<String>,
+    /// The directions from the start to the security checkpoint and then onto the floor.
+    checkpoint: Option<(Vec<String>, String)>,
+}
+
+impl Adventure<Droid> {
+    pub fn new(computer: Computer) -> Self {
+        Adventure::with_droid(Droid::new(computer))
+    }
+}
+
+impl<T: Terminal> Adventure<T> {
+    pub fn with_droid(droid: T) -> Self {
+        Adventure {
+            droid,
+            dangerous: DANGEROUS_ITEMS.iter().map(|item| item.to_string()).collect(),
+            rooms: HashMap::new(),
+            inventory: Vec::new(),
+            checkpoint: None,
+        }
+    }
+
+    /// Marks an item to leave alone, on top of `DANGEROUS_ITEMS`.
+    pub fn avoid(&mut self, item: &str) {
+        self.dangerous.insert(item.to_string());
+    }
+
+    pub fn rooms(&self) -> &HashMap<String, Room> {
+        &self.rooms
+    }
+
+    pub fn inventory(&self) -> &[String] {
+        &self.inventory
+    }
+
+    /// Items found to be dangerous, whether known beforehand or discovered by taking them.
+    pub fn dangerous(&self) -> &HashSet<String> {
+        &self.dangerous
+    }
+
+    /// Explores the whole ship and solves the pressure-sensitive floor.
+    pub fn solve(&mut self) -> Result<Solution, AdventureError> {
+        let output = self.droid.start()?;
+        let room = parse_room(&output).ok_or(AdventureError::NoRoom { output })?;
+        self.explore(room, &mut Vec::new())?;
+
+        let (path, floor) = self.checkpoint.clone().ok_or(AdventureError::NoCheckpoint)?;
+        for direction in &path {
+            self.go(direction)?;
+        }
+        self.try_combinations(&floor)
+    }
+
+    /// Walks every room reachable from `room` depth first, taking the safe items, and returns
+    /// to `room`. `path` holds the directions taken from the start.
+    fn explore(&mut self, room: Room, path: &mut Vec<String>) -> Result<(), AdventureError> {
+        for item in &room.items {
+            if !self.dangerous.contains(item) {
+                self.take(item)?;
+            }
+        }
+        self.rooms.insert(room.name.clone(), room.clone());
+
+        let back = path.last().map(|direction| opposite(direction).to_string());
+        for door in &room.doors {
+            if back.as_ref() == Some(door) {
+                continue;
+            }
+            let (next, ejected) = self.go(door)?;
+            if ejected {
+                self.checkpoint = Some((path.clone(), door.clone()));
+                continue;
+            }
+            if self.rooms.contains_key(&next.name) {
+                self.go(opposite(door))?;
+                continue;
+            }
+            path.push(door.clone());
+            self.explore(next, path)?;
+            path.pop();
+            self.go(opposite(door))?;
+        }
+        Ok(())
+    }
+
+    /// Takes an item, putting the droid back as it was and marking the item as dangerous if
+    /// taking it halts the droid, traps it or sends it into a loop.
+    fn take(&mut self, item: &str) -> Result<(), AdventureError> {
+        let saved = self.droid.clone();
+        match self.droid.command(&format!("take {}", item)) {
+            Ok(output) if !self.droid.is_halted() && !TRAP_MESSAGES.iter().any(|trap| output.contains(trap)) => {
+                self.inventory.push(item.to_string());
+                Ok(())
+            }
+            Ok(_) | Err(AdventureError::StepLimit { .. }) => {
+                self.droid = saved;
+                self.dangerous.insert(item.to_string());
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Moves the droid, returning the room it ends up in and whether the pressure-sensitive
+    /// floor ejected it back to where it came from.
+    fn go(&mut self, direction: &str) -> Result<(Room, bool), AdventureError> {
+        let output = self.droid.command(direction)?;
+        if self.droid.is_halted() {
+            return Err(AdventureError::Halted { output });
+        }
+        let ejected = output.contains(EJECTED_MESSAGE);
+        let room = parse_room(&output).ok_or(AdventureError::NoRoom { output })?;
+        Ok((room, ejected))
+    }
+
+    /// Tries carrying each combination of items onto the floor, starting from a saved droid
+    /// holding nothing at the checkpoint. The floor says whether the droid is too heavy or too
+    /// light, so heavier combinations than one too heavy and lighter ones than one too light
+    /// are skipped.
+    fn try_combinations(&mut self, floor: &str) -> Result<Solution, AdventureError> {
+        for item in &self.inventory {
+            self.droid.command(&format!("drop {}", item))?;
+        }
+        let saved = self.droid.clone();
+        let items = self.inventory.clone();
+
+        let password_pattern = Regex::new(r"typing (\d+)").unwrap();
+        let mut too_heavy: Vec<u32> = Vec::new();
+        let mut too_light: Vec<u32> = Vec::new();
+        for mask in combinations(items.len()) {
+            if too_heavy.iter().any(|&heavy| heavy & !mask == 0)
+                || too_light.iter().any(|&light| mask & !light == 0) {
+                continue;
+            }
+            self.droid = saved.clone();
+            let carried: Vec<String> = items.iter().enumerate()
+                .filter(|(index, _)| mask & (1 << index) != 0)
+                .map(|(_, item)| item.clone())
+                .collect();
+            for item in &carried {
+                self.droid.command(&format!("take {}", item))?;
+            }
+
+            let output = self.droid.command(floor)?;
+            if output.contains("lighter than the detected") {
+                too_heavy.push(mask);
+            } else if output.contains("heavier than the detected") {
+                too_light.push(mask);
+            } else if self.droid.is_halted() || !output.contains(EJECTED_MESSAGE) {
+                let password = password_pattern.captures(&output)
+                    .map(|captures| captures[1].to_string());
+                return Ok(Solution { items: carried, password, message: output });
+            }
+        }
+        Err(AdventureError::NoCombination)
+    }
+}
+
+/// Every subset of `count` items as bit masks, fewest items first.
+fn combinations(count: usize) -> Vec<u32> {
+    let mut masks: Vec<u32> = (0..(1u32 << count)).collect();
+    masks.sort_by_key(|mask| mask.count_ones());
+    masks
+}
@@ -0,0 +1,113 @@
+//! The tractor beam drone system of day 19.
+//!
+//! The drone program reads an x and a y coordinate, outputs 1 if the point is pulled by the beam
+//! and 0 if not, then halts, so every query is run on a fresh copy of the program.
+
+use failure_derive::Fail;
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
+
+use crate::int_code_big::{Computer, ComputerExecutionError, ComputerState};
+
+/// How many columns the beam is assumed to move right at most per row. A row is searched out to
+/// this many columns per row from the emitter, since rows close to the emitter can miss the beam
+/// entirely.
+pub const MAX_BEAM_SLOPE: i64 = 100;
+
+#[derive(Debug, Fail)]
+pub enum BeamError {
+    #[fail(display = "Drone failed")]
+    ExecutionError(#[cause] ComputerExecutionError),
+    #[fail(display = "Invalid drone report {}", value)]
+    InvalidReport { value: BigInt },
+    #[fail(display = "Drone halted without a report")]
+    NoReport,
+}
+
+impl From<ComputerExecutionError> for BeamError {
+    fn from(err: ComputerExecutionError) -> Self {
+        BeamError::ExecutionError(err)
+    }
+}
+
+pub struct Beam {
+    drone: Computer,
+    queries: usize,
+}
+
+impl Beam {
+    pub fn new(drone: Computer) -> Self {
+        Beam { drone, queries: 0 }
+    }
+
+    /// The number of drones deployed so far.
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+
+    /// Deploys a drone to the point to see if it is pulled by the beam.
+    pub fn is_pulled(&mut self, x: i64, y: i64) -> Result<bool, BeamError> {
+        self.queries += 1;
+        let mut drone = self.drone.clone();
+        let mut coordinates = vec![x, y].into_iter();
+        loop {
+            match drone.state() {
+                ComputerState::Running => drone.step()?,
+                ComputerState::WaitingForInput => match coordinates.next() {
+                    Some(coordinate) => drone.input(BigInt::from(coordinate))?,
+                    None => return Err(BeamError::NoReport),
+                },
+                ComputerState::WaitingToOutput(_) => {
+                    let value = drone.output()?;
+                    return match value {
+                        _ if value.is_zero() => Ok(false),
+                        _ if value.is_one() => Ok(true),
+                        _ => Err(BeamError::InvalidReport { value }),
+                    };
+                }
+                ComputerState::Halted => return Err(BeamError::NoReport),
+            }
+        }
+    }
+
+    /// The number of points pulled in the area from the emitter out to `width` by `height`.
+    pub fn count(&mut self, width: i64, height: i64) -> Result<usize, BeamError> {
+        let mut count = 0;
+        for y in 0..height {
+            for x in 0..width {
+                if self.is_pulled(x, y)? {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// The top left corner of the square of `size` closest to the emitter that fits entirely in
+    /// the beam, searching no further than row `max_y`.
+    ///
+    /// Only the beam's left edge is followed down the rows, as it never moves left. A square
+    /// ending on a row must start at that row's left edge, so it fits if the beam also covers
+    /// the point `size - 1` up and to the right.
+    pub fn fit_square(&mut self, size: i64, max_y: i64) -> Result<Option<(i64, i64)>, BeamError> {
+        let mut left = 0;
+        for y in (size - 1)..=max_y {
+            let mut edge = None;
+            for x in left..=(y * MAX_BEAM_SLOPE) {
+                if self.is_pulled(x, y)? {
+                    edge = Some(x);
+                    break;
+                }
+            }
+            let x = match edge {
+                Some(x) => x,
+                None => continue,
+            };
+            left = x;
+            if self.is_pulled(x + size - 1, y - size + 1)? {
+                return Ok(Some((x, y - size + 1)));
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,74 @@
+//! The arcade cabinet running hand-written games.
+
+use num_bigint::BigInt;
+
+#[path = "../src/arcade.rs"]
+pub mod arcade;
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+
+use arcade::{Arcade, ArcadeError, AutoPlayer, Joystick, Neutral, Screen, Tile};
+
+/// Halts straight away unless set to free play, which turns the first add into a multiply. When
+/// playing it draws the ball at 5,2 and the paddle at 3,3, reads the joystick and shows its value
+/// plus 10 as the score.
+const FREE_PLAY_GAME: &str = "1,0,0,200,1008,200,4,201,1006,201,35,\
+104,5,104,2,104,4,104,3,104,3,104,3,3,100,1001,100,10,100,104,-1,104,0,4,100,99";
+
+/// Draws a block at 1,1 as well as the ball and paddle, then scores the joystick like
+/// `FREE_PLAY_GAME` without needing free play.
+const BLOCK_GAME: &str = "104,5,104,2,104,4,104,3,104,3,104,3,104,1,104,1,104,2,\
+3,100,1001,100,10,100,104,-1,104,0,4,100,99";
+
+fn game(program: &str) -> int_code_big::Computer {
+    int_code_big::Computer::from_memory(int_code_big::parse_memory(program).unwrap())
+}
+
+#[test]
+fn games_need_free_play_to_start() {
+    let mut arcade = Arcade::new(game(FREE_PLAY_GAME), AutoPlayer);
+    arcade.run().unwrap();
+    assert_eq!(arcade.screen().count(Tile::Ball), 0);
+    assert_eq!(*arcade.screen().score(), BigInt::from(0));
+
+    let mut arcade = Arcade::new(game(FREE_PLAY_GAME), AutoPlayer);
+    arcade.free_play();
+    arcade.run().unwrap();
+    assert_eq!(arcade.screen().find(Tile::Ball), Some((5, 2)));
+    assert_eq!(*arcade.screen().score(), BigInt::from(11), "the ball is right of the paddle");
+}
+
+#[test]
+fn controllers_see_the_screen() {
+    let mut seen = Vec::new();
+    let mut arcade = Arcade::new(game(FREE_PLAY_GAME), |screen: &Screen| {
+        seen.push(screen.find(Tile::Paddle));
+        Joystick::Left
+    });
+    arcade.free_play();
+    arcade.run().unwrap();
+    assert_eq!(*arcade.screen().score(), BigInt::from(9));
+    assert_eq!(seen, vec![Some((3, 3))]);
+}
+
+#[test]
+fn screens_count_and_render_tiles() {
+    let mut arcade = Arcade::new(game(BLOCK_GAME), Neutral);
+    arcade.run().unwrap();
+    let screen = arcade.screen();
+    assert_eq!(screen.count(Tile::Block), 1);
+    assert_eq!(screen.tile((1, 1)), Tile::Block);
+    assert_eq!(screen.tile((0, 0)), Tile::Empty);
+    assert_eq!(*screen.score(), BigInt::from(10));
+    assert_eq!(screen.render(), "      \n =    \n     o\n   -  \nScore: 10\n");
+}
+
+#[test]
+fn invalid_tiles_are_errors() {
+    match Arcade::new(game("104,1,104,2,104,7,99"), Neutral).run() {
+        Err(ArcadeError::InvalidTile { x, y, tile }) => {
+            assert_eq!((x, y, tile), (BigInt::from(1), BigInt::from(2), BigInt::from(7)));
+        }
+        other => panic!("expected InvalidTile but got {:?}", other.err()),
+    }
+}
@@ -0,0 +1,74 @@
+//! The hull painting robot driven by hand-written brains.
+
+use std::env;
+use std::fs;
+use std::process;
+
+use num_bigint::BigInt;
+
+#[path = "../src/hull_robot.rs"]
+pub mod hull_robot;
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+
+use hull_robot::{Colour, Robot, RobotError};
+
+/// Reads the panel colour before each move, then paints and turns as in the day 11 example.
+const EXAMPLE: &str = "3,100,104,1,104,0,3,100,104,0,104,0,3,100,104,1,104,0,3,100,104,1,104,0,\
+3,100,104,0,104,1,3,100,104,1,104,0,3,100,104,1,104,0,99";
+
+fn load(brain: &str, start: Colour) -> Robot {
+    Robot::new(int_code_big::Computer::from_memory(int_code_big::parse_memory(brain).unwrap()), start)
+}
+
+#[test]
+fn example_paints_six_panels() {
+    let mut robot = load(EXAMPLE, Colour::Black);
+    robot.run().unwrap();
+    assert_eq!(robot.painted_count(), 6);
+    assert_eq!(robot.colour((0, 0)), Colour::Black);
+    assert_eq!(robot.colour((1, -1)), Colour::White);
+    assert_eq!(robot.render(), "..#\n..#\n##.\n");
+}
+
+#[test]
+fn brains_read_the_starting_panel() {
+    // Outputs the colour it reads as the paint, then turns right
+    let mut robot = load("3,100,4,100,104,1,99", Colour::White);
+    robot.run().unwrap();
+    assert_eq!(robot.painted_count(), 1);
+    assert_eq!(robot.render(), "#\n");
+
+    let mut robot = load("3,100,4,100,104,1,99", Colour::Black);
+    robot.run().unwrap();
+    assert_eq!(robot.render(), "");
+}
+
+#[test]
+fn invalid_outputs_are_errors() {
+    assert!(matches!(load("104,2,104,0,99", Colour::Black).run(), Err(RobotError::InvalidColour { .. })));
+    assert!(matches!(load("104,1,104,3,99", Colour::Black).run(), Err(RobotError::InvalidTurn { .. })));
+    match load("104,1,104,-1,99", Colour::Black).run() {
+        Err(RobotError::InvalidTurn { value }) => assert_eq!(value, BigInt::from(-1)),
+        other => panic!("expected InvalidTurn but got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn images_scale_each_panel() {
+    let mut robot = load(EXAMPLE, Colour::Black);
+    robot.run().unwrap();
+
+    let file = env::temp_dir().join(format!("hull_robot_{}.ppm", process::id()));
+    robot.write_image(&file, 2).unwrap();
+    let image = fs::read(&file).unwrap();
+    fs::remove_file(&file).unwrap();
+
+    let header = b"P6\n6 6\n255\n";
+    assert_eq!(&image[..header.len()], header);
+    let pixels = &image[header.len()..];
+    assert_eq!(pixels.len(), 6 * 6 * 3);
+    // The top right panel is white and the top left black
+    assert_eq!(&pixels[5 * 3..6 * 3], &[255, 255, 255]);
+    assert_eq!(&pixels[..3], &[0, 0, 0]);
+}
@@ -0,0 +1,131 @@
+//! Computers running as futures on the single-threaded executor, connected by channels.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code_async.rs"]
+pub mod int_code_async;
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+
+use int_code_async::{channel, run_async, Executor};
+use int_code_big::{Computer, ComputerExecutionError};
+
+/// Reads a number and outputs it doubled.
+const DOUBLE: &str = "3,9,1002,9,2,9,4,9,99,0";
+/// Reads a number and outputs one more than it.
+const INCREMENT: &str = "3,9,1001,9,1,9,4,9,99,0";
+/// Reads a number, then outputs it after counting down from it, over many instructions.
+const COUNT_DOWN: &str = "3,12,1001,13,-1,13,1005,13,2,4,12,99,0,20000";
+
+fn computer(program: &str) -> Computer {
+    Computer::from_memory(int_code_big::parse_memory(program).unwrap())
+}
+
+type Outcome = Rc<RefCell<Option<Result<Computer, ComputerExecutionError>>>>;
+
+#[test]
+fn pipelines_pass_values_along() {
+    let (input, double_input) = channel();
+    let (double_output, mut increment_input) = channel();
+    let (increment_output, mut result) = channel();
+    input.send(BigInt::from(20));
+
+    let mut executor = Executor::new();
+    // The increment is spawned first so it has to wait for the doubled value
+    executor.spawn(async move {
+        run_async(computer(INCREMENT), &mut increment_input, increment_output).await.unwrap();
+    });
+    executor.spawn(async move {
+        run_async(computer(DOUBLE), double_input, double_output).await.unwrap();
+    });
+    assert_eq!(executor.run(), 0);
+    drop(executor);
+
+    assert_eq!(result.try_recv(), Some(BigInt::from(41)));
+    assert_eq!(result.try_recv(), None);
+}
+
+#[test]
+fn long_runs_yield_to_other_machines() {
+    let (input, count_input) = channel();
+    let (count_output, mut counted) = channel();
+    let (increment_feed, increment_input) = channel();
+    let (increment_output, _incremented) = channel();
+    input.send(BigInt::from(7));
+    increment_feed.send(BigInt::from(1));
+
+    let finished = Rc::new(RefCell::new(Vec::new()));
+    let mut executor = Executor::new();
+    let order = finished.clone();
+    executor.spawn(async move {
+        run_async(computer(COUNT_DOWN), count_input, count_output).await.unwrap();
+        order.borrow_mut().push("count down");
+    });
+    let order = finished.clone();
+    executor.spawn(async move {
+        run_async(computer(INCREMENT), increment_input, increment_output).await.unwrap();
+        order.borrow_mut().push("increment");
+    });
+    assert_eq!(executor.run(), 0);
+    drop(executor);
+
+    // The count down was spawned first but takes far longer than one poll's worth of steps
+    assert_eq!(*finished.borrow(), vec!["increment", "count down"]);
+    assert_eq!(counted.try_recv(), Some(BigInt::from(7)));
+}
+
+#[test]
+fn closed_inputs_are_errors() {
+    let (input, receiver) = channel();
+    let (output, _outputs) = channel();
+    drop(input);
+
+    let outcome: Outcome = Rc::new(RefCell::new(None));
+    let mut executor = Executor::new();
+    let result = outcome.clone();
+    executor.spawn(async move {
+        *result.borrow_mut() = Some(run_async(computer(DOUBLE), receiver, output).await);
+    });
+    assert_eq!(executor.run(), 0);
+    assert!(matches!(outcome.borrow_mut().take(), Some(Err(ComputerExecutionError::InputClosed))));
+}
+
+#[test]
+fn inputs_closing_while_waiting_are_errors() {
+    let (input, receiver) = channel();
+    let (output, _outputs) = channel();
+
+    let outcome: Outcome = Rc::new(RefCell::new(None));
+    let mut executor = Executor::new();
+    let result = outcome.clone();
+    executor.spawn(async move {
+        *result.borrow_mut() = Some(run_async(computer(DOUBLE), receiver, output).await);
+    });
+    executor.spawn(async move {
+        drop(input);
+    });
+    assert_eq!(executor.run(), 0);
+    assert!(matches!(outcome.borrow_mut().take(), Some(Err(ComputerExecutionError::InputClosed))));
+}
+
+#[test]
+fn machines_waiting_on_each_other_are_reported_stuck() {
+    let (first_output, first_input) = channel();
+    let (second_output, second_input) = channel();
+
+    let outcome: Outcome = Rc::new(RefCell::new(None));
+    let mut executor = Executor::new();
+    let result = outcome.clone();
+    // Each machine holds the only sender for the other's input, so neither input ever closes
+    executor.spawn(async move {
+        *result.borrow_mut() = Some(run_async(computer(DOUBLE), first_input, second_output).await);
+    });
+    executor.spawn(async move {
+        run_async(computer(INCREMENT), second_input, first_output).await.unwrap();
+    });
+    assert_eq!(executor.run(), 2);
+    assert!(outcome.borrow().is_none());
+}
@@ -0,0 +1,172 @@
+//! Round trips through the binary program and memory image formats, and decoding of damaged
+//! files.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code.rs"]
+pub mod int_code;
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+
+use int_code_big::binary::{self, BinaryFormatError};
+
+const QUINE: &str = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+const LARGE: &str = "104,1125899906842624,99,-1125899906842624";
+
+fn words(program: &str) -> Vec<BigInt> {
+    program.split(',').map(|word| word.parse().unwrap()).collect()
+}
+
+fn temp_file(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("int_code_binary_{}_{}", name, process::id()))
+}
+
+#[test]
+fn text_round_trips_through_binary() {
+    for program in &[QUINE, LARGE, "0", "-1,-64,63,64,-65"] {
+        let words = words(program);
+        let width = binary::minimum_width(&words);
+        let bytes = binary::encode(&words, width).unwrap();
+        assert!(binary::is_binary(&bytes));
+
+        let (decoded_width, decoded) = binary::decode(&bytes).unwrap();
+        assert_eq!(decoded_width, width);
+        assert_eq!(binary::to_text(&decoded), *program);
+    }
+}
+
+#[test]
+fn widths_are_checked() {
+    assert_eq!(binary::minimum_width(&words(QUINE)), 32);
+    assert_eq!(binary::minimum_width(&words(LARGE)), 64);
+
+    match binary::encode(&words(LARGE), 32) {
+        Err(BinaryFormatError::WordTooWide { address, width: 32, .. }) => assert_eq!(address, BigInt::from(1)),
+        other => panic!("expected WordTooWide but got {:?}", other),
+    }
+}
+
+#[test]
+fn truncated_programs_are_rejected() {
+    let bytes = binary::encode(&words(LARGE), 64).unwrap();
+    for len in 0..bytes.len() {
+        let result = binary::decode(&bytes[..len]);
+        assert!(
+            matches!(result, Err(BinaryFormatError::Truncated) | Err(BinaryFormatError::MissingHeader)),
+            "decoding {} of {} bytes gave {:?}", len, bytes.len(), result
+        );
+    }
+}
+
+#[test]
+fn corrupt_programs_are_rejected() {
+    let bytes = binary::encode(&words(QUINE), 32).unwrap();
+
+    assert!(matches!(binary::decode(b"1,2,3"), Err(BinaryFormatError::MissingHeader)));
+
+    let mut version = bytes.clone();
+    version[3] = 99;
+    assert!(matches!(binary::decode(&version), Err(BinaryFormatError::UnsupportedVersion { version: 99 })));
+
+    let mut trailing = bytes.clone();
+    trailing.extend_from_slice(&[1, 2]);
+    assert!(matches!(binary::decode(&trailing), Err(BinaryFormatError::TrailingBytes { count: 2 })));
+
+    let mut narrow = bytes;
+    narrow[4] = 4;
+    assert!(matches!(binary::decode(&narrow), Err(BinaryFormatError::WordTooWide { width: 4, .. })));
+}
+
+#[test]
+fn overlong_varints_are_rejected() {
+    let mut bytes = binary::encode(&[], 0).unwrap();
+    bytes.pop();
+    bytes.push(1);
+    bytes.extend(std::iter::repeat(0x80).take(binary::MAX_VARINT_BYTES * 4));
+    assert!(matches!(binary::decode(&bytes), Err(BinaryFormatError::VarintTooLong { .. })));
+}
+
+#[test]
+fn memory_images_round_trip_through_computers() {
+    let mut computer = int_code_big::Computer::from_memory(int_code_big::parse_memory("1,2,0,4").unwrap());
+    computer.patch(BigInt::from(1000), BigInt::from(-5));
+    computer.patch(BigInt::from(1001), BigInt::from(6));
+    computer.patch(BigInt::from(-3), BigInt::from(7));
+    computer.patch(BigInt::from(5000), BigInt::from(1) << 70usize);
+
+    let image = computer.memory_image();
+    let (width, memory) = binary::decode_image(&image).unwrap();
+    assert_eq!(width, 0);
+    assert_eq!(memory.len(), 7, "the zero word is left out");
+
+    let file = temp_file("image");
+    fs::write(&file, &image).unwrap();
+    let loaded = int_code_big::Computer::new(&file).unwrap();
+    fs::remove_file(file).unwrap();
+
+    let non_zero = |computer: &int_code_big::Computer| {
+        let mut memory: Vec<(BigInt, BigInt)> = computer.memory().iter()
+            .filter(|(_, word)| **word != BigInt::from(0))
+            .map(|(address, word)| (address.clone(), word.clone()))
+            .collect();
+        memory.sort();
+        memory
+    };
+    assert_eq!(non_zero(&loaded), non_zero(&computer));
+}
+
+#[test]
+fn memory_images_load_into_the_small_interpreter() {
+    let memory = vec![(BigInt::from(0), BigInt::from(104)), (BigInt::from(1), BigInt::from(3)), (BigInt::from(3), BigInt::from(99))];
+    let image = binary::encode_image(&memory, 32).unwrap();
+    assert_eq!(binary::to_text(&binary::to_words(&binary::decode_image(&image).unwrap().1).unwrap()), "104,3,0,99");
+
+    let file = temp_file("small_image");
+    fs::write(&file, &image).unwrap();
+    let loaded = int_code::load_memory(&file).unwrap();
+    fs::remove_file(file).unwrap();
+    assert_eq!(loaded, vec![104, 3, 0, 99]);
+
+    let negative = binary::encode_image(&[(BigInt::from(-1), BigInt::from(1))], 32).unwrap();
+    let (_, memory) = binary::decode_image(&negative).unwrap();
+    assert!(matches!(binary::to_words(&memory), Err(BinaryFormatError::NegativeAddress { .. })));
+}
+
+#[test]
+fn far_off_image_words_are_rejected() {
+    let far = BigInt::from(1u64 << 40);
+    let image = binary::encode_image(&[(BigInt::from(0), BigInt::from(99)), (far.clone(), BigInt::from(1))], 32).unwrap();
+    let (_, memory) = binary::decode_image(&image).unwrap();
+    match binary::to_words(&memory) {
+        Err(BinaryFormatError::AddressTooLarge { address, max }) => {
+            assert_eq!(address, far);
+            assert_eq!(max, binary::MAX_DENSE_ADDRESS);
+        }
+        other => panic!("expected AddressTooLarge but got {:?}", other),
+    }
+
+    let file = temp_file("far_image");
+    fs::write(&file, &image).unwrap();
+    let loaded = int_code::load_memory(&file);
+    fs::remove_file(file).unwrap();
+    assert!(loaded.is_err());
+
+    let limit = [(BigInt::from(binary::MAX_DENSE_ADDRESS), BigInt::from(1))];
+    assert_eq!(binary::to_words(&limit).unwrap().len(), binary::MAX_DENSE_ADDRESS + 1);
+}
+
+#[test]
+fn programs_decode_as_images() {
+    let bytes = binary::encode(&words("1,0,2"), 32).unwrap();
+    let (_, memory) = binary::decode_image(&bytes).unwrap();
+    assert_eq!(memory, vec![
+        (BigInt::from(0), BigInt::from(1)),
+        (BigInt::from(1), BigInt::from(0)),
+        (BigInt::from(2), BigInt::from(2)),
+    ]);
+}
@@ -0,0 +1,103 @@
+//! Input searches by the concolic explorer on small programs from days 5 and 9.
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+#[path = "../src/int_code_concolic.rs"]
+pub mod int_code_concolic;
+#[path = "../src/int_code_symbolic.rs"]
+pub mod int_code_symbolic;
+
+use int_code_concolic::{Constraint, Explorer, Goal};
+
+/// Outputs 1 if the input equals 8 and 0 otherwise.
+const EQUALS_EIGHT: &str = "3,9,8,9,10,9,4,9,99,-1,8";
+/// Jumps over the add at 5 when the input is zero, outputting 0, and outputs 1 otherwise.
+const JUMP: &str = "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9";
+/// Outputs the first input minus 8, then the second input minus 3.
+const TWO_OUTPUTS: &str = "3,20,3,21,1001,20,-8,22,4,22,1001,21,-3,22,4,22,99";
+
+fn explorer(program: &str) -> Explorer {
+    Explorer::new(int_code_big::Computer::from_memory(int_code_big::parse_memory(program).unwrap()))
+}
+
+fn numbers(values: &[i64]) -> Vec<BigInt> {
+    values.iter().map(|value| BigInt::from(*value)).collect()
+}
+
+#[test]
+fn finds_inputs_for_an_output() {
+    let found = explorer(EQUALS_EIGHT).find(&Goal::Output(BigInt::from(1))).unwrap();
+    assert_eq!(found.inputs, numbers(&[8]));
+    assert_eq!(found.outputs, numbers(&[1]));
+
+    let found = explorer(EQUALS_EIGHT).find(&Goal::Output(BigInt::from(0))).unwrap();
+    assert_eq!(found.runs, 1, "the first run with input zero already outputs zero");
+}
+
+#[test]
+fn finds_inputs_that_reach_an_address() {
+    let found = explorer(JUMP).find(&Goal::ReachAddress(BigInt::from(5))).unwrap();
+    assert_ne!(found.inputs[0], BigInt::from(0));
+
+    let trace = explorer(JUMP).run(&numbers(&[0]), &Goal::ReachAddress(BigInt::from(5)));
+    assert_eq!(trace.outputs.iter().map(|output| output.value.clone()).collect::<Vec<_>>(), numbers(&[0]));
+}
+
+#[test]
+fn unreachable_goals_give_up() {
+    let mut explorer = explorer(EQUALS_EIGHT);
+    explorer.max_runs = 20;
+    assert!(explorer.find(&Goal::Output(BigInt::from(2))).is_none());
+    assert!(explorer.find(&Goal::ReachAddress(BigInt::from(100))).is_none());
+}
+
+#[test]
+fn finds_inputs_for_an_output_index() {
+    let found = explorer(TWO_OUTPUTS).find(&Goal::OutputAt(1, BigInt::from(0))).unwrap();
+    assert_eq!(found.outputs[1], BigInt::from(0));
+    assert_eq!(found.inputs[1], BigInt::from(3));
+}
+
+#[test]
+fn finds_inputs_for_every_output() {
+    let found = explorer(TWO_OUTPUTS).find(&Goal::AllOutputs(BigInt::from(0))).unwrap();
+    assert_eq!(found.inputs, numbers(&[8, 3]));
+    assert_eq!(found.outputs, numbers(&[0, 0]));
+
+    // A program without outputs never meets the goal
+    let mut explorer = explorer("3,0,99");
+    explorer.max_runs = 5;
+    assert!(explorer.find(&Goal::AllOutputs(BigInt::from(0))).is_none());
+}
+
+#[test]
+fn traces_record_path_constraints() {
+    let trace = explorer(EQUALS_EIGHT).run(&numbers(&[5]), &Goal::Output(BigInt::from(1)));
+    let constraints: Vec<&Constraint> = trace.constraints().collect();
+    assert!(constraints.iter().all(|constraint| constraint.satisfied(&trace.inputs)));
+    assert_eq!(trace.outputs[0].expr.as_ref().map(|expr| expr.to_string()), Some("(v0 == 8)".to_string()));
+    assert_eq!(trace.error, None);
+}
+
+#[test]
+fn solve_satisfies_linear_constraints() {
+    let trace = explorer(EQUALS_EIGHT).run(&numbers(&[5]), &Goal::Output(BigInt::from(1)));
+    let expr = trace.outputs[0].expr.clone().unwrap();
+    let constraints = [Constraint { condition: expr, holds: true }];
+    assert_eq!(int_code_concolic::solve(&constraints, &numbers(&[5]), 1), Some(numbers(&[8])));
+}
+
+#[test]
+fn execution_errors_end_the_trace() {
+    // The add reads the input from 7 and its second argument from address -5
+    let program = "3,7,1,7,-5,9,99,0,0,0";
+    let trace = explorer(program).run(&numbers(&[1]), &Goal::Output(BigInt::from(3)));
+    assert_eq!(trace.inputs, numbers(&[1]));
+    assert_eq!(trace.error, Some("Negative address -5".to_string()));
+
+    let mut explorer = explorer(program);
+    explorer.max_runs = 5;
+    assert!(explorer.find(&Goal::Output(BigInt::from(3))).is_none());
+}
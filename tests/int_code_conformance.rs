@@ -0,0 +1,264 @@
+//! Runs the example programs published with the Intcode puzzles against both interpreters.
+//!
+//! Further cases can be dropped into `tests/int_code_conformance/`, or into the directory named by
+//! the `INT_CODE_CONFORMANCE_DIR` environment variable. Cases in a file are separated by blank
+//! lines, lines starting with `#` are comments, and each case has these keys:
+//!
+//! ```text
+//! program: 3,9,8,9,10,9,4,9,99,-1,8
+//! input: 8
+//! output: 1
+//! memory: 3,9,8,9,10,9,4,9,99,1,8
+//! interpreters: big
+//! ```
+//!
+//! Only `program` is required. `memory` is compared against the start of memory after halting and
+//! `interpreters: big` skips the i32 interpreter for programs it does not support.
+
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code.rs"]
+pub mod int_code;
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+
+struct SmallIO {
+    inputs: VecDeque<i32>,
+    outputs: Vec<i32>,
+}
+
+impl int_code::IO for SmallIO {
+    fn get_input(&mut self) -> i32 {
+        self.inputs.pop_front().expect("program read more input than given")
+    }
+
+    fn output(&mut self, output: i32) {
+        self.outputs.push(output)
+    }
+}
+
+struct BigIO {
+    inputs: VecDeque<BigInt>,
+    outputs: Vec<BigInt>,
+}
+
+impl int_code_big::IO for BigIO {
+    fn get_input(&mut self) -> BigInt {
+        self.inputs.pop_front().expect("program read more input than given")
+    }
+
+    fn output(&mut self, output: BigInt) {
+        self.outputs.push(output)
+    }
+}
+
+/// Returns the outputs and final memory of the i32 interpreter.
+fn run_small(program: &str, inputs: &[i64]) -> (Vec<String>, Vec<String>) {
+    let mut state = int_code::ComputerState::new(int_code::parse_memory(program).unwrap());
+    let mut io = SmallIO { inputs: inputs.iter().map(|input| *input as i32).collect(), outputs: Vec::new() };
+    int_code::execute_computer(&mut state, &mut io);
+
+    (to_strings(&io.outputs), to_strings(state.memory()))
+}
+
+/// Returns the outputs and final memory of the BigInt interpreter, with memory read up to the
+/// program's length.
+fn run_big(program: &str, inputs: &[i64]) -> (Vec<String>, Vec<String>) {
+    let memory = int_code_big::parse_memory(program).unwrap();
+    let len = memory.len();
+    let mut computer = int_code_big::Computer::from_memory(memory);
+    let mut io = BigIO { inputs: inputs.iter().map(|input| BigInt::from(*input)).collect(), outputs: Vec::new() };
+    computer.run(&mut io).unwrap();
+
+    let memory: Vec<BigInt> = (0..len).map(|address| computer[&BigInt::from(address)].clone()).collect();
+    (to_strings(&io.outputs), to_strings(&memory))
+}
+
+fn to_strings<T: ToString>(values: &[T]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn split(list: &str) -> Vec<String> {
+    list.split(',').map(|word| word.trim().to_string()).filter(|word| !word.is_empty()).collect()
+}
+
+fn assert_outputs(program: &str, inputs: &[i64], expected: &str) {
+    let expected = split(expected);
+    assert_eq!(run_small(program, inputs).0, expected, "int_code outputs of {} with {:?}", program, inputs);
+    assert_eq!(run_big(program, inputs).0, expected, "int_code_big outputs of {} with {:?}", program, inputs);
+}
+
+fn assert_big_outputs(program: &str, inputs: &[i64], expected: &str) {
+    assert_eq!(run_big(program, inputs).0, split(expected), "int_code_big outputs of {} with {:?}", program, inputs);
+}
+
+fn assert_memory(program: &str, expected: &str) {
+    let expected = split(expected);
+    assert_eq!(run_small(program, &[]).1, expected, "int_code memory of {}", program);
+    assert_eq!(run_big(program, &[]).1, expected, "int_code_big memory of {}", program);
+}
+
+#[test]
+fn day_2_examples() {
+    assert_memory("1,9,10,3,2,3,11,0,99,30,40,50", "3500,9,10,70,2,3,11,0,99,30,40,50");
+    assert_memory("1,0,0,0,99", "2,0,0,0,99");
+    assert_memory("2,3,0,3,99", "2,3,0,6,99");
+    assert_memory("2,4,4,5,99,0", "2,4,4,5,99,9801");
+    assert_memory("1,1,1,4,99,5,6,0,99", "30,1,1,4,2,5,6,0,99");
+}
+
+#[test]
+fn day_5_input_output_and_modes() {
+    assert_outputs("3,0,4,0,99", &[42], "42");
+    assert_outputs("3,0,4,0,99", &[-7], "-7");
+    assert_memory("1002,4,3,4,33", "1002,4,3,4,99");
+    assert_memory("1101,100,-1,4,0", "1101,100,-1,4,99");
+}
+
+#[test]
+fn day_5_comparisons() {
+    let equal_position = "3,9,8,9,10,9,4,9,99,-1,8";
+    let less_position = "3,9,7,9,10,9,4,9,99,-1,8";
+    let equal_immediate = "3,3,1108,-1,8,3,4,3,99";
+    let less_immediate = "3,3,1107,-1,8,3,4,3,99";
+
+    for program in &[equal_position, equal_immediate] {
+        assert_outputs(program, &[8], "1");
+        assert_outputs(program, &[7], "0");
+        assert_outputs(program, &[9], "0");
+    }
+    for program in &[less_position, less_immediate] {
+        assert_outputs(program, &[7], "1");
+        assert_outputs(program, &[8], "0");
+        assert_outputs(program, &[9], "0");
+    }
+}
+
+#[test]
+fn day_5_jumps() {
+    for program in &["3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9", "3,3,1105,-1,9,1101,0,0,12,4,12,99,1"] {
+        assert_outputs(program, &[0], "0");
+        assert_outputs(program, &[5], "1");
+        assert_outputs(program, &[-3], "1");
+    }
+}
+
+#[test]
+fn day_5_larger_example() {
+    let program = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,\
+        1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,\
+        999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+
+    assert_outputs(program, &[7], "999");
+    assert_outputs(program, &[8], "1000");
+    assert_outputs(program, &[9], "1001");
+}
+
+#[test]
+fn day_9_quine() {
+    let program = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    assert_big_outputs(program, &[], program);
+}
+
+#[test]
+fn day_9_large_numbers() {
+    let outputs = run_big("1102,34915192,34915192,7,4,7,99,0", &[]).0;
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(outputs[0].len(), 16);
+
+    assert_big_outputs("104,1125899906842624,99", &[], "1125899906842624");
+}
+
+#[derive(Default)]
+struct Case {
+    location: String,
+    program: String,
+    inputs: Vec<i64>,
+    outputs: Option<String>,
+    memory: Option<String>,
+    big_only: bool,
+}
+
+fn parse_cases(file: &str, text: &str) -> Vec<Case> {
+    let mut cases = Vec::new();
+    let mut case: Option<Case> = None;
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.is_empty() {
+            cases.extend(case.take());
+            continue;
+        }
+
+        let case = case.get_or_insert_with(|| Case { location: format!("{}:{}", file, number + 1), ..Case::default() });
+        let (key, value) = match line.find(':') {
+            Some(colon) => (line[..colon].trim(), line[colon + 1..].trim()),
+            None => panic!("{}:{}: expected `key: value`", file, number + 1),
+        };
+        match key {
+            "program" => case.program = value.to_string(),
+            "input" => case.inputs = split(value).iter().map(|input| input.parse().unwrap()).collect(),
+            "output" => case.outputs = Some(value.to_string()),
+            "memory" => case.memory = Some(value.to_string()),
+            "interpreters" => case.big_only = value == "big",
+            _ => panic!("{}:{}: unknown key {}", file, number + 1, key),
+        }
+    }
+    cases.extend(case);
+    cases
+}
+
+/// Checks a case, returning a description of each mismatch.
+fn check_case(case: &Case) -> Vec<String> {
+    let mut runs = vec![("int_code_big", run_big(&case.program, &case.inputs))];
+    if !case.big_only {
+        runs.push(("int_code", run_small(&case.program, &case.inputs)));
+    }
+
+    let mut failures = Vec::new();
+    for (name, (outputs, memory)) in runs {
+        if let Some(expected) = &case.outputs {
+            if outputs != split(expected) {
+                failures.push(format!("{} ({}): outputs {:?}, expected {}", case.location, name, outputs, expected));
+            }
+        }
+        if let Some(expected) = &case.memory {
+            let expected = split(expected);
+            if !memory.starts_with(&expected) {
+                failures.push(format!("{} ({}): memory {:?}, expected {:?}", case.location, name, memory, expected));
+            }
+        }
+    }
+    failures
+}
+
+#[test]
+fn conformance_directory() {
+    let directory = env::var_os("INT_CODE_CONFORMANCE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/int_code_conformance"));
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&directory).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+
+    let mut failures = Vec::new();
+    for file in files {
+        let text = fs::read_to_string(&file).unwrap();
+        for case in parse_cases(&file.display().to_string(), &text) {
+            failures.extend(check_case(&case));
+        }
+    }
+
+    assert!(failures.is_empty(), "conformance failures:\n{}", failures.join("\n"));
+}
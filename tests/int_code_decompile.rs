@@ -0,0 +1,91 @@
+//! Rendered pseudocode for small programs in the styles of days 5, 7 and 9.
+
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+#[path = "../src/int_code_decompile.rs"]
+pub mod int_code_decompile;
+
+fn decompile(program: &str) -> String {
+    let memory = int_code_big::Computer::from_memory(int_code_big::parse_memory(program).unwrap()).memory().clone();
+    int_code_decompile::decompile(&memory).to_string()
+}
+
+/// Calls a function that adds one to its argument, using day 9's calling convention.
+const CALL: &str = "109,10,21101,5,0,1,21101,13,0,0,1105,1,20,204,1,99,0,0,0,0,\
+109,3,21201,-2,1,-2,109,-3,2105,1,0";
+
+#[test]
+fn called_functions_name_their_frame() {
+    assert_eq!(decompile(CALL), "
+fn main() {
+    rb += 10
+    f20(5)
+    output(rb[1])
+    halt
+}
+
+fn f20(arg1) {
+    arg1 += 1
+    return
+}
+");
+}
+
+#[test]
+fn relative_base_changes_outside_frames_are_printed() {
+    // Day 9's quine moves the relative base in main, which has no frame
+    assert_eq!(decompile("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99"), "v100 = 0
+
+fn main() {
+    loop {
+        rb += 1
+        output(rb[-1])
+        v100 += 1
+        if v100 == 16 {
+            break
+        }
+    }
+    halt
+}
+");
+
+    // Once a function moves the relative base by an unknown amount its frame is lost
+    let program = CALL.replace("21201,-2,1,-2,109,-3", "9,50,109,-2,109,-1");
+    let decompiled = decompile(&program);
+    assert!(decompiled.contains("fn f20(arg1) {\n    rb += v50\n    rb -= 2\n    rb -= 1\n    return\n}"), "{}", decompiled);
+}
+
+#[test]
+fn self_modifying_code_stops_with_a_note() {
+    // Like day 5, the input is added to the op code at 6, turning it into a valid instruction
+    assert_eq!(decompile("3,11,1,11,6,6,1100,1,1,11,99,0"), "v6 = 1100
+
+fn main() {
+    v11 = input()
+    v6 = v11 + v6
+    invalid 1100  // self-modifying: the program writes to 6 before running it, stopping here
+}
+");
+}
+
+#[test]
+fn unresolved_jumps_stop_with_a_note() {
+    // Like day 7, the input picks the jump target
+    assert_eq!(decompile("3,8,1001,8,10,8,105,1,0,99"), "
+fn main() {
+    code[8] = input()
+    code[8] += 10
+    goto *memory[code[8]]  // unresolved jump, stopping here
+}
+");
+}
+
+#[test]
+fn invalid_words_that_are_not_written_have_no_note() {
+    assert_eq!(decompile("104,1,77"), "
+fn main() {
+    output(1)
+    invalid 77
+}
+");
+}
@@ -0,0 +1,97 @@
+//! Memory-mapped devices attached to `int_code_big::Computer`.
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+
+use int_code_big::{ClockDevice, Computer, FramebufferDevice, RandomDevice, StopCondition};
+
+const DEVICE: i64 = 1000;
+
+struct Outputs(Vec<BigInt>);
+
+impl int_code_big::IO for Outputs {
+    fn get_input(&mut self) -> BigInt {
+        panic!("unexpected input")
+    }
+
+    fn output(&mut self, output: BigInt) {
+        self.0.push(output)
+    }
+}
+
+fn load(program: &str) -> Computer {
+    Computer::from_memory(int_code_big::parse_memory(program).unwrap())
+}
+
+fn run(computer: &mut Computer) -> Vec<BigInt> {
+    let mut outputs = Outputs(Vec::new());
+    computer.run(&mut outputs).unwrap();
+    outputs.0
+}
+
+fn device_range(len: i64) -> std::ops::Range<BigInt> {
+    BigInt::from(DEVICE)..BigInt::from(DEVICE + len)
+}
+
+#[test]
+fn programs_read_and_write_devices() {
+    // Adds two clock reads and outputs the sum
+    let mut computer = load("1,1000,1000,20,4,20,99");
+    let clock = computer.attach_device(device_range(1), ClockDevice::default()).unwrap();
+    assert_eq!(run(&mut computer), vec![BigInt::from(3)]);
+    assert_eq!(*clock.lock().unwrap().ticks(), BigInt::from(2));
+
+    let mut computer = load("1101,0,7,1001,99");
+    let framebuffer = computer.attach_device(device_range(4), FramebufferDevice::new(2, 2)).unwrap();
+    run(&mut computer);
+    assert_eq!(framebuffer.lock().unwrap().render(), " #\n  \n");
+}
+
+#[test]
+fn overlapping_devices_are_rejected() {
+    let mut computer = load("99");
+    computer.attach_device(device_range(4), FramebufferDevice::new(2, 2)).unwrap();
+    assert!(computer.attach_device(BigInt::from(DEVICE + 3)..BigInt::from(DEVICE + 5), ClockDevice::default()).is_err());
+}
+
+#[test]
+fn clones_get_their_own_devices() {
+    let mut original = load("1,1000,1000,20,4,20,99");
+    let clock = original.attach_device(device_range(1), ClockDevice::default()).unwrap();
+
+    let mut clone = original.clone();
+    assert_eq!(run(&mut clone), vec![BigInt::from(3)]);
+    assert_eq!(*clock.lock().unwrap().ticks(), BigInt::from(0));
+
+    assert_eq!(run(&mut original), vec![BigInt::from(3)]);
+    assert_eq!(*clock.lock().unwrap().ticks(), BigInt::from(2));
+}
+
+#[test]
+fn peeking_has_no_side_effects() {
+    let mut computer = load("99");
+    let clock = computer.attach_device(device_range(1), ClockDevice::default()).unwrap();
+    computer.attach_device(BigInt::from(DEVICE + 1)..BigInt::from(DEVICE + 2), RandomDevice::new(7)).unwrap();
+
+    assert_eq!(computer.peek(&BigInt::from(DEVICE)), BigInt::from(0));
+    assert_eq!(*clock.lock().unwrap().ticks(), BigInt::from(0));
+
+    let random_address = BigInt::from(DEVICE + 1);
+    let peeked = computer.peek(&random_address);
+    assert_eq!(computer.peek(&random_address), peeked);
+    assert_eq!(computer.read(&random_address), peeked);
+    assert_ne!(computer.peek(&random_address), peeked);
+}
+
+#[test]
+fn memory_stop_conditions_do_not_tick_devices() {
+    let mut computer = load("1101,1,1,20,1101,2,2,21,99");
+    let clock = computer.attach_device(device_range(1), ClockDevice::default()).unwrap();
+
+    let conditions = [StopCondition::Memory(BigInt::from(DEVICE), Box::new(|ticks| *ticks > BigInt::from(5)))];
+    let stop = computer.run_until(&mut Outputs(Vec::new()), &conditions).unwrap();
+    assert!(matches!(stop, int_code_big::Stop::Halted));
+    assert_eq!(*clock.lock().unwrap().ticks(), BigInt::from(0));
+}
@@ -0,0 +1,82 @@
+//! Memory diffs between computers before and after running part of a program.
+
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+#[path = "../src/int_code_diff.rs"]
+pub mod int_code_diff;
+
+use int_code_big::{Computer, ComputerState};
+use int_code_diff::{diff, CellChange};
+
+fn computer(program: &str) -> Computer {
+    Computer::from_memory(int_code_big::parse_memory(program).unwrap())
+}
+
+fn big(value: i64) -> BigInt {
+    BigInt::from(value)
+}
+
+#[test]
+fn equal_computers_have_no_differences() {
+    let before = computer("1,0,0,0,99");
+    let changes = diff(&before, &before.clone());
+    assert!(changes.is_empty());
+    assert!(changes.groups().is_empty());
+    assert_eq!(changes.to_string(), "no differences\n");
+}
+
+#[test]
+fn changed_added_and_removed_cells_are_reported() {
+    let before = computer("1,0,0,0,99");
+    let mut after = before.clone();
+    after.patch(big(0), big(2));
+    after.patch(big(7), big(5));
+
+    let changes = diff(&before, &after);
+    assert_eq!(changes.cells.len(), 2);
+    assert_eq!(changes.cells[&big(0)], CellChange::Changed(big(1), big(2)));
+    assert_eq!(changes.cells[&big(7)], CellChange::Added(big(5)));
+    assert_eq!(changes.program_counter, None);
+
+    let reversed = diff(&after, &before);
+    assert_eq!(reversed.cells[&big(7)], CellChange::Removed(big(5)));
+    assert_eq!(reversed.cells[&big(7)].before(), Some(&big(5)));
+    assert_eq!(reversed.cells[&big(7)].after(), None);
+}
+
+#[test]
+fn adjacent_cells_are_grouped() {
+    let before = computer("1,0,0,0,99");
+    let mut after = before.clone();
+    for (address, value) in &[(1, 9), (2, 8), (3, 7), (5, 6), (10, 4)] {
+        after.patch(big(*address), big(*value));
+    }
+
+    let changes = diff(&before, &after);
+    let groups: Vec<Vec<i64>> = changes.groups().iter()
+        .map(|group| group.iter().map(|(address, _)| address.to_i64().unwrap()).collect())
+        .collect();
+    assert_eq!(groups, vec![vec![1, 2, 3], vec![5], vec![10]]);
+    assert_eq!(changes.to_string(), "@ 1..=3\n- 0,0,0\n+ 9,8,7\n@ 5\n- _\n+ 6\n@ 10\n- _\n+ 4\n");
+}
+
+#[test]
+fn registers_and_state_are_compared() {
+    let before = computer("109,5,3,0,99");
+    let mut after = before.clone();
+    after.step().unwrap();
+    after.step().unwrap();
+
+    let changes = diff(&before, &after);
+    assert!(changes.cells.is_empty());
+    assert_eq!(changes.program_counter, Some((big(0), big(2))));
+    assert_eq!(changes.relative_base, Some((big(0), big(5))));
+    assert_eq!(changes.state, Some((ComputerState::Running, ComputerState::WaitingForInput)));
+    assert_eq!(
+        changes.to_string(),
+        "program counter: 0 -> 2\nrelative base: 0 -> 5\nstate: Running -> WaitingForInput\n"
+    );
+}
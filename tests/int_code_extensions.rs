@@ -0,0 +1,117 @@
+//! Custom instructions registered on both interpreters.
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code.rs"]
+pub mod int_code;
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+
+/// Op code 20 doubles its first parameter into the address given by its second, then the
+/// program outputs the result.
+const DOUBLE_PROGRAM: &str = "120,21,7,4,7,99,0,0";
+
+struct SmallDouble {
+    op_code: i32,
+}
+
+impl int_code::ParameterizedOp for SmallDouble {
+    fn num_params(&self) -> usize {
+        2
+    }
+
+    fn num_parameterized_params(&self) -> usize {
+        1
+    }
+
+    fn get_opcode(&self) -> i32 {
+        self.op_code
+    }
+
+    fn execute(&self, args: Vec<i32>, state: &mut int_code::ComputerState, _io: &mut dyn int_code::IO) -> int_code::OpResult {
+        state.patch(args[1] as usize, args[0] * 2);
+        int_code::OpResult::AdvanceProgramCounter
+    }
+}
+
+#[derive(Debug)]
+struct BigDouble {
+    op_code: u8,
+}
+
+impl int_code_big::ExtensionOp for BigDouble {
+    fn op_code(&self) -> u8 {
+        self.op_code
+    }
+
+    fn num_args(&self) -> u8 {
+        2
+    }
+
+    fn execute(&self, computer: &mut int_code_big::Computer, op_modes: &int_code_big::OpModes) -> Result<int_code_big::OpResult, int_code_big::ComputerExecutionError> {
+        let value = computer.arg(0, op_modes)? * 2;
+        let address = computer.position_arg(1, op_modes)?;
+        computer.write(address, value)?;
+        Ok(int_code_big::OpResult::AdvanceProgramCounter)
+    }
+}
+
+struct Outputs<T>(Vec<T>);
+
+impl int_code::IO for Outputs<i32> {
+    fn get_input(&mut self) -> i32 {
+        panic!("unexpected input")
+    }
+
+    fn output(&mut self, output: i32) {
+        self.0.push(output)
+    }
+}
+
+impl int_code_big::IO for Outputs<BigInt> {
+    fn get_input(&mut self) -> BigInt {
+        panic!("unexpected input")
+    }
+
+    fn output(&mut self, output: BigInt) {
+        self.0.push(output)
+    }
+}
+
+#[test]
+fn small_interpreter_runs_registered_ops() {
+    let double = SmallDouble { op_code: 20 };
+    let mut ops = int_code::Ops::new();
+    ops.register_op(&double).unwrap();
+
+    let mut state = int_code::ComputerState::new(int_code::parse_memory(DOUBLE_PROGRAM).unwrap());
+    let mut outputs = Outputs(Vec::new());
+    int_code::execute_computer_with_ops(&mut state, &mut outputs, &ops);
+    assert_eq!(outputs.0, vec![42]);
+}
+
+#[test]
+fn small_interpreter_rejects_built_in_op_codes() {
+    let plus = SmallDouble { op_code: 1 };
+    let mut ops = int_code::Ops::new();
+    assert_eq!(ops.register_op(&plus).map_err(|err| err.op), Err(1));
+}
+
+#[test]
+fn big_interpreter_runs_registered_ops() {
+    let mut computer = int_code_big::Computer::from_memory(int_code_big::parse_memory(DOUBLE_PROGRAM).unwrap());
+    computer.register_op(BigDouble { op_code: 20 }).unwrap();
+
+    let mut outputs = Outputs(Vec::new());
+    computer.run(&mut outputs).unwrap();
+    assert_eq!(outputs.0, vec![BigInt::from(42)]);
+}
+
+#[test]
+fn big_interpreter_rejects_built_in_op_codes() {
+    let mut computer = int_code_big::Computer::from_memory(int_code_big::parse_memory(DOUBLE_PROGRAM).unwrap());
+    match computer.register_op(BigDouble { op_code: 1 }) {
+        Err(int_code_big::ComputerExecutionError::OpCodeInUse { op }) => assert_eq!(op, 1),
+        other => panic!("expected OpCodeInUse but got {:?}", other),
+    }
+}
@@ -0,0 +1,81 @@
+//! Cases found by the differential fuzzer.
+
+#[path = "../src/int_code.rs"]
+pub mod int_code;
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+#[path = "../src/int_code_fuzz.rs"]
+pub mod int_code_fuzz;
+
+use int_code_fuzz::{compare, run_big, run_small, Comparison, Outcome};
+
+fn parse(program: &str) -> Vec<i32> {
+    int_code::parse_memory(program).unwrap()
+}
+
+/// Case 714 of seed 7 writes an instruction with a mode digit of 9 past its parameters, then
+/// runs it.
+const MODE_PAST_PARAMETERS: &str = "1006,0,0,1006,0,0,1,0,0,1,101,-7,0,18,7";
+
+/// Case 2544 of seed 7 rewrites its own immediate operand so the word squares on every loop.
+const SQUARING: &str = "4,12,1002,4,-14,4,1005,13,2,99,16,16,-15,-6";
+
+#[test]
+fn words_growing_past_i32_are_skipped() {
+    assert!(matches!(compare(&parse(SQUARING), &[], 1_000), Comparison::OutOfRange));
+    assert!(matches!(compare(&parse(SQUARING), &[], 1_000_000), Comparison::OutOfRange));
+}
+
+#[test]
+fn big_run_stops_at_the_first_word_past_i32() {
+    let run = run_big(&parse(SQUARING), &[], 1_000_000);
+    assert_eq!(run.outcome, Outcome::OutOfRange);
+    assert!(run.memory.iter().all(|word| word.bits() <= 64));
+}
+
+#[test]
+fn unknown_mode_digits_past_the_parameters_are_errors() {
+    let program = parse(MODE_PAST_PARAMETERS);
+    assert_eq!(run_small(&program, &[], 1_000).outcome, Outcome::Error("Invalid op mode 9".to_string()));
+    assert_eq!(run_big(&program, &[], 1_000).outcome, Outcome::Error("Invalid op mode 9".to_string()));
+    assert!(matches!(compare(&program, &[], 1_000), Comparison::Agree));
+}
+
+#[test]
+fn invalid_input_instructions_fail_before_reading_input() {
+    // Case 8514 of seed 7: the add writes 103 after the program, an input in immediate mode
+    let program = parse("101,2,0,4");
+    assert!(matches!(run_big(&program, &[], 100).outcome, Outcome::Error(_)));
+    assert!(matches!(compare(&program, &[], 100), Comparison::Agree));
+}
+
+#[test]
+fn jump_targets_are_read_when_not_jumping() {
+    // Case 19995 of seed 42: the jump at 4 isn't taken but its target is at address -1
+    let program = parse("3,4,104,0,0,0,-1,4");
+    assert_eq!(run_big(&program, &[6], 100).outputs, vec![0.into()]);
+    assert!(matches!(compare(&program, &[6], 100), Comparison::Agree));
+}
+
+#[test]
+fn writes_far_past_the_program_are_skipped() {
+    assert!(matches!(compare(&parse("1101,0,0,100000,99"), &[], 100), Comparison::OutOfRange));
+    assert!(matches!(compare(&parse("1101,0,0,1000,99"), &[], 100), Comparison::Agree));
+}
+
+#[test]
+#[should_panic(expected = "Invalid op mode 3")]
+fn int_code_rejects_unknown_mode_digits_on_halt() {
+    let mut state = int_code::ComputerState::new(vec![30099]);
+    int_code::execute_computer(&mut state, &mut NoIO);
+}
+
+struct NoIO;
+
+impl int_code::IO for NoIO {
+    fn get_input(&mut self) -> i32 {
+        panic!("no input")
+    }
+
+    fn output(&mut self, _output: i32) {}
+}
@@ -0,0 +1,57 @@
+//! Parsing of memory patches given inline or in files.
+
+use std::env;
+use std::fs;
+use std::process;
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code_patch.rs"]
+pub mod int_code_patch;
+
+use int_code_patch::{Patch, PatchError};
+
+fn patch(address: i64, value: i64, line: usize) -> Patch {
+    Patch { address: BigInt::from(address), value: BigInt::from(value), line }
+}
+
+#[test]
+fn inline_patches_are_numbered_by_position() {
+    assert_eq!(int_code_patch::load_patches("1=12, 2 = 2").unwrap(), vec![patch(1, 12, 1), patch(2, 2, 2)]);
+    assert_eq!(int_code_patch::load_patches("-1=-5").unwrap(), vec![patch(-1, -5, 1)]);
+}
+
+#[test]
+fn patch_files_skip_comments_and_blank_lines() {
+    // The '=' in the file name must not make it look like an inline patch
+    let file = env::temp_dir().join(format!("int_code_patch_a=b_{}", process::id()));
+    fs::write(&file, "# Day 2 alarm state\n1=12\n\n2 = 2  # verb\n").unwrap();
+    let patches = int_code_patch::load_patches(&format!("@{}", file.display()));
+    fs::remove_file(&file).unwrap();
+    assert_eq!(patches.unwrap(), vec![patch(1, 12, 2), patch(2, 2, 4)]);
+}
+
+#[test]
+fn missing_patch_files_are_io_errors() {
+    assert!(matches!(int_code_patch::load_patches("@/does/not/exist"), Err(PatchError::IoError(_))));
+}
+
+#[test]
+fn invalid_patches_report_their_line() {
+    match int_code_patch::parse_patches("1=2\n\n3\n") {
+        Err(PatchError::InvalidPatch { line, text }) => {
+            assert_eq!(line, 3);
+            assert_eq!(text, "3");
+        }
+        other => panic!("expected InvalidPatch but got {:?}", other),
+    }
+    assert!(matches!(int_code_patch::load_patches("1=2,x=3"), Err(PatchError::InvalidPatch { line: 2, .. })));
+    assert!(matches!(int_code_patch::load_patches("file.txt"), Err(PatchError::InvalidPatch { line: 1, .. })));
+}
+
+#[test]
+fn small_patches_must_fit() {
+    assert_eq!(int_code_patch::to_small(&[patch(1, 12, 1)]).unwrap(), vec![(1, 12)]);
+    assert!(matches!(int_code_patch::to_small(&[patch(-1, 0, 4)]), Err(PatchError::OutOfRange { line: 4, .. })));
+    assert!(matches!(int_code_patch::to_small(&[patch(0, 1 << 40, 1)]), Err(PatchError::OutOfRange { line: 1, .. })));
+}
@@ -0,0 +1,138 @@
+//! Records runs of both interpreters and replays the logs against them.
+
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code.rs"]
+pub mod int_code;
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+#[path = "../src/int_code_record.rs"]
+pub mod int_code_record;
+
+use int_code_record::{Direction, Event, Recorder, RecordingIO, Replayer};
+
+const EQUALS_EIGHT: &str = "3,9,8,9,10,9,4,9,99,-1,8";
+const LESS_THAN_EIGHT: &str = "3,9,7,9,10,9,4,9,99,-1,8";
+
+struct QueueIO {
+    inputs: VecDeque<i64>,
+    outputs: Vec<String>,
+}
+
+impl QueueIO {
+    fn new(inputs: &[i64]) -> Self {
+        QueueIO { inputs: inputs.iter().cloned().collect(), outputs: Vec::new() }
+    }
+}
+
+impl int_code::IO for QueueIO {
+    fn get_input(&mut self) -> i32 {
+        self.inputs.pop_front().expect("program read more input than given") as i32
+    }
+
+    fn output(&mut self, output: i32) {
+        self.outputs.push(output.to_string())
+    }
+}
+
+impl int_code_big::IO for QueueIO {
+    fn get_input(&mut self) -> BigInt {
+        BigInt::from(self.inputs.pop_front().expect("program read more input than given"))
+    }
+
+    fn output(&mut self, output: BigInt) {
+        self.outputs.push(output.to_string())
+    }
+}
+
+fn log_file(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("int_code_record_{}_{}.log", name, process::id()))
+}
+
+fn record_small(program: &str, inputs: &[i64], log: &PathBuf) -> Vec<String> {
+    let mut state = int_code::ComputerState::new(int_code::parse_memory(program).unwrap());
+    let mut io = RecordingIO::new(QueueIO::new(inputs), Recorder::create(log).unwrap());
+    int_code::execute_computer(&mut state, &mut io);
+    io.into_inner().outputs
+}
+
+fn record_big(program: &str, inputs: &[i64], log: &PathBuf) -> Vec<String> {
+    let mut computer = int_code_big::Computer::from_memory(int_code_big::parse_memory(program).unwrap());
+    let mut io = RecordingIO::new(QueueIO::new(inputs), Recorder::create(log).unwrap());
+    computer.run(&mut io).unwrap();
+    io.into_inner().outputs
+}
+
+fn replay_small(program: &str, events: Vec<Event>) -> Replayer {
+    let mut replayer = Replayer::new(events);
+    replayer.replay_small(&mut int_code::ComputerState::new(int_code::parse_memory(program).unwrap()));
+    replayer
+}
+
+fn replay_big(program: &str, events: Vec<Event>) -> Replayer {
+    let mut replayer = Replayer::new(events);
+    let mut computer = int_code_big::Computer::from_memory(int_code_big::parse_memory(program).unwrap());
+    replayer.replay_big(&mut computer).unwrap();
+    replayer
+}
+
+fn event(direction: Direction, instruction: u64, value: i64) -> Event {
+    Event { direction, instruction, value: BigInt::from(value) }
+}
+
+#[test]
+fn both_interpreters_record_the_same_log() {
+    let small_log = log_file("small");
+    let big_log = log_file("big");
+    assert_eq!(record_small(EQUALS_EIGHT, &[8], &small_log), vec!["1"]);
+    assert_eq!(record_big(EQUALS_EIGHT, &[8], &big_log), vec!["1"]);
+
+    let expected = vec![event(Direction::Input, 1, 8), event(Direction::Output, 3, 1)];
+    assert_eq!(int_code_record::read_log(&small_log).unwrap(), expected);
+    assert_eq!(int_code_record::read_log(&big_log).unwrap(), expected);
+
+    fs::remove_file(small_log).unwrap();
+    fs::remove_file(big_log).unwrap();
+}
+
+#[test]
+fn recorded_runs_replay_without_divergence() {
+    let log = log_file("round_trip");
+    record_big(EQUALS_EIGHT, &[7], &log);
+    let events = int_code_record::read_log(&log).unwrap();
+    fs::remove_file(log).unwrap();
+
+    let replayer = replay_big(EQUALS_EIGHT, events.clone());
+    assert_eq!(replayer.divergence(), None);
+    assert_eq!(replayer.matched(), 2);
+
+    let replayer = replay_small(EQUALS_EIGHT, events);
+    assert_eq!(replayer.divergence(), None);
+    assert_eq!(replayer.matched(), 2);
+}
+
+#[test]
+fn replay_reports_the_first_divergence() {
+    let events = vec![event(Direction::Input, 1, 7), event(Direction::Output, 3, 0)];
+    for replayer in &[replay_big(LESS_THAN_EIGHT, events.clone()), replay_small(LESS_THAN_EIGHT, events.clone())] {
+        assert_eq!(replayer.divergence(), Some("output 1 at instruction 3 but the log has output 3 0"));
+        assert_eq!(replayer.matched(), 1);
+    }
+}
+
+#[test]
+fn replay_reports_unused_events() {
+    let events = vec![
+        event(Direction::Input, 1, 8),
+        event(Direction::Output, 3, 1),
+        event(Direction::Output, 4, 2),
+    ];
+    let replayer = replay_big(EQUALS_EIGHT, events);
+    assert_eq!(replayer.divergence(), Some("halted at instruction 4 with 1 events left"));
+}
@@ -0,0 +1,106 @@
+//! Exit codes, input sources and ASCII mode of the `int-code-run` binary.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{self, Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Outputs the sum of three inputs.
+const SUM: &str = "3,20,3,21,3,22,1,20,21,23,1,22,23,23,4,23,99";
+/// Echoes input until it reads a zero.
+const ECHO: &str = "3,11,1005,11,6,99,4,11,1105,1,0,0";
+
+const EXIT_HALTED: i32 = 0;
+const EXIT_USAGE: i32 = 1;
+const EXIT_LOAD_ERROR: i32 = 2;
+const EXIT_STEP_LIMIT: i32 = 3;
+const EXIT_INPUT_EXHAUSTED: i32 = 4;
+const EXIT_PROGRAM_ERROR: i32 = 5;
+
+fn temp_file(name: &str, contents: &str) -> PathBuf {
+    let file = env::temp_dir().join(format!("int_code_run_{}_{}", name, process::id()));
+    fs::write(&file, contents).unwrap();
+    file
+}
+
+static PROGRAMS: AtomicUsize = AtomicUsize::new(0);
+
+fn run(program: &str, args: &[&str], stdin: &str) -> Output {
+    let file = temp_file(&format!("program_{}", PROGRAMS.fetch_add(1, Ordering::SeqCst)), program);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_int-code-run"))
+        .arg(&file)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Runs that fail early may close stdin before it is written
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap_or(());
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(file).unwrap();
+    output
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn halting_programs_exit_with_zero() {
+    let output = run(SUM, &["--input", "1,2", "--input", "3"], "");
+    assert_eq!(output.status.code(), Some(EXIT_HALTED));
+    assert_eq!(stdout(&output), "6\n");
+}
+
+#[test]
+fn each_kind_of_failure_has_its_own_exit_code() {
+    assert_eq!(run(SUM, &["--bogus"], "").status.code(), Some(EXIT_USAGE));
+    assert_eq!(run(SUM, &["--input-file", "a", "--stdin"], "").status.code(), Some(EXIT_USAGE));
+    assert_eq!(run("1,2,x", &[], "").status.code(), Some(EXIT_LOAD_ERROR));
+    assert_eq!(run(SUM, &["--input-file", "/nonexistent/inputs"], "").status.code(), Some(EXIT_LOAD_ERROR));
+    assert_eq!(run(SUM, &["--patch", "1=x"], "").status.code(), Some(EXIT_LOAD_ERROR));
+    assert_eq!(run("1105,1,0", &["--max-steps", "10"], "").status.code(), Some(EXIT_STEP_LIMIT));
+    assert_eq!(run(SUM, &["--input", "1,2"], "").status.code(), Some(EXIT_INPUT_EXHAUSTED));
+    assert_eq!(run("98", &[], "").status.code(), Some(EXIT_PROGRAM_ERROR));
+    assert_eq!(run("1101,1,1,-1,99", &[], "").status.code(), Some(EXIT_PROGRAM_ERROR));
+
+    let missing = Command::new(env!("CARGO_BIN_EXE_int-code-run")).arg("/nonexistent/program").output().unwrap();
+    assert_eq!(missing.status.code(), Some(EXIT_LOAD_ERROR));
+}
+
+#[test]
+fn inputs_come_from_arguments_then_a_file() {
+    let inputs = temp_file("inputs", "2\n3\n");
+    let output = run(SUM, &["--input", "1", "--input-file", inputs.to_str().unwrap()], "");
+    fs::remove_file(inputs).unwrap();
+    assert_eq!(output.status.code(), Some(EXIT_HALTED));
+    assert_eq!(stdout(&output), "6\n");
+}
+
+#[test]
+fn inputs_come_from_stdin() {
+    let output = run(SUM, &["--stdin"], "4, 5\n6\n");
+    assert_eq!(output.status.code(), Some(EXIT_HALTED));
+    assert_eq!(stdout(&output), "15\n");
+
+    let output = run(SUM, &["--stdin"], "4\n");
+    assert_eq!(output.status.code(), Some(EXIT_INPUT_EXHAUSTED));
+
+    let output = run(SUM, &["--stdin"], "4\nfive\n");
+    assert_eq!(output.status.code(), Some(EXIT_LOAD_ERROR));
+}
+
+#[test]
+fn ascii_mode_converts_lines_and_characters() {
+    // 1000 isn't ASCII so it is printed as a number, and the line is echoed with its newline
+    let output = run(ECHO, &["--ascii", "--stdin", "--input", "1000"], "hi\n");
+    assert_eq!(output.status.code(), Some(EXIT_INPUT_EXHAUSTED));
+    assert_eq!(stdout(&output), "1000\nhi\n");
+
+    let output = run(ECHO, &["--stdin"], "104 105\n0\n");
+    assert_eq!(output.status.code(), Some(EXIT_HALTED));
+    assert_eq!(stdout(&output), "104\n105\n");
+}
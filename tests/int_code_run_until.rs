@@ -0,0 +1,123 @@
+//! Running `int_code_big::Computer` until a stop condition fires, and resuming afterwards.
+
+use std::collections::VecDeque;
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+
+use int_code_big::{Computer, Stop, StopCondition};
+
+/// Reads n, then outputs 1 to n from a counter at 101.
+const COUNT: &str = "3,100,1001,101,1,101,4,101,8,101,100,102,1006,102,2,99";
+const COUNTER: i64 = 101;
+const OUTPUT: i64 = 6;
+
+struct QueueIO {
+    inputs: VecDeque<BigInt>,
+    outputs: Vec<BigInt>,
+}
+
+impl int_code_big::IO for QueueIO {
+    fn get_input(&mut self) -> BigInt {
+        self.inputs.pop_front().expect("program read more input than given")
+    }
+
+    fn output(&mut self, output: BigInt) {
+        self.outputs.push(output)
+    }
+}
+
+fn count_to(n: i64) -> (Computer, QueueIO) {
+    let computer = Computer::from_memory(int_code_big::parse_memory(COUNT).unwrap());
+    (computer, QueueIO { inputs: vec![BigInt::from(n)].into(), outputs: Vec::new() })
+}
+
+fn numbers(values: &[i64]) -> Vec<BigInt> {
+    values.iter().map(|value| BigInt::from(*value)).collect()
+}
+
+#[test]
+fn address_conditions_stop_before_the_instruction() {
+    let (mut computer, mut io) = count_to(3);
+    let conditions = [StopCondition::Address(BigInt::from(OUTPUT))];
+
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Condition(0));
+    assert_eq!(*computer.program_counter(), BigInt::from(OUTPUT));
+    assert_eq!(computer[&BigInt::from(COUNTER)], BigInt::from(1));
+    assert!(io.outputs.is_empty());
+
+    // Resuming runs the instruction and stops there again on the next pass of the loop
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Condition(0));
+    assert_eq!(computer[&BigInt::from(COUNTER)], BigInt::from(2));
+    assert_eq!(io.outputs, numbers(&[1]));
+}
+
+#[test]
+fn output_conditions_stop_after_the_output() {
+    let (mut computer, mut io) = count_to(5);
+    let conditions = [StopCondition::Output(Box::new(|output| *output > BigInt::from(2)))];
+
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Condition(0));
+    assert_eq!(io.outputs, numbers(&[1, 2, 3]));
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Condition(0));
+    assert_eq!(io.outputs, numbers(&[1, 2, 3, 4]));
+}
+
+#[test]
+fn output_equals_conditions_stop_after_that_value() {
+    let (mut computer, mut io) = count_to(5);
+    let conditions = [StopCondition::OutputEquals(BigInt::from(2))];
+
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Condition(0));
+    assert_eq!(io.outputs, numbers(&[1, 2]));
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Halted);
+    assert_eq!(io.outputs, numbers(&[1, 2, 3, 4, 5]));
+}
+
+#[test]
+fn input_conditions_stop_before_reading() {
+    let (mut computer, mut io) = count_to(2);
+    let conditions = [StopCondition::WaitingForInput];
+
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Condition(0));
+    assert_eq!(io.inputs.len(), 1);
+
+    // Resuming reads the input rather than stopping before it again
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Halted);
+    assert!(io.inputs.is_empty());
+    assert_eq!(io.outputs, numbers(&[1, 2]));
+}
+
+#[test]
+fn memory_conditions_fire_once_per_change() {
+    let (mut computer, mut io) = count_to(5);
+    let conditions = [StopCondition::Memory(BigInt::from(COUNTER), Box::new(|counter| *counter >= BigInt::from(3)))];
+
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Condition(0));
+    assert_eq!(computer[&BigInt::from(COUNTER)], BigInt::from(3));
+
+    // The condition still holds, so it doesn't fire again until it stops holding and holds again
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Halted);
+    assert_eq!(io.outputs, numbers(&[1, 2, 3, 4, 5]));
+}
+
+#[test]
+fn the_condition_that_fired_is_reported() {
+    let (mut computer, mut io) = count_to(5);
+    let conditions = [
+        StopCondition::Address(BigInt::from(1000)),
+        StopCondition::OutputEquals(BigInt::from(4)),
+        StopCondition::Output(Box::new(|output| *output > BigInt::from(1))),
+    ];
+
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Condition(2));
+    assert_eq!(io.outputs, numbers(&[1, 2]));
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Condition(2));
+    assert_eq!(io.outputs, numbers(&[1, 2, 3]));
+
+    // When several fire at once the earliest in the list wins
+    assert_eq!(computer.run_until(&mut io, &conditions).unwrap(), Stop::Condition(1));
+    assert_eq!(io.outputs, numbers(&[1, 2, 3, 4]));
+}
@@ -0,0 +1,108 @@
+//! Detection of programs writing into their own instructions by `int_code_big`.
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+
+use int_code_big::{Computer, ComputerExecutionError, ComputerState, SelfModificationMode};
+
+fn computer(program: &str, mode: SelfModificationMode) -> Computer {
+    let mut computer = Computer::from_memory(int_code_big::parse_memory(program).unwrap());
+    computer.set_self_modification_mode(mode);
+    computer
+}
+
+/// Runs to the end without input, returning the outputs.
+fn run(computer: &mut Computer) -> Vec<i64> {
+    let mut outputs = Vec::new();
+    loop {
+        match computer.state() {
+            ComputerState::Running => computer.step().unwrap(),
+            ComputerState::WaitingToOutput(_) => outputs.push(computer.output().unwrap().to_string().parse().unwrap()),
+            ComputerState::WaitingForInput => panic!("unexpected input"),
+            ComputerState::Halted => return outputs,
+        }
+    }
+}
+
+/// The pc, address and value of each reported write.
+fn modifications(computer: &Computer) -> Vec<(i64, i64, i64)> {
+    computer.self_modifications().iter()
+        .map(|modification| {
+            let number = |value: &BigInt| value.to_string().parse().unwrap();
+            (number(&modification.pc), number(&modification.address), number(&modification.value))
+        })
+        .collect()
+}
+
+#[test]
+fn writes_to_executed_addresses_are_reported() {
+    // The first add writes to data at 9, the second overwrites the first add's opcode
+    let mut reporting = computer("1101,2,3,9,1101,7,7,0,99,0", SelfModificationMode::Report);
+    run(&mut reporting);
+    assert_eq!(modifications(&reporting), vec![(4, 0, 14)]);
+}
+
+#[test]
+fn writes_to_the_arguments_of_the_next_instruction_are_reported() {
+    // The first add changes the last argument of the second, which then writes over the first
+    let mut computer = computer("1101,5,0,6,1101,0,0,0,99", SelfModificationMode::Report);
+    run(&mut computer);
+    assert_eq!(modifications(&computer), vec![(0, 6, 5), (4, 0, 5)]);
+}
+
+#[test]
+fn writes_to_the_target_of_the_next_jump_are_reported() {
+    // The add changes what the output at the jump's target prints
+    let mut computer = computer("1101,3,4,10,1105,1,9,99,0,104,5,99", SelfModificationMode::Report);
+    assert_eq!(run(&mut computer), vec![7]);
+    assert_eq!(modifications(&computer), vec![(0, 10, 7)]);
+}
+
+#[test]
+fn writes_past_the_next_instruction_are_not_reported() {
+    let mut computer = computer("1101,3,4,9,1101,0,0,10,99,0,0", SelfModificationMode::Report);
+    run(&mut computer);
+    assert_eq!(modifications(&computer), vec![]);
+}
+
+#[test]
+fn instructions_executed_while_ignoring_are_not_tracked() {
+    let mut ignoring = computer("1101,2,3,9,1101,7,7,0,99,0", SelfModificationMode::Ignore);
+    ignoring.step().unwrap();
+    ignoring.set_self_modification_mode(SelfModificationMode::Report);
+    run(&mut ignoring);
+    assert_eq!(modifications(&ignoring), vec![]);
+
+    let mut reporting = computer("1101,2,3,9,1101,7,7,0,99,0", SelfModificationMode::Report);
+    run(&mut reporting);
+    assert_eq!(modifications(&reporting), vec![(4, 0, 14)]);
+}
+
+#[test]
+fn trapped_writes_leave_the_computer_unchanged() {
+    let mut computer = computer("1101,5,0,6,1101,0,0,0,99", SelfModificationMode::Trap);
+    match computer.step() {
+        Err(ComputerExecutionError::SelfModifyingWrite { pc, address }) => {
+            assert_eq!(pc, BigInt::from(0));
+            assert_eq!(address, BigInt::from(6));
+        }
+        other => panic!("expected a trapped write but got {:?}", other),
+    }
+    assert_eq!(*computer.program_counter(), BigInt::from(0));
+    assert_eq!(*computer.state(), ComputerState::Running);
+    assert_eq!(computer.instructions(), 0);
+    assert_eq!(computer[&BigInt::from(6)], BigInt::from(0));
+    assert!(computer.self_modifications().is_empty());
+}
+
+#[test]
+fn trapped_input_stays_waiting_for_input() {
+    let mut computer = computer("3,1,99", SelfModificationMode::Trap);
+    computer.step().unwrap();
+    assert!(matches!(computer.input(BigInt::from(5)), Err(ComputerExecutionError::SelfModifyingWrite { .. })));
+    assert_eq!(*computer.program_counter(), BigInt::from(0));
+    assert_eq!(*computer.state(), ComputerState::WaitingForInput);
+    assert_eq!(computer[&BigInt::from(1)], BigInt::from(1));
+}
@@ -0,0 +1,131 @@
+//! Polynomial arithmetic and symbolic evaluation of day 2 style programs.
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code_symbolic.rs"]
+pub mod int_code_symbolic;
+
+use int_code_symbolic::{Polynomial, SymbolicError, Value};
+
+/// The example from day 2, which computes `(memory[9] + memory[10]) * memory[11]` into cell 0.
+const EXAMPLE: &str = "1,9,10,3,2,3,11,0,99,30,40,50";
+
+fn memory(program: &str) -> Vec<BigInt> {
+    program.split(',').map(|word| word.parse().unwrap()).collect()
+}
+
+fn numbers(values: &[i64]) -> Vec<BigInt> {
+    values.iter().map(|value| BigInt::from(*value)).collect()
+}
+
+#[test]
+fn polynomial_arithmetic() {
+    let x = Polynomial::variable(0);
+    let y = Polynomial::variable(1);
+    let three = Polynomial::constant(3);
+
+    // (x + 3)(x - y) = x^2 - x*y + 3x - 3y
+    let product = &(&x + &three) * &(&x - &y);
+    assert_eq!(product.to_string(), "-v0*v1 + v0^2 + 3*v0 - 3*v1");
+    assert_eq!(product.to_string_with(&["noun", "verb"]), "-noun*verb + noun^2 + 3*noun - 3*verb");
+    assert_eq!(product.variables(), vec![0, 1]);
+    assert_eq!(product.degree_in(0), 2);
+    assert_eq!(product.degree_in(1), 1);
+    assert_eq!(product.evaluate(&numbers(&[2, 5])), BigInt::from(-15));
+
+    assert_eq!(&product - &product, Polynomial::constant(0));
+    assert_eq!((&product - &product).to_string(), "0");
+    assert_eq!((-&x).to_string(), "-v0");
+    assert_eq!(three.as_constant(), Some(BigInt::from(3)));
+    assert_eq!(x.as_constant(), None);
+}
+
+#[test]
+fn substitution_leaves_univariate_polynomials() {
+    let x = Polynomial::variable(0);
+    let y = Polynomial::variable(1);
+    let polynomial = &(&x * &y) + &(&y * &y);
+
+    let substituted = polynomial.substitute(0, &BigInt::from(4));
+    assert_eq!(substituted.to_string(), "v1^2 + 4*v1");
+    assert_eq!(substituted.univariate_coefficients(1), Some(numbers(&[0, 4, 1])));
+    assert_eq!(polynomial.univariate_coefficients(1), None);
+}
+
+#[test]
+fn example_evaluates_to_a_polynomial() {
+    let polynomial = int_code_symbolic::evaluate_cell(&memory(EXAMPLE), &[9, 10], 0).unwrap();
+    assert_eq!(polynomial.to_string(), "50*v0 + 50*v1");
+    assert_eq!(polynomial.evaluate(&numbers(&[30, 40])), BigInt::from(3500));
+
+    let cells = int_code_symbolic::evaluate(&memory(EXAMPLE), &[]).unwrap();
+    assert_eq!(cells[0], Value::Known(Polynomial::constant(3500)));
+    assert_eq!(cells[3], Value::Known(Polynomial::constant(70)));
+}
+
+#[test]
+fn reads_through_symbolic_addresses_are_unknown() {
+    // Cells 1 and 2 are the addresses the first add reads, so its result is unknown, which makes
+    // the second instruction's read address unknown as well
+    let cells = int_code_symbolic::evaluate(&memory(EXAMPLE), &[1, 2]).unwrap();
+    assert_eq!(cells[3], Value::Unknown);
+    assert!(matches!(
+        int_code_symbolic::evaluate_cell(&memory(EXAMPLE), &[1, 2], 0),
+        Err(SymbolicError::UnknownResult { address: 0 })
+    ));
+}
+
+#[test]
+fn unsupported_programs_are_rejected() {
+    assert!(matches!(
+        int_code_symbolic::evaluate(&memory("1,0,0,0,3,0,99"), &[]),
+        Err(SymbolicError::UnsupportedOpCode { pc: 4, .. })
+    ));
+    assert!(matches!(
+        int_code_symbolic::evaluate(&memory("1,0,0,0,99"), &[0]),
+        Err(SymbolicError::SymbolicOpCode { pc: 0 })
+    ));
+    assert!(matches!(
+        int_code_symbolic::evaluate(&memory("1,0,0,0,99"), &[3]),
+        Err(SymbolicError::SymbolicAddress { pc: 0 })
+    ));
+    match int_code_symbolic::evaluate(&memory("1,0,0,100,99"), &[]) {
+        Err(SymbolicError::OutOfRange { pc, address }) => {
+            assert_eq!(pc, 0);
+            assert_eq!(address, BigInt::from(100));
+        }
+        other => panic!("expected OutOfRange but got {:?}", other),
+    }
+    assert!(matches!(
+        int_code_symbolic::evaluate(&memory("1,0,0,0"), &[]),
+        Err(SymbolicError::OutOfRange { pc: 4, .. })
+    ));
+}
+
+#[test]
+fn variables_and_cells_past_the_end_are_errors() {
+    assert!(matches!(
+        int_code_symbolic::evaluate(&memory(EXAMPLE), &[9, 12]),
+        Err(SymbolicError::VariableOutOfRange { variable: 1, address: 12 })
+    ));
+    assert!(matches!(
+        int_code_symbolic::evaluate_cell(&memory(EXAMPLE), &[], 12),
+        Err(SymbolicError::CellOutOfRange { address: 12 })
+    ));
+}
+
+#[test]
+fn solve_finds_every_assignment() {
+    let polynomial = int_code_symbolic::evaluate_cell(&memory(EXAMPLE), &[9, 10], 0).unwrap();
+    let solutions = int_code_symbolic::solve(&polynomial, &BigInt::from(500), &[0..=99, 0..=99]);
+    assert_eq!(solutions, (0..=10).map(|a| vec![a, 10 - a]).collect::<Vec<_>>());
+
+    // 100 * noun + verb, like day 2, including the edges of the domain
+    let polynomial = &(&Polynomial::variable(0) * &Polynomial::constant(100)) + &Polynomial::variable(1);
+    assert_eq!(int_code_symbolic::solve(&polynomial, &BigInt::from(9999), &[0..=99, 0..=99]), vec![vec![99, 99]]);
+    assert!(int_code_symbolic::solve(&polynomial, &BigInt::from(10000), &[0..=99, 0..=99]).is_empty());
+
+    // Quadratic in the last variable
+    let x = Polynomial::variable(0);
+    assert_eq!(int_code_symbolic::solve(&(&x * &x), &BigInt::from(49), &[-10..=10]), vec![vec![-7], vec![7]]);
+}
@@ -0,0 +1,147 @@
+//! The n-body simulation and moon parser with the day 12 examples.
+
+use std::collections::HashSet;
+
+#[path = "../src/n_body.rs"]
+pub mod n_body;
+
+use n_body::{Body, Format, MoonParseError, Simulation};
+
+const FIRST_EXAMPLE: &str = "<x=-1, y=0, z=2>\n<x=2, y=-10, z=-7>\n<x=4, y=-8, z=8>\n<x=3, y=5, z=-1>\n";
+const SECOND_EXAMPLE: &str = "<x=-8, y=-10, z=0>\n<x=5, y=5, z=10>\n<x=2, y=-7, z=3>\n<x=9, y=-8, z=-3>\n";
+
+fn simulation(text: &str) -> Simulation<3> {
+    Simulation::new(n_body::parse_moons(text).unwrap())
+}
+
+#[test]
+fn steps_apply_gravity_then_velocity() {
+    let mut moons = simulation(FIRST_EXAMPLE);
+    moons.step();
+    assert_eq!(moons.steps(), 1);
+    assert_eq!(moons.bodies()[0], Body { position: [2, -1, 1], velocity: [3, -1, -1] });
+    assert_eq!(moons.bodies()[3], Body { position: [2, 2, 0], velocity: [-1, -3, 1] });
+}
+
+#[test]
+fn energy_multiplies_potential_and_kinetic() {
+    let body = Body { position: [2, 1, -3], velocity: [-3, -2, 1] };
+    assert_eq!((body.potential_energy(), body.kinetic_energy(), body.energy()), (6, 6, 36));
+    assert_eq!(Body::new([1, 2, 3]).energy(), 0);
+
+    let mut moons = simulation(FIRST_EXAMPLE);
+    moons.run(10);
+    assert_eq!(moons.total_energy(), 179);
+
+    let mut moons = simulation(SECOND_EXAMPLE);
+    moons.run(100);
+    assert_eq!(moons.total_energy(), 1940);
+}
+
+#[test]
+fn periods_agree() {
+    let moons = simulation(FIRST_EXAMPLE);
+    assert_eq!(moons.period(), 2772);
+    assert_eq!(moons.period_by_axis(), 2772);
+    assert_eq!(moons.find_repeat(3000), Some((0, 2772)));
+    assert_eq!(moons.find_repeat(100), None);
+
+    assert_eq!(simulation(SECOND_EXAMPLE).period_by_axis(), 4686774924);
+}
+
+#[test]
+fn periods_start_from_the_current_state() {
+    let mut moons = simulation(FIRST_EXAMPLE);
+    moons.run(5);
+    assert_eq!(moons.period(), 2772);
+    assert_eq!(moons.find_repeat(3000), Some((5, 2777)));
+}
+
+#[test]
+fn equal_states_hash_alike_whatever_the_steps() {
+    let start = simulation(FIRST_EXAMPLE);
+    let mut later = start.clone();
+    later.run(2772);
+    assert_eq!(later.steps(), 2772);
+    assert_eq!(later, start);
+
+    let mut states = HashSet::new();
+    states.insert(start.clone());
+    assert!(!states.insert(later));
+    let mut moved = start;
+    moved.step();
+    assert!(states.insert(moved));
+}
+
+#[test]
+fn single_axes_simulate_on_their_own() {
+    let mut moons = simulation(FIRST_EXAMPLE);
+    let mut x = moons.axis(0);
+    moons.run(7);
+    x.run(7);
+    let positions: Vec<i64> = moons.bodies().iter().map(|body| body.position[0]).collect();
+    let axis: Vec<i64> = x.bodies().iter().map(|body| body.position[0]).collect();
+    assert_eq!(axis, positions);
+}
+
+#[test]
+fn exports_every_step() {
+    let mut out = Vec::new();
+    simulation(FIRST_EXAMPLE).export(&mut out, Format::Csv, 1).unwrap();
+    let csv = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 1 + 2 * 4);
+    assert_eq!(lines[0], "step,body,p0,p1,p2,v0,v1,v2");
+    assert_eq!(lines[1], "0,0,-1,0,2,0,0,0");
+    assert_eq!(lines[5], "1,0,2,-1,1,3,-1,-1");
+
+    let mut out = Vec::new();
+    let mut moons = Simulation::new(vec![[1, 2], [3, 2]]);
+    moons.export(&mut out, Format::Jsonl, 1).unwrap();
+    assert_eq!(moons.steps(), 1);
+    assert_eq!(String::from_utf8(out).unwrap(), "\
+{\"step\":0,\"bodies\":[{\"position\":[1,2],\"velocity\":[0,0]},{\"position\":[3,2],\"velocity\":[0,0]}]}
+{\"step\":1,\"bodies\":[{\"position\":[2,2],\"velocity\":[1,0]},{\"position\":[2,2],\"velocity\":[-1,0]}]}
+");
+}
+
+#[test]
+fn axes_can_come_in_any_order() {
+    assert_eq!(n_body::parse_moons("<z=3, x=1, y=2>\n< y = -2 ,z=-3,x=-1 >").unwrap(), vec![[1, 2, 3], [-1, -2, -3]]);
+}
+
+#[test]
+fn comments_and_blank_lines_are_skipped() {
+    let text = "# Io\n<x=-1, y=0, z=2>  # start\n\n   \n<x=2, y=-10, z=-7>\n";
+    assert_eq!(n_body::parse_moons(text).unwrap(), vec![[-1, 0, 2], [2, -10, -7]]);
+}
+
+#[test]
+fn errors_give_the_line() {
+    assert_eq!(
+        n_body::parse_moons("<x=1, y=2, z=3>\n\n<x=1, z=3>"),
+        Err(MoonParseError::MissingAxis { line: 3, axis: 'y' })
+    );
+    assert_eq!(
+        n_body::parse_moons("<x=1, y=2, x=3>"),
+        Err(MoonParseError::DuplicateAxis { line: 1, axis: 'x' })
+    );
+    assert_eq!(
+        n_body::parse_moons("# moons\n<x=1, y=2, w=3>"),
+        Err(MoonParseError::UnknownAxis { line: 2, axis: "w".to_string() })
+    );
+    assert_eq!(
+        n_body::parse_moons("<x=1, y=two, z=3>"),
+        Err(MoonParseError::InvalidCoordinate { line: 1, text: "two".to_string() })
+    );
+    assert_eq!(
+        n_body::parse_moons("x=1, y=2, z=3"),
+        Err(MoonParseError::InvalidMoon { line: 1, text: "x=1, y=2, z=3".to_string() })
+    );
+}
+
+#[test]
+fn there_must_be_a_moon() {
+    assert_eq!(n_body::parse_moons(""), Err(MoonParseError::NoMoons));
+    assert_eq!(n_body::parse_moons("# nothing here\n\n"), Err(MoonParseError::NoMoons));
+}
@@ -0,0 +1,60 @@
+//! Exploring a hand-written maze with the repair droid.
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+#[path = "../src/repair_droid.rs"]
+pub mod repair_droid;
+
+use repair_droid::{Cell, DroidError, Movement, Status};
+
+/// A corridor running east from the start to the oxygen system three cells away, walled in
+/// everywhere else. The droid keeps its x in 1000 and only moves east or west along y = 0.
+const CORRIDOR: &str = "3,1001,1008,1001,4,1002,1008,1001,3,1003,1,1000,1002,1004,102,-1,1003,1005,\
+1,1004,1005,1004,1,1002,1003,1005,1006,1005,60,1007,1004,0,1005,1005,1005,60,107,3,1004,1005,1005,\
+1005,60,1001,1004,0,1000,1008,1000,3,1005,1001,1005,1,1005,4,1005,1105,1,0,104,0,1105,1,0";
+
+fn droid(program: &str) -> int_code_big::Computer {
+    int_code_big::Computer::from_memory(int_code_big::parse_memory(program).unwrap())
+}
+
+#[test]
+fn commands_report_the_status() {
+    let mut droid = droid(CORRIDOR);
+    assert_eq!(repair_droid::command(&mut droid, Movement::North).unwrap(), Status::Wall);
+    assert_eq!(repair_droid::command(&mut droid, Movement::West).unwrap(), Status::Wall);
+    assert_eq!(repair_droid::command(&mut droid, Movement::East).unwrap(), Status::Moved);
+    assert_eq!(repair_droid::command(&mut droid, Movement::East).unwrap(), Status::Moved);
+    assert_eq!(repair_droid::command(&mut droid, Movement::East).unwrap(), Status::FoundOxygen);
+}
+
+#[test]
+fn exploring_maps_the_whole_maze() {
+    let map = repair_droid::explore(droid(CORRIDOR)).unwrap();
+    assert_eq!(map.render(), " #### \n#D..O#\n #### \n");
+    assert_eq!(map.cell((2, 0)), Cell::Open);
+    assert_eq!(map.cell((2, 1)), Cell::Wall);
+    assert_eq!(map.cell((10, 10)), Cell::Wall, "unexplored cells are walls");
+    assert_eq!(map.oxygen(), Some((3, 0)));
+}
+
+#[test]
+fn distances_are_measured_through_open_cells() {
+    let map = repair_droid::explore(droid(CORRIDOR)).unwrap();
+    assert_eq!(map.oxygen_distance(), Some(3));
+    assert_eq!(map.fill_time(), Some(3));
+    let distances = map.distances((1, 0));
+    assert_eq!(distances.len(), 4);
+    assert_eq!(distances[&(0, 0)], 1);
+    assert_eq!(distances[&(3, 0)], 2);
+}
+
+#[test]
+fn misbehaving_droids_are_errors() {
+    assert!(matches!(repair_droid::command(&mut droid("99"), Movement::North), Err(DroidError::Halted)));
+    match repair_droid::command(&mut droid("3,0,104,7,99"), Movement::North) {
+        Err(DroidError::InvalidStatus { value }) => assert_eq!(value, BigInt::from(7)),
+        other => panic!("expected InvalidStatus but got {:?}", other),
+    }
+}
@@ -0,0 +1,113 @@
+//! Reading the scaffold camera and programming the vacuum robot with the day 17 examples.
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+#[path = "../src/scaffold.rs"]
+pub mod scaffold;
+
+use scaffold::{Move, Routines, ScaffoldError, Turn, View, MAX_ROUTINE_LENGTH};
+
+const PART_1_EXAMPLE: &str = "\
+..#..........
+..#..........
+#######...###
+#.#...#...#.#
+#############
+..#...#...#..
+..#####...^..
+";
+
+const PART_2_EXAMPLE: &str = "\
+#######...#####
+#.....#...#...#
+#.....#...#...#
+......#...#...#
+......#...###.#
+......#.....#.#
+^########...#.#
+......#.#...#.#
+......#########
+........#...#..
+....#########..
+....#...#......
+....#...#......
+....#...#......
+....#####......
+";
+
+/// Halts without output unless woken, which turns the first add into a multiply. When woken it
+/// reads five lines and reports 1000 dust.
+const ROBOT: &str = "1,0,0,200,1008,200,4,201,1006,201,33,\
+3,100,1008,100,10,101,1,102,101,102,1008,102,5,103,1005,103,31,1105,1,11,104,1000,99";
+
+fn computer(program: &str) -> int_code_big::Computer {
+    int_code_big::Computer::from_memory(int_code_big::parse_memory(program).unwrap())
+}
+
+fn join(moves: &[Move]) -> String {
+    moves.iter().map(|step| step.to_string()).collect::<Vec<_>>().join(",")
+}
+
+#[test]
+fn intersections_are_aligned() {
+    let view = View::parse(PART_1_EXAMPLE);
+    assert_eq!(view.intersections(), vec![(2, 2), (2, 4), (6, 4), (10, 4)]);
+    assert_eq!(view.alignment_sum(), 76);
+    assert_eq!(view.robot(), Some(((10, 6), (0, -1))));
+}
+
+#[test]
+fn paths_go_straight_on_at_intersections() {
+    let path = View::parse(PART_2_EXAMPLE).path().unwrap();
+    assert_eq!(join(&path), "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2");
+}
+
+#[test]
+fn paths_start_forward_when_facing_the_scaffold() {
+    let path = View::parse(">###\n...#\n...#\n").path().unwrap();
+    assert_eq!(path, vec![Move { turn: None, distance: 3 }, Move { turn: Some(Turn::Right), distance: 2 }]);
+    assert_eq!(join(&path), "3,R,2");
+
+    assert!(matches!(View::parse("###\n").path(), Err(ScaffoldError::NoRobot)));
+}
+
+#[test]
+fn routines_expand_back_to_the_path() {
+    let path = View::parse(PART_2_EXAMPLE).path().unwrap();
+    let routines = Routines::compress(&path).unwrap();
+    let expanded: Vec<Move> = routines.main.iter()
+        .flat_map(|&function| routines.functions[function].clone())
+        .collect();
+    assert_eq!(expanded, path);
+    assert!(routines.lines().iter().all(|line| line.len() <= MAX_ROUTINE_LENGTH), "{:?}", routines.lines());
+}
+
+#[test]
+fn unused_functions_repeat_the_first() {
+    let path = View::parse("^\n#\n").path().unwrap();
+    assert!(path.is_empty());
+    assert_eq!(Routines::compress(&path), None);
+
+    let path = View::parse(">##\n").path().unwrap();
+    let routines = Routines::compress(&path).unwrap();
+    assert_eq!(routines.lines(), vec!["A", "2", "2", "2"]);
+}
+
+#[test]
+fn woken_robots_report_dust() {
+    let path = View::parse(PART_2_EXAMPLE).path().unwrap();
+    let routines = Routines::compress(&path).unwrap();
+    assert_eq!(scaffold::wake(computer(ROBOT), &routines, false).unwrap(), BigInt::from(1000));
+    assert!(matches!(scaffold::wake(computer("1,0,0,200,99"), &routines, true), Err(ScaffoldError::NoDust)));
+}
+
+#[test]
+fn cameras_print_the_view() {
+    let camera = computer("104,35,104,94,104,10,99");
+    let text = scaffold::read_camera(camera).unwrap();
+    assert_eq!(text, "#^\n");
+    assert_eq!(View::parse(&text).robot(), Some(((1, 0), (0, -1))));
+    assert!(matches!(scaffold::read_camera(computer("104,1000,99")), Err(ScaffoldError::InvalidOutput { .. })));
+}
@@ -0,0 +1,116 @@
+//! Springscript scripts, the droid runner and the script search against simulated hulls.
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+#[path = "../src/springdroid.rs"]
+pub mod springdroid;
+
+use springdroid::{Mode, Outcome, Register, Script, SpringdroidError, MAX_INSTRUCTIONS};
+
+fn droid(program: &str) -> int_code_big::Computer {
+    int_code_big::Computer::from_memory(int_code_big::parse_memory(program).unwrap())
+}
+
+fn hull(tiles: &str) -> Vec<bool> {
+    tiles.chars().map(|tile| tile == '#').collect()
+}
+
+/// Bit n set when there is ground n + 1 tiles after `position`, with ground past the end.
+fn ground(hull: &[bool], position: usize) -> u16 {
+    (0..9)
+        .filter(|&index| hull.get(position + index + 1).cloned().unwrap_or(true))
+        .fold(0, |ground, index| ground | 1 << index)
+}
+
+/// Runs the script along the hull like the droid would, printing the hull as its animation if
+/// it falls.
+fn simulate(script: &Script, tiles: &str) -> Outcome {
+    let hull = hull(tiles);
+    let mut position = 0;
+    while position < hull.len() {
+        position += if script.jumps(ground(&hull, position)) { 4 } else { 1 };
+        if !hull.get(position).cloned().unwrap_or(true) {
+            return Outcome::Fell(format!("\n\n@\n{}\n\n", tiles));
+        }
+    }
+    Outcome::Damage(BigInt::from(tiles.len()))
+}
+
+#[test]
+fn scripts_round_trip_through_text() {
+    let script = Script::new(Mode::Walk).not(Register::A, Register::J).and(Register::D, Register::J);
+    assert_eq!(script.to_string(), "NOT A J\nAND D J\nWALK\n");
+    assert_eq!(Script::parse("NOT A J\n\nAND D J\nWALK\n").unwrap(), script);
+    assert_eq!(script.compile().unwrap(), script.to_string());
+}
+
+#[test]
+fn invalid_scripts_are_rejected() {
+    assert!(matches!(Script::parse("NOT A J\n"), Err(SpringdroidError::MissingMode)));
+    assert!(matches!(Script::parse("NOT A J\nJUMP\nWALK"), Err(SpringdroidError::InvalidInstruction { line: 2, .. })));
+    assert!(matches!(Script::parse("NOT X J\nWALK"), Err(SpringdroidError::InvalidInstruction { line: 1, .. })));
+
+    let unwritable = Script::new(Mode::Walk).not(Register::A, Register::B);
+    assert!(matches!(unwritable.compile(), Err(SpringdroidError::NotWritable { index: 0, register: Register::B })));
+    let unpowered = Script::new(Mode::Walk).or(Register::E, Register::J);
+    assert!(matches!(unpowered.compile(), Err(SpringdroidError::UnpoweredSensor { index: 0, .. })));
+    assert!(Script::new(Mode::Run).or(Register::E, Register::J).compile().is_ok());
+
+    let long = (0..=MAX_INSTRUCTIONS).fold(Script::new(Mode::Walk), |script, _| script.not(Register::J, Register::J));
+    assert!(matches!(long.compile(), Err(SpringdroidError::TooManyInstructions { .. })));
+}
+
+#[test]
+fn scripts_decide_jumps_from_the_sensors() {
+    // Jump over a hole one tile ahead when there is ground to land on
+    let script = Script::new(Mode::Walk).not(Register::A, Register::J).and(Register::D, Register::J);
+    assert!(script.jumps(0b1110));
+    assert!(!script.jumps(0b0110));
+    assert!(!script.jumps(0b1111));
+    assert!(!Script::new(Mode::Walk).jumps(0));
+}
+
+#[test]
+fn droids_report_damage_or_their_fall() {
+    let script = Script::new(Mode::Walk).not(Register::A, Register::J);
+    assert_eq!(springdroid::run(droid("3,100,104,20000,99"), &script).unwrap(), Outcome::Damage(BigInt::from(20000)));
+    assert_eq!(springdroid::run(droid("104,88,104,10,99"), &script).unwrap(), Outcome::Fell("X\n".to_string()));
+    assert!(matches!(springdroid::run(droid("104,-1,99"), &script), Err(SpringdroidError::InvalidOutput { .. })));
+}
+
+#[test]
+fn falls_show_the_hull() {
+    let fell = Outcome::Fell("\n\nWalkway:\n\n.................\n.................\n@................\n#####.#..########\n\n".to_string());
+    assert_eq!(fell.hull(), Some(hull("#####.#..########")));
+    assert_eq!(Outcome::Damage(BigInt::from(1)).hull(), None);
+}
+
+#[test]
+fn search_learns_from_each_fall() {
+    let hulls = ["#####.########", "#####..#.######", "#####...#######"];
+    let mut attempts = 0;
+    let found = springdroid::search_with(Mode::Walk, |script| {
+        attempts += 1;
+        // The droid falls on the first hull it doesn't cross, like a real droid on its course
+        Ok(hulls.iter()
+            .map(|tiles| simulate(script, tiles))
+            .find(|outcome| matches!(outcome, Outcome::Fell(_)))
+            .unwrap_or(Outcome::Damage(BigInt::from(42))))
+    });
+
+    let (script, damage) = found.unwrap().unwrap();
+    assert_eq!(damage, BigInt::from(42));
+    assert!(hulls.iter().all(|tiles| matches!(simulate(&script, tiles), Outcome::Damage(_))), "{}", script);
+    assert!(script.compile().is_ok());
+    // Only scripts crossing every hull seen so far are attempted, one per new fall at most
+    assert!(attempts <= hulls.len() + 1, "{} attempts", attempts);
+}
+
+#[test]
+fn search_gives_up_when_nothing_crosses() {
+    // No jump clears a gap of four
+    let found = springdroid::search_with(Mode::Walk, |script| Ok(simulate(script, "####....#####"))).unwrap();
+    assert_eq!(found, None);
+}
@@ -0,0 +1,226 @@
+//! The day 25 adventure solver playing a scripted fake ship.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+#[path = "../src/text_adventure.rs"]
+pub mod text_adventure;
+
+use text_adventure::{Adventure, AdventureError, Room, Terminal};
+
+const START: &str = "Hull Breach";
+const CHECKPOINT: &str = "Security Checkpoint";
+const FLOOR: &str = "Pressure-Sensitive Floor";
+/// The weight the floor wants, which is the coin and the ring.
+const TARGET: u32 = 10;
+
+/// A small ship answering commands with the same text as the real droid:
+///
+/// ```text
+///   Lab - Kitchen
+///            |
+///       Hull Breach - Security Checkpoint - (north) Pressure-Sensitive Floor
+/// ```
+#[derive(Clone)]
+struct FakeShip {
+    room: &'static str,
+    items: HashMap<&'static str, Vec<&'static str>>,
+    inventory: Vec<&'static str>,
+    stuck: bool,
+    halted: bool,
+    /// The items carried onto the floor each time, shared by every saved copy of the ship.
+    weighed: Rc<RefCell<Vec<Vec<&'static str>>>>,
+}
+
+impl FakeShip {
+    fn new() -> Self {
+        let mut items = HashMap::new();
+        items.insert(START, vec!["mug"]);
+        items.insert("Kitchen", vec!["sticky magnet", "coin"]);
+        items.insert("Lab", vec!["book", "photons"]);
+        items.insert(CHECKPOINT, vec!["ring"]);
+        FakeShip { room: START, items, inventory: Vec::new(), stuck: false, halted: false, weighed: Rc::default() }
+    }
+
+    fn doors(room: &str) -> Vec<(&'static str, &'static str)> {
+        match room {
+            START => vec![("north", "Kitchen"), ("east", CHECKPOINT)],
+            "Kitchen" => vec![("south", START), ("west", "Lab")],
+            "Lab" => vec![("east", "Kitchen")],
+            CHECKPOINT => vec![("north", FLOOR), ("west", START)],
+            _ => vec![],
+        }
+    }
+
+    fn weight(item: &str) -> u32 {
+        match item {
+            "mug" => 1,
+            "coin" => 2,
+            "book" => 4,
+            "ring" => 8,
+            _ => 100,
+        }
+    }
+
+    fn describe(&self, room: &str) -> String {
+        let mut text = format!("\n\n\n== {} ==\nA room on the fake ship.\n\nDoors here lead:\n", room);
+        for (door, _) in FakeShip::doors(room) {
+            text.push_str(&format!("- {}\n", door));
+        }
+        let items = &self.items[room];
+        if !items.is_empty() {
+            text.push_str("\nItems here:\n");
+            for item in items {
+                text.push_str(&format!("- {}\n", item));
+            }
+        }
+        text.push_str("\nCommand?\n");
+        text
+    }
+
+    fn weigh(&mut self) -> String {
+        let mut carried = self.inventory.clone();
+        carried.sort();
+        self.weighed.borrow_mut().push(carried);
+
+        let floor = "\n\n\n== Pressure-Sensitive Floor ==\nAnalyzing...\n\nDoors here lead:\n- south\n\n";
+        let weight: u32 = self.inventory.iter().map(|item| FakeShip::weight(item)).sum();
+        let alert = if weight > TARGET {
+            "lighter"
+        } else if weight < TARGET {
+            "heavier"
+        } else {
+            self.halted = true;
+            return format!("{}A loud, robotic voice says \"Analysis complete! You may proceed.\" and you enter the cockpit.\n\
+                Santa notices your small droid, looks puzzled for a moment, realizes what has happened, and radios your ship directly.\n\
+                \"Oh, hello! You should be able to get in by typing 2424 on the keypad at the main airlock.\"\n", floor);
+        };
+        format!(
+            "{}A loud, robotic voice says \"Alert! Droids on this ship are {} than the detected value!\" and you are ejected back to the checkpoint.\n{}",
+            floor, alert, self.describe(CHECKPOINT)
+        )
+    }
+}
+
+impl Terminal for FakeShip {
+    fn start(&mut self) -> Result<String, AdventureError> {
+        Ok(self.describe(START))
+    }
+
+    fn command(&mut self, command: &str) -> Result<String, AdventureError> {
+        if let Some(item) = command.strip_prefix("take ") {
+            let items = self.items.get_mut(self.room).unwrap();
+            let index = items.iter().position(|other| *other == item).unwrap();
+            let item = items.remove(index);
+            self.inventory.push(item);
+            return Ok(match item {
+                "sticky magnet" => {
+                    self.stuck = true;
+                    "\nThe sticky magnet is stuck to you.  You can't move!!\n\nCommand?\n".to_string()
+                }
+                "photons" => {
+                    self.halted = true;
+                    "\nIt is suddenly completely dark! You are eaten by a Grue!\n".to_string()
+                }
+                _ => format!("\nYou take the {}.\n\nCommand?\n", item),
+            });
+        }
+        if let Some(item) = command.strip_prefix("drop ") {
+            let index = self.inventory.iter().position(|other| *other == item).unwrap();
+            let item = self.inventory.remove(index);
+            self.items.get_mut(self.room).unwrap().push(item);
+            return Ok(format!("\nYou drop the {}.\n\nCommand?\n", item));
+        }
+
+        let next = FakeShip::doors(self.room).into_iter().find(|(door, _)| *door == command);
+        match next {
+            _ if self.stuck => Ok("\nYou can't move!!\n\nCommand?\n".to_string()),
+            Some((_, FLOOR)) => Ok(self.weigh()),
+            Some((_, room)) => {
+                self.room = room;
+                Ok(self.describe(room))
+            }
+            None => Ok("\nYou can't go that way.\n\nCommand?\n".to_string()),
+        }
+    }
+
+    fn is_halted(&self) -> bool {
+        self.halted
+    }
+}
+
+#[test]
+fn rooms_are_parsed_from_the_last_description() {
+    let ship = FakeShip::new();
+    let room = text_adventure::parse_room(&ship.describe("Kitchen")).unwrap();
+    assert_eq!(room, Room {
+        name: "Kitchen".to_string(),
+        description: "A room on the fake ship.".to_string(),
+        doors: vec!["south".to_string(), "west".to_string()],
+        items: vec!["sticky magnet".to_string(), "coin".to_string()],
+    });
+
+    let mut ship = FakeShip::new();
+    ship.command("east").unwrap();
+    let ejected = ship.command("north").unwrap();
+    assert_eq!(text_adventure::parse_room(&ejected).unwrap().name, CHECKPOINT);
+    assert_eq!(text_adventure::parse_room("\nYou can't go that way.\n\nCommand?\n"), None);
+}
+
+#[test]
+fn traps_are_avoided() {
+    let mut adventure = Adventure::with_droid(FakeShip::new());
+    adventure.solve().unwrap();
+
+    assert!(adventure.dangerous().contains("sticky magnet"), "the magnet keeps the droid running");
+    assert!(adventure.dangerous().contains("photons"));
+    let mut inventory = adventure.inventory().to_vec();
+    inventory.sort();
+    assert_eq!(inventory, vec!["book", "coin", "mug", "ring"]);
+
+    let mut rooms: Vec<&String> = adventure.rooms().keys().collect();
+    rooms.sort();
+    assert_eq!(rooms, vec![START, "Kitchen", "Lab", CHECKPOINT]);
+}
+
+#[test]
+fn the_floor_is_solved_with_pruning() {
+    let ship = FakeShip::new();
+    let weighed = ship.weighed.clone();
+    let mut adventure = Adventure::with_droid(ship);
+    let solution = adventure.solve().unwrap();
+
+    let mut items = solution.items.clone();
+    items.sort();
+    assert_eq!(items, vec!["coin", "ring"]);
+    assert_eq!(solution.password, Some("2424".to_string()));
+
+    // Exploring walks onto the floor once empty handed, then each combination is weighed at most
+    // once and never after a subset was already too heavy or a superset too light
+    let weighed = weighed.borrow();
+    let combinations = &weighed[1..];
+    assert!(combinations.len() < 16, "{} combinations weighed", combinations.len());
+    let weight = |items: &[&str]| items.iter().map(|item| FakeShip::weight(item)).sum::<u32>();
+    for (i, later) in combinations.iter().enumerate() {
+        for earlier in &combinations[..i] {
+            let subset = earlier.iter().all(|item| later.contains(item));
+            let superset = later.iter().all(|item| earlier.contains(item));
+            assert!(!(subset && weight(earlier) > TARGET), "{:?} weighed after {:?}", later, earlier);
+            assert!(!(superset && weight(earlier) < TARGET), "{:?} weighed after {:?}", later, earlier);
+        }
+    }
+}
+
+#[test]
+fn unreachable_weights_are_errors() {
+    // Without the ring and the coin nothing adds up to the target
+    let mut ship = FakeShip::new();
+    ship.items.insert(CHECKPOINT, vec![]);
+    let mut adventure = Adventure::with_droid(ship);
+    adventure.avoid("coin");
+    let err = adventure.solve().unwrap_err();
+    assert!(matches!(err, AdventureError::NoCombination), "{}", err);
+}
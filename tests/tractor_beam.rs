@@ -0,0 +1,67 @@
+//! Probing a hand-written tractor beam.
+
+use num_bigint::BigInt;
+
+#[path = "../src/int_code_big.rs"]
+pub mod int_code_big;
+#[path = "../src/tractor_beam.rs"]
+pub mod tractor_beam;
+
+use tractor_beam::{Beam, BeamError};
+
+/// Pulls the points with y / 2 <= x <= 2y.
+const WEDGE: &str = "3,100,3,101,1002,101,2,102,7,102,100,103,1002,100,2,102,7,102,101,104,\
+1,103,104,102,1008,102,0,102,4,102,99";
+
+/// Pulls the points with 2y <= x <= 3y, so the beam starts well right of the diagonal.
+const STEEP_WEDGE: &str = "3,100,3,101,1002,101,2,102,7,100,102,103,1002,101,3,102,7,102,100,104,\
+1,103,104,102,1008,102,0,102,4,102,99";
+
+fn beam(program: &str) -> Beam {
+    Beam::new(int_code_big::Computer::from_memory(int_code_big::parse_memory(program).unwrap()))
+}
+
+#[test]
+fn drones_report_whether_points_are_pulled() {
+    let mut beam = beam(WEDGE);
+    assert!(beam.is_pulled(0, 0).unwrap());
+    assert!(beam.is_pulled(5, 10).unwrap());
+    assert!(beam.is_pulled(20, 10).unwrap());
+    assert!(!beam.is_pulled(4, 10).unwrap());
+    assert!(!beam.is_pulled(21, 10).unwrap());
+    assert_eq!(beam.queries(), 5);
+}
+
+#[test]
+fn pulled_points_are_counted() {
+    let mut beam = beam(WEDGE);
+    assert_eq!(beam.count(50, 50).unwrap(), 1250);
+    assert_eq!(beam.queries(), 2500);
+    assert_eq!(beam.count(3, 3).unwrap(), 5);
+}
+
+#[test]
+fn squares_fit_where_the_edges_allow() {
+    assert_eq!(beam(WEDGE).fit_square(100, 1000).unwrap(), Some((99, 99)));
+    assert_eq!(beam(WEDGE).fit_square(2, 100).unwrap(), Some((1, 1)));
+    assert_eq!(beam(WEDGE).fit_square(100, 150).unwrap(), None);
+}
+
+#[test]
+fn squares_fit_in_a_beam_right_of_the_diagonal() {
+    let mut beam = beam(STEEP_WEDGE);
+    assert!(beam.is_pulled(200, 99).unwrap());
+    assert!(!beam.is_pulled(199, 100).unwrap());
+    assert_eq!(beam.fit_square(100, 1000).unwrap(), Some((792, 297)));
+    assert_eq!(beam.fit_square(2, 100).unwrap(), Some((8, 3)));
+}
+
+#[test]
+fn misbehaving_drones_are_errors() {
+    assert!(matches!(beam("99").is_pulled(0, 0), Err(BeamError::NoReport)));
+    assert!(matches!(beam("3,0,3,0,3,0,99").is_pulled(0, 0), Err(BeamError::NoReport)));
+    match beam("104,2,99").is_pulled(0, 0) {
+        Err(BeamError::InvalidReport { value }) => assert_eq!(value, BigInt::from(2)),
+        other => panic!("expected InvalidReport but got {:?}", other),
+    }
+}